@@ -0,0 +1,217 @@
+#!/usr/bin/env python3
+"""Write the sound fixtures used by the sound-fx-3000 tests.
+
+The clips are encoded by hand so no encoder is needed to regenerate them:
+
+- sine.flac: 4096 stereo frames at 48 kHz, a 375 Hz sine with an amplitude of
+  0.5 on the left and 0.25 on the right channel, stored as verbatim 16 bit
+  samples
+- silence.ogg: 1280 stereo frames at 24 kHz of vorbis encoded silence, all
+  packets mark the floor of both channels as unused, the lower sample rate
+  lets tests resample it
+"""
+
+import hashlib
+import math
+import struct
+from pathlib import Path
+
+RATE = 48000
+OGG_RATE = 24000
+FLAC_FRAMES = 4096
+OGG_PACKETS = 11  # the first packet only primes the overlap, each following adds 128 frames
+
+
+class BitWriter:
+    def __init__(self, lsb_first):
+        self.lsb_first = lsb_first
+        self.bits = []
+
+    def write(self, value, count):
+        bits = [(value >> i) & 1 for i in range(count)]
+        self.bits += bits if self.lsb_first else bits[::-1]
+
+    def bytes(self):
+        bits = self.bits + [0] * (-len(self.bits) % 8)
+        out = bytearray()
+        for i in range(0, len(bits), 8):
+            byte = bits[i : i + 8]
+            if not self.lsb_first:
+                byte = byte[::-1]
+            out.append(sum(bit << n for n, bit in enumerate(byte)))
+        return bytes(out)
+
+
+def crc(data, width, poly):
+    top = 1 << (width - 1)
+    mask = (1 << width) - 1
+    value = 0
+    for byte in data:
+        value ^= byte << (width - 8)
+        for _ in range(8):
+            value = ((value << 1) ^ poly) if value & top else (value << 1)
+            value &= mask
+    return value
+
+
+def sine_samples():
+    samples = []
+    for i in range(FLAC_FRAMES):
+        x = math.sin(2 * math.pi * 375 * i / RATE)
+        samples.append((round(0.5 * x * 32767), round(0.25 * x * 32767)))
+    return samples
+
+
+def flac():
+    samples = sine_samples()
+    md5 = hashlib.md5(b"".join(struct.pack("<hh", *frame) for frame in samples)).digest()
+
+    info = BitWriter(lsb_first=False)
+    info.write(FLAC_FRAMES, 16)  # min block size
+    info.write(FLAC_FRAMES, 16)  # max block size
+    info.write(0, 24)  # min frame size, unknown
+    info.write(0, 24)  # max frame size, unknown
+    info.write(RATE, 20)
+    info.write(2 - 1, 3)  # channels
+    info.write(16 - 1, 5)  # bits per sample
+    info.write(FLAC_FRAMES, 36)
+    streaminfo = info.bytes() + md5
+
+    header = BitWriter(lsb_first=False)
+    header.write(0b11111111111110, 14)  # sync code
+    header.write(0, 1)  # reserved
+    header.write(0, 1)  # fixed block size
+    header.write(0b0111, 4)  # 16 bit block size at the end of the header
+    header.write(0b1010, 4)  # 48 kHz
+    header.write(0b0001, 4)  # independent left and right channel
+    header.write(0b100, 3)  # 16 bits per sample
+    header.write(0, 1)  # reserved
+    header.write(0, 8)  # frame number
+    header.write(FLAC_FRAMES - 1, 16)
+    frame = header.bytes()
+    frame += bytes([crc(frame, 8, 0x07)])
+
+    subframes = BitWriter(lsb_first=False)
+    for channel in range(2):
+        subframes.write(0, 1)  # padding
+        subframes.write(0b000001, 6)  # verbatim
+        subframes.write(0, 1)  # no wasted bits
+        for sample in samples:
+            subframes.write(sample[channel] & 0xFFFF, 16)
+    frame += subframes.bytes()
+    frame += struct.pack(">H", crc(frame, 16, 0x8005))
+
+    block_header = struct.pack(">I", 0x80 << 24 | len(streaminfo))  # last block, streaminfo
+    return b"fLaC" + block_header + streaminfo + frame
+
+
+def vorbis_identification():
+    bits = BitWriter(lsb_first=True)
+    bits.write(0, 32)  # version
+    bits.write(2, 8)  # channels
+    bits.write(OGG_RATE, 32)
+    bits.write(0, 32)  # max bitrate
+    bits.write(0, 32)  # nominal bitrate
+    bits.write(0, 32)  # min bitrate
+    bits.write(8, 4)  # short block size 256
+    bits.write(11, 4)  # long block size 2048
+    bits.write(1, 1)  # framing
+    return b"\x01vorbis" + bits.bytes()
+
+
+def vorbis_comment():
+    vendor = b"sound-fx-3000 fixtures"
+    bits = BitWriter(lsb_first=True)
+    bits.write(len(vendor), 32)
+    for byte in vendor:
+        bits.write(byte, 8)
+    bits.write(0, 32)  # no comments
+    bits.write(1, 1)  # framing
+    return b"\x03vorbis" + bits.bytes()
+
+
+def vorbis_setup():
+    bits = BitWriter(lsb_first=True)
+
+    bits.write(1 - 1, 8)  # codebooks
+    bits.write(0x564342, 24)  # sync
+    bits.write(1, 16)  # dimensions
+    bits.write(2, 24)  # entries
+    bits.write(0, 1)  # not ordered
+    bits.write(0, 1)  # not sparse
+    bits.write(1 - 1, 5)  # length of the first entry
+    bits.write(1 - 1, 5)  # length of the second entry
+    bits.write(0, 4)  # no lookup
+
+    bits.write(1 - 1, 6)  # time domain transforms
+    bits.write(0, 16)
+
+    bits.write(1 - 1, 6)  # floors
+    bits.write(1, 16)  # floor type 1
+    bits.write(0, 5)  # no partitions
+    bits.write(1 - 1, 2)  # multiplier
+    bits.write(8, 4)  # range bits
+
+    bits.write(1 - 1, 6)  # residues
+    bits.write(0, 16)  # residue type 0
+    bits.write(0, 24)  # begin
+    bits.write(0, 24)  # end
+    bits.write(1 - 1, 24)  # partition size
+    bits.write(1 - 1, 6)  # classifications
+    bits.write(0, 8)  # classbook
+    bits.write(0, 3)  # no books used by the classification
+    bits.write(0, 1)
+
+    bits.write(1 - 1, 6)  # mappings
+    bits.write(0, 16)  # mapping type 0
+    bits.write(0, 1)  # one submap
+    bits.write(0, 1)  # no coupling
+    bits.write(0, 2)  # reserved
+    bits.write(0, 8)  # unused time config
+    bits.write(0, 8)  # floor
+    bits.write(0, 8)  # residue
+
+    bits.write(1 - 1, 6)  # modes
+    bits.write(0, 1)  # short block
+    bits.write(0, 16)  # window type
+    bits.write(0, 16)  # transform type
+    bits.write(0, 8)  # mapping
+
+    bits.write(1, 1)  # framing
+    return b"\x05vorbis" + bits.bytes()
+
+
+def vorbis_audio():
+    bits = BitWriter(lsb_first=True)
+    bits.write(0, 1)  # audio packet
+    bits.write(0, 1)  # left floor unused
+    bits.write(0, 1)  # right floor unused
+    return bits.bytes()
+
+
+def ogg_page(packets, sequence, granule, flags):
+    lacing = bytearray()
+    for packet in packets:
+        lacing += b"\xff" * (len(packet) // 255) + bytes([len(packet) % 255])
+    header = b"OggS" + struct.pack(
+        "<BBqIIIB", 0, flags, granule, 0x5F583330, sequence, 0, len(lacing)
+    )
+    page = bytearray(header + lacing + b"".join(packets))
+    page[22:26] = struct.pack("<I", crc(page, 32, 0x04C11DB7))
+    return bytes(page)
+
+
+def ogg():
+    audio = [vorbis_audio()] * OGG_PACKETS
+    granule = (OGG_PACKETS - 1) * 128
+    return (
+        ogg_page([vorbis_identification()], 0, 0, 0x02)
+        + ogg_page([vorbis_comment(), vorbis_setup()], 1, 0, 0)
+        + ogg_page(audio, 2, granule, 0x04)
+    )
+
+
+if __name__ == "__main__":
+    dir = Path(__file__).parent
+    (dir / "sine.flac").write_bytes(flac())
+    (dir / "silence.ogg").write_bytes(ogg())
@@ -1,5 +1,5 @@
 mod output;
 mod sound;
 
-pub use output::Output;
+pub use output::{Output, ThreadOptions};
 pub use sound::Sound;
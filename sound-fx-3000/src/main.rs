@@ -2,7 +2,7 @@ use std::{io, path::PathBuf};
 
 use anyhow::{Context, Result};
 use clap::Parser;
-use sound_fx_3000::{Output, Sound};
+use sound_fx_3000::{Output, Sound, ThreadOptions};
 
 #[derive(Debug, Parser)]
 #[clap(version)]
@@ -16,6 +16,10 @@ struct Args {
     /// Output volume
     volume: Option<f32>,
 
+    #[clap(long)]
+    /// Request a high scheduling priority for the output thread
+    high_priority: bool,
+
     /// Path to an audio file
     path: PathBuf,
 }
@@ -32,7 +36,17 @@ fn main() -> Result<()> {
 
     eprintln!("start");
 
-    let output = Output::spawn(sound.spec().rate, args.device.as_deref())?;
+    let output = Output::spawn_with_options(
+        sound.spec().rate,
+        args.device.as_deref(),
+        ThreadOptions {
+            high_priority: args.high_priority,
+            ..Default::default()
+        },
+    )?;
+    if let Some(err) = output.priority_error() {
+        eprintln!("{err:#}, using the default priority");
+    }
 
     for line in io::stdin().lines() {
         let _line = line.context("read line")?;
@@ -1,6 +1,9 @@
 use std::{
     any::Any,
-    sync::{mpsc, Arc},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
     thread::JoinHandle,
     time::{Duration, Instant},
 };
@@ -13,6 +16,7 @@ use libpulse_binding::{
 };
 use libpulse_simple_binding::Simple;
 use symphonia::core::audio::Channels;
+use thread_priority::ThreadPriority;
 use zerocopy::IntoBytes;
 
 use crate::Sound;
@@ -28,29 +32,77 @@ pub struct Output {
     sample_rate: u32,
     tx: mpsc::Sender<Frames>,
     handle: JoinHandle<()>,
+    muted: Arc<AtomicBool>,
+    priority_error: Option<anyhow::Error>,
+}
+
+/// Options for the spawned output thread
+#[derive(Debug, Clone)]
+pub struct ThreadOptions {
+    /// Name of the output thread
+    pub name: String,
+
+    /// Request an elevated scheduling priority for the output thread
+    ///
+    /// If the priority can not be changed (e.g. missing permissions), the thread keeps running
+    /// with the default priority.
+    pub high_priority: bool,
+}
+
+impl Default for ThreadOptions {
+    fn default() -> Self {
+        Self {
+            name: "audio output".into(),
+            high_priority: false,
+        }
+    }
 }
 
 impl Output {
     /// Spawn the output thread and return the output handle
     pub fn spawn(sample_rate: u32, device: Option<&str>) -> Result<Self> {
+        Self::spawn_with_options(sample_rate, device, ThreadOptions::default())
+    }
+
+    /// Spawn the output thread with custom thread options and return the output handle
+    pub fn spawn_with_options(
+        sample_rate: u32,
+        device: Option<&str>,
+        options: ThreadOptions,
+    ) -> Result<Self> {
         let output = PaOutput::open(sample_rate, device)?;
+        Self::spawn_sink(sample_rate, output, options)
+    }
 
+    fn spawn_sink(
+        sample_rate: u32,
+        output: impl Sink + Send + 'static,
+        options: ThreadOptions,
+    ) -> Result<Self> {
         let (tx, rx) = mpsc::channel();
+        let muted = Arc::new(AtomicBool::new(false));
 
-        let handle = std::thread::Builder::new()
-            .name("audio output".into())
-            .spawn(move || {
-                run(sample_rate, output, rx);
-            })
-            .context("spawn audio output thread")?;
+        let thread_muted = muted.clone();
+        let (handle, priority_error) = spawn_thread(options, move || {
+            run(sample_rate, output, rx, &thread_muted);
+        })?;
 
         Ok(Self {
             sample_rate,
             tx,
             handle,
+            muted,
+            priority_error,
         })
     }
 
+    /// Why the elevated priority requested with [`ThreadOptions::high_priority`] was not set
+    ///
+    /// The output thread keeps running with the default priority.
+    pub fn priority_error(&self) -> Option<&anyhow::Error> {
+        self.priority_error.as_ref()
+    }
+
     /// Play a sound by submitting it to the worker thread
     pub fn play(&self, sound: &Sound) -> Result<()> {
         anyhow::ensure!(
@@ -63,10 +115,42 @@ impl Output {
         Ok(())
     }
 
+    /// Mute or unmute the output
+    ///
+    /// Sounds of a muted output keep playing silently, such that they do not resume late when
+    /// the output is unmuted.
+    pub fn set_muted(&self, muted: bool) {
+        self.muted.store(muted, Ordering::Relaxed);
+    }
+
+    pub fn is_muted(&self) -> bool {
+        self.muted.load(Ordering::Relaxed)
+    }
+
     /// Stop the worker thread after all remaining sound is played
     pub fn shutdown(self) -> Result<()> {
         drop(self.tx);
-        match self.handle.join() {
+        Self::join(self.handle)
+    }
+
+    /// Stop the worker thread after all remaining sound is played, but wait at most `timeout`
+    ///
+    /// If the timeout expires, the worker thread is detached and keeps playing in the background.
+    pub fn shutdown_timeout(self, timeout: Duration) -> Result<()> {
+        drop(self.tx);
+        let deadline = Instant::now() + timeout;
+        while !self.handle.is_finished() {
+            anyhow::ensure!(
+                Instant::now() < deadline,
+                "audio output thread did not stop within {timeout:?}",
+            );
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        Self::join(self.handle)
+    }
+
+    fn join(handle: JoinHandle<()>) -> Result<()> {
+        match handle.join() {
             Ok(()) => Ok(()),
             Err(err) => {
                 anyhow::bail!("audio output thread panicked: {}", payload_as_str(&err));
@@ -75,7 +159,30 @@ impl Output {
     }
 }
 
-fn run(sample_rate: u32, mut output: PaOutput, rx: mpsc::Receiver<Frames>) {
+/// Spawn the output thread, returns why the elevated priority could not be set
+fn spawn_thread(
+    options: ThreadOptions,
+    f: impl FnOnce() + Send + 'static,
+) -> Result<(JoinHandle<()>, Option<anyhow::Error>)> {
+    let (priority_tx, priority_rx) = mpsc::sync_channel(1);
+    let handle = std::thread::Builder::new()
+        .name(options.name)
+        .spawn(move || {
+            let priority = if options.high_priority {
+                thread_priority::set_current_thread_priority(ThreadPriority::Max)
+                    .map_err(|err| anyhow::anyhow!("set audio output thread priority: {err:?}"))
+            } else {
+                Ok(())
+            };
+            let _ = priority_tx.send(priority.err());
+            f();
+        })
+        .context("spawn audio output thread")?;
+    let priority_error = priority_rx.recv().context("audio output thread stopped")?;
+    Ok((handle, priority_error))
+}
+
+fn run(sample_rate: u32, mut output: impl Sink, rx: mpsc::Receiver<Frames>, muted: &AtomicBool) {
     let mut playing = Vec::new();
     let mut start = Instant::now();
     loop {
@@ -87,17 +194,7 @@ fn run(sample_rate: u32, mut output: PaOutput, rx: mpsc::Receiver<Frames>) {
             playing.push((sound, 0));
         }
 
-        let mut chunk = [[0.0; 2]; CHUNK_SIZE];
-        for (sound, index) in &mut playing {
-            let sound_chunk = &sound[*index..];
-            let sound_chunk = sound_chunk.get(..chunk.len()).unwrap_or(sound_chunk);
-            for (c, s) in std::iter::zip(&mut chunk, sound_chunk) {
-                c[0] += s[0];
-                c[1] += s[1];
-            }
-            *index += chunk.len();
-        }
-        playing.retain(|(sound, index)| *index < sound.len());
+        let chunk = mix(&mut playing, muted.load(Ordering::Relaxed));
 
         output.write(&chunk).unwrap();
         start += Duration::from_secs(chunk.len() as u64) / sample_rate;
@@ -107,6 +204,30 @@ fn run(sample_rate: u32, mut output: PaOutput, rx: mpsc::Receiver<Frames>) {
     }
 }
 
+/// Mix the next chunk of the playing sounds, the sounds advance even if the output is muted
+fn mix(playing: &mut Vec<(Frames, usize)>, muted: bool) -> [[f32; 2]; CHUNK_SIZE] {
+    let mut chunk = [[0.0; 2]; CHUNK_SIZE];
+    for (sound, index) in &mut *playing {
+        let sound_chunk = &sound[*index..];
+        let sound_chunk = sound_chunk.get(..chunk.len()).unwrap_or(sound_chunk);
+        for (c, s) in std::iter::zip(&mut chunk, sound_chunk) {
+            c[0] += s[0];
+            c[1] += s[1];
+        }
+        *index += chunk.len();
+    }
+    playing.retain(|(sound, index)| *index < sound.len());
+    if muted {
+        chunk = [[0.0; 2]; CHUNK_SIZE];
+    }
+    chunk
+}
+
+/// Destination of the mixed chunks
+trait Sink {
+    fn write(&mut self, data: &[[f32; 2]]) -> Result<()>;
+}
+
 struct PaOutput {
     pa: Simple,
 }
@@ -138,7 +259,9 @@ impl PaOutput {
 
         Ok(Self { pa })
     }
+}
 
+impl Sink for PaOutput {
     fn write(&mut self, data: &[[f32; 2]]) -> Result<()> {
         if data.is_empty() {
             return Ok(());
@@ -196,3 +319,125 @@ fn payload_as_str(payload: &dyn Any) -> &str {
         "Box<dyn Any>"
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    /// Sink that keeps all written frames
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<[f32; 2]>>>);
+
+    impl Sink for Recorder {
+        fn write(&mut self, data: &[[f32; 2]]) -> Result<()> {
+            self.0.lock().unwrap().extend_from_slice(data);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn shutdown_plays_the_queued_sounds() {
+        let recorder = Recorder::default();
+        let output = Output::spawn_sink(48000, recorder.clone(), ThreadOptions::default()).unwrap();
+        let sound = Sound::test(vec![[0.5, -0.25]; CHUNK_SIZE * 3], 48000);
+        output.play(&sound).unwrap();
+        output.play(&sound).unwrap();
+        output.shutdown().unwrap();
+
+        // the sounds may overlap, but both are played completely
+        let written = recorder.0.lock().unwrap();
+        assert_eq!(written.len() % CHUNK_SIZE, 0);
+        let left: f32 = written.iter().map(|frame| frame[0]).sum();
+        let right: f32 = written.iter().map(|frame| frame[1]).sum();
+        assert_eq!(left, 2.0 * 0.5 * (CHUNK_SIZE * 3) as f32);
+        assert_eq!(right, 2.0 * -0.25 * (CHUNK_SIZE * 3) as f32);
+    }
+
+    #[test]
+    fn shutdown_timeout_expires() {
+        let output =
+            Output::spawn_sink(48000, Recorder::default(), ThreadOptions::default()).unwrap();
+        // one second of silence
+        output
+            .play(&Sound::test(vec![[0.0; 2]; 48000], 48000))
+            .unwrap();
+        let err = output
+            .shutdown_timeout(Duration::from_millis(10))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "audio output thread did not stop within 10ms",
+        );
+    }
+
+    #[test]
+    fn spawn_thread_with_name() {
+        let options = ThreadOptions {
+            name: "test output".into(),
+            high_priority: false,
+        };
+        let (tx, rx) = mpsc::channel();
+        let (handle, priority_error) = spawn_thread(options, move || {
+            tx.send(std::thread::current().name().map(String::from))
+                .unwrap();
+        })
+        .unwrap();
+        assert_eq!(handle.thread().name(), Some("test output"));
+        assert_eq!(rx.recv().unwrap().as_deref(), Some("test output"));
+        // the default priority is not changed, so it can not fail
+        assert!(priority_error.is_none());
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn spawn_thread_with_high_priority() {
+        let options = ThreadOptions {
+            high_priority: true,
+            ..Default::default()
+        };
+        let (tx, rx) = mpsc::channel();
+        let (handle, priority_error) = spawn_thread(options, move || {
+            tx.send(std::thread::current().name().map(String::from))
+                .unwrap();
+        })
+        .unwrap();
+        assert_eq!(handle.thread().name(), Some("audio output"));
+        assert_eq!(rx.recv().unwrap().as_deref(), Some("audio output"));
+        // without permission to elevate the priority the thread still runs
+        // and the error is returned to the caller instead of failing the spawn
+        if let Some(err) = priority_error {
+            assert!(
+                err.to_string()
+                    .starts_with("set audio output thread priority: "),
+                "{err:#}",
+            );
+        }
+        handle.join().unwrap();
+    }
+
+    fn playing() -> Vec<(Frames, usize)> {
+        vec![(vec![[0.5, -0.5]; CHUNK_SIZE * 2].into(), 0)]
+    }
+
+    #[test]
+    fn muted_output_is_silent() {
+        let mut playing = playing();
+        let chunk = mix(&mut playing, true);
+        assert!(chunk.iter().all(|frame| *frame == [0.0, 0.0]));
+        // the sound keeps playing silently
+        assert_eq!(playing[0].1, CHUNK_SIZE);
+    }
+
+    #[test]
+    fn unmuted_output_is_not_silent() {
+        let mut playing = playing();
+        let chunk = mix(&mut playing, false);
+        assert!(chunk.iter().all(|frame| *frame == [0.5, -0.5]));
+        assert_eq!(playing[0].1, CHUNK_SIZE);
+
+        mix(&mut playing, false);
+        assert!(playing.is_empty());
+    }
+}
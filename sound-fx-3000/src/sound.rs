@@ -2,8 +2,8 @@ use std::{fs::File, io, path::Path, sync::Arc};
 
 use anyhow::{Context, Result};
 use symphonia::core::{
-    audio::{AudioBufferRef, Signal, SignalSpec},
-    codecs::DecoderOptions,
+    audio::{AudioBufferRef, SampleBuffer, SignalSpec},
+    codecs::{self, CodecType, DecoderOptions},
     errors::Error,
     formats::FormatOptions,
     io::MediaSourceStream,
@@ -40,7 +40,12 @@ impl Sound {
             metadata: _,
         } = symphonia::default::get_probe()
             .format(&hint, source, &format_options, &Default::default())
-            .context("probe audio file")?;
+            .with_context(|| {
+                format!(
+                    "unsupported audio format: {:?}",
+                    path.extension().unwrap_or_default(),
+                )
+            })?;
 
         // eprintln!("{:#?}", metadata.get());
         // eprintln!("{:#?}", format.metadata().current());
@@ -57,7 +62,12 @@ impl Sound {
 
         let mut decoder = symphonia::default::get_codecs()
             .make(&track.codec_params, &decoder_options)
-            .context("init codec")?;
+            .with_context(|| {
+                format!(
+                    "unsupported audio codec: {}",
+                    codec_name(track.codec_params.codec),
+                )
+            })?;
 
         anyhow::ensure!(
             track.codec_params.start_ts == 0,
@@ -115,6 +125,68 @@ impl Sound {
         }
     }
 
+    /// Root mean square of all samples, a rough measure of the loudness
+    pub fn rms(&self) -> f32 {
+        if self.frames.is_empty() {
+            return 0.0;
+        }
+        let sum: f64 = self
+            .frames
+            .iter()
+            .flatten()
+            .map(|sample| f64::from(*sample).powi(2))
+            .sum();
+        (sum / (self.frames.len() * 2) as f64).sqrt() as f32
+    }
+
+    /// Largest absolute sample value
+    pub fn peak(&self) -> f32 {
+        self.frames
+            .iter()
+            .flatten()
+            .fold(0.0, |peak, sample| sample.abs().max(peak))
+    }
+
+    /// Scale the sound to the target loudness (RMS in dBFS)
+    ///
+    /// The gain is limited such that the peak does not clip.
+    pub fn normalize(&mut self, target_dbfs: f32) {
+        let rms = self.rms();
+        if rms == 0.0 {
+            return;
+        }
+        let mut gain = 10f32.powf(target_dbfs / 20.0) / rms;
+        let peak = self.peak();
+        if peak * gain > 1.0 {
+            gain = 1.0 / peak;
+        }
+        self.set_volume(gain);
+    }
+
+    /// Convert the sound to another sample rate using linear interpolation
+    ///
+    /// This is lossy, sounds exported with the target sample rate sound better.
+    pub fn resample(&mut self, rate: u32) {
+        if self.spec.rate == rate || self.frames.is_empty() {
+            self.spec.rate = rate;
+            return;
+        }
+
+        let step = f64::from(self.spec.rate) / f64::from(rate);
+        let len = (self.frames.len() as u64 * u64::from(rate) / u64::from(self.spec.rate)) as usize;
+        self.frames = (0..len)
+            .map(|i| {
+                let pos = i as f64 * step;
+                let index = pos as usize;
+                let t = (pos - index as f64) as f32;
+                let a = self.frames[index];
+                let b = self.frames.get(index + 1).copied().unwrap_or(a);
+                [a[0] + (b[0] - a[0]) * t, a[1] + (b[1] - a[1]) * t]
+            })
+            .collect();
+        self.spec.rate = rate;
+    }
+
     /// Return the first signal spec of the decoded sound packets
     pub fn spec(&self) -> SignalSpec {
         self.spec
@@ -137,29 +209,139 @@ impl Buffer {
             return Ok(());
         }
 
-        let decoded = match decoded {
-            AudioBufferRef::U8(_) => todo!("handle U8 audio buffer"),
-            AudioBufferRef::U16(_) => todo!("handle U16 audio buffer"),
-            AudioBufferRef::U24(_) => todo!("handle U24 audio buffer"),
-            AudioBufferRef::U32(_) => todo!("handle U32 audio buffer"),
-            AudioBufferRef::S8(_) => todo!("handle S8 audio buffer"),
-            AudioBufferRef::S16(_) => todo!("handle S16 audio buffer"),
-            AudioBufferRef::S24(_) => todo!("handle S24 audio buffer"),
-            AudioBufferRef::S32(_) => todo!("handle S32 audio buffer"),
-            AudioBufferRef::F32(decoded) => decoded,
-            AudioBufferRef::F64(_) => todo!("handle F64 audio buffer"),
-        };
-
         anyhow::ensure!(
             decoded.spec().channels.count() == 2,
             "expected stereo sound, found {} channels",
             decoded.spec().channels.count(),
         );
 
-        for (l, r) in std::iter::zip(decoded.chan(0), decoded.chan(1)) {
-            self.buffer.push([*l, *r]);
+        // convert integer samples (e.g. flac) to f32
+        let mut samples = SampleBuffer::<f32>::new(decoded.capacity() as u64, *decoded.spec());
+        samples.copy_interleaved_ref(decoded);
+        for frame in samples.samples().chunks_exact(2) {
+            self.buffer.push([frame[0], frame[1]]);
         }
 
         Ok(())
     }
 }
+
+fn codec_name(codec: CodecType) -> String {
+    match codec {
+        codecs::CODEC_TYPE_MP3 => "mp3".into(),
+        codecs::CODEC_TYPE_FLAC => "flac".into(),
+        codecs::CODEC_TYPE_VORBIS => "vorbis".into(),
+        codecs::CODEC_TYPE_OPUS => "opus".into(),
+        codecs::CODEC_TYPE_AAC => "aac".into(),
+        codecs::CODEC_TYPE_NULL => "unknown".into(),
+        codec => format!("{codec:?}"),
+    }
+}
+
+#[cfg(test)]
+impl Sound {
+    /// A stereo sound of the given frames
+    pub(crate) fn test(frames: Vec<[f32; 2]>, rate: u32) -> Self {
+        use symphonia::core::audio::Channels;
+
+        Self {
+            frames: frames.into(),
+            spec: SignalSpec::new(rate, Channels::FRONT_LEFT | Channels::FRONT_RIGHT),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures")
+            .join(name)
+    }
+
+    #[test]
+    #[cfg(feature = "flac")]
+    fn decode_flac() {
+        use std::f32::consts::TAU;
+
+        let sound = Sound::open(&fixture("sine.flac")).unwrap();
+        assert_eq!(sound.spec().rate, 48000);
+        assert_eq!(sound.spec().channels.count(), 2);
+
+        let frames = sound.frames();
+        assert_eq!(frames.len(), 4096);
+        for (i, [left, right]) in frames.iter().enumerate() {
+            let x = (TAU * 375.0 * i as f32 / 48000.0).sin();
+            assert!((left - 0.5 * x).abs() < 1e-3, "left frame {i}: {left}");
+            assert!((right - 0.25 * x).abs() < 1e-3, "right frame {i}: {right}");
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "ogg")]
+    fn decode_ogg_vorbis() {
+        let sound = Sound::open(&fixture("silence.ogg")).unwrap();
+        assert_eq!(sound.spec().rate, 24000);
+        assert_eq!(sound.spec().channels.count(), 2);
+
+        let frames = sound.frames();
+        assert_eq!(frames.len(), 1280);
+        assert!(frames.iter().all(|frame| *frame == [0.0, 0.0]));
+    }
+
+    #[test]
+    #[cfg(feature = "flac")]
+    fn normalize_to_target_rms() {
+        let mut loud = Sound::open(&fixture("sine.flac")).unwrap();
+        let mut quiet = loud.clone();
+        quiet.set_volume(0.05);
+        assert!(loud.rms() > 10.0 * quiet.rms());
+
+        let target = 10f32.powf(-20.0 / 20.0);
+        for sound in [&mut loud, &mut quiet] {
+            sound.normalize(-20.0);
+            assert!((sound.rms() - target).abs() < 1e-3, "rms {}", sound.rms());
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "flac")]
+    fn normalize_does_not_clip() {
+        let mut sound = Sound::open(&fixture("sine.flac")).unwrap();
+        sound.normalize(0.0);
+        assert!((sound.peak() - 1.0).abs() < 1e-6, "peak {}", sound.peak());
+        assert!(sound.rms() < 1.0);
+    }
+
+    #[test]
+    fn resample_interpolates_between_frames() {
+        let mut sound = Sound::test(vec![[0.0, 1.0], [1.0, 0.0], [0.0, -1.0]], 24000);
+        sound.resample(48000);
+        assert_eq!(sound.spec().rate, 48000);
+        assert_eq!(
+            &sound.frames()[..],
+            [
+                [0.0, 1.0],
+                [0.5, 0.5],
+                [1.0, 0.0],
+                [0.5, -0.5],
+                [0.0, -1.0],
+                [0.0, -1.0],
+            ]
+        );
+
+        sound.resample(16000);
+        assert_eq!(sound.spec().rate, 16000);
+        assert_eq!(&sound.frames()[..], [[0.0, 1.0], [0.5, -0.5]]);
+    }
+
+    #[test]
+    fn unsupported_format_is_named() {
+        let err = Sound::open(&fixture("generate.py")).err().unwrap();
+        assert_eq!(err.to_string(), r#"unsupported audio format: "py""#);
+    }
+}
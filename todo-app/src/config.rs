@@ -1,11 +1,22 @@
-use std::{collections::HashMap, fs, io, path::Path};
+use std::{
+    borrow::Borrow,
+    collections::HashMap,
+    fmt::{self, Write},
+    fs, io,
+    marker::PhantomData,
+    path::Path,
+    time::{Duration, Instant},
+};
 
 use anyhow::{Context, Result};
 use crokey::KeyCombination;
 use directories::ProjectDirs;
-use serde::Deserialize;
+use serde::{
+    de::{IntoDeserializer, MapAccess, Visitor},
+    Deserialize, Deserializer,
+};
 
-use crate::model::{default_undo_steps, Command};
+use crate::model::{default_show_undo_status, default_undo_steps, Command};
 
 #[derive(Debug, Deserialize)]
 #[serde(deny_unknown_fields)]
@@ -13,8 +24,12 @@ pub struct Config {
     #[serde(default = "default_undo_steps")]
     pub undo_steps: usize,
 
+    /// Describe the change reverted by undo or redo below the list
+    #[serde(default = "default_show_undo_status")]
+    pub show_undo_status: bool,
+
     #[serde(default = "Keybindings::empty")]
-    pub keybindings: Keybindings,
+    pub keybindings: Keybindings<Binding>,
 }
 
 impl Config {
@@ -39,14 +54,55 @@ impl Config {
     }
 }
 
+const EXAMPLE: &str = r#"# Number of changes that can be undone
+undo_steps = 4096
+
+# Describe the change reverted by undo or redo, e.g. "undid delete of 'Buy milk'"
+show_undo_status = true
+
+# Keybindings extend the defaults below, bind a key to "unbind" to remove a default keybinding
+"#;
+
+impl Config {
+    /// Commented example config, the keybindings are the defaults
+    pub fn example() -> Result<String> {
+        let mut example = EXAMPLE.to_string();
+        let keybindings = Keybindings::default();
+        for (mode, keybindings) in [
+            ("normal", &keybindings.normal),
+            ("insert", &keybindings.insert),
+        ] {
+            let mut keybindings = keybindings
+                .iter()
+                .map(|(keys, command)| {
+                    Ok((
+                        toml::Value::String(keys.to_string()),
+                        toml::Value::try_from(command).context("serialize command")?,
+                    ))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            keybindings.sort_by_key(|(keys, _)| keys.to_string());
+
+            writeln!(example, "\n[keybindings.{mode}]").unwrap();
+            for (keys, command) in keybindings {
+                writeln!(example, "{keys} = {command}").unwrap();
+            }
+        }
+        Ok(example)
+    }
+}
+
 #[derive(Debug, Deserialize)]
-#[serde(deny_unknown_fields)]
-pub struct Keybindings {
-    #[serde(default)]
-    pub normal: HashMap<KeyCombination, Command>,
+#[serde(
+    deny_unknown_fields,
+    bound(deserialize = "B: Deserialize<'de> + fmt::Debug")
+)]
+pub struct Keybindings<B = Command> {
+    #[serde(default, deserialize_with = "unique_keys")]
+    pub normal: HashMap<KeySequence, B>,
 
-    #[serde(default)]
-    pub insert: HashMap<KeyCombination, Command>,
+    #[serde(default, deserialize_with = "unique_keys")]
+    pub insert: HashMap<KeySequence, B>,
 }
 
 impl Default for Keybindings {
@@ -58,16 +114,388 @@ impl Default for Keybindings {
     }
 }
 
-impl Keybindings {
+impl<B> Keybindings<B> {
     pub fn empty() -> Self {
         Self {
             normal: HashMap::new(),
             insert: HashMap::new(),
         }
     }
+}
+
+impl Keybindings {
+    /// Add the configured keybindings, replacing or removing the existing ones
+    pub fn extend(&mut self, other: Keybindings<Binding>) {
+        for (keybindings, other) in [
+            (&mut self.normal, other.normal),
+            (&mut self.insert, other.insert),
+        ] {
+            for (keys, binding) in other {
+                match binding {
+                    Binding::Command(command) => {
+                        keybindings.insert(keys, command);
+                    }
+                    Binding::Unbind => {
+                        keybindings.remove(&keys);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Warn about key sequences that can not be entered, because their start is bound as well
+    pub fn warn_conflicts(&self) {
+        for conflict in self.conflicts() {
+            eprintln!("warning: {conflict}");
+        }
+    }
+
+    /// Key sequences that can not be entered, because their start is bound as well
+    fn conflicts(&self) -> Vec<String> {
+        let mut all_conflicts = Vec::new();
+        for (mode, keybindings) in [("normal", &self.normal), ("insert", &self.insert)] {
+            let mut conflicts = Vec::new();
+            for (keys, command) in keybindings {
+                for (prefix, prefix_command) in keybindings {
+                    if prefix.0.len() < keys.0.len() && keys.0.starts_with(&prefix.0) {
+                        conflicts.push(format!(
+                            "{mode} keybinding `{keys}` ({command:?}) is unreachable, \
+                            `{prefix}` is bound to {prefix_command:?}",
+                        ));
+                    }
+                }
+            }
+            conflicts.sort();
+            all_conflicts.extend(conflicts);
+        }
+        all_conflicts
+    }
+}
+
+/// Configured binding of a key sequence, `"unbind"` removes a default keybinding
+#[derive(Debug, Clone, Copy)]
+pub enum Binding {
+    Command(Command),
+    Unbind,
+}
+
+impl<'de> Deserialize<'de> for Binding {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let name = String::deserialize(deserializer)?;
+        if name == "unbind" {
+            return Ok(Self::Unbind);
+        }
+        Command::deserialize(name.into_deserializer()).map(Self::Command)
+    }
+}
+
+/// Deserialize a keybinding map, a key sequence may only be bound once
+///
+/// Different spellings like `ctrl-c` and `Ctrl-C` are the same key sequence and would otherwise
+/// silently replace each other.
+fn unique_keys<'de, V, D>(deserializer: D) -> Result<HashMap<KeySequence, V>, D::Error>
+where
+    V: Deserialize<'de> + fmt::Debug,
+    D: Deserializer<'de>,
+{
+    struct UniqueKeysVisitor<V>(PhantomData<V>);
+
+    impl<'de, V> Visitor<'de> for UniqueKeysVisitor<V>
+    where
+        V: Deserialize<'de> + fmt::Debug,
+    {
+        type Value = HashMap<KeySequence, V>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a map of key sequences to commands")
+        }
+
+        fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+        where
+            A: MapAccess<'de>,
+        {
+            let mut keybindings = HashMap::new();
+            while let Some((keys, command)) = map.next_entry::<KeySequence, V>()? {
+                if let Some(previous) = keybindings.get(&keys) {
+                    return Err(serde::de::Error::custom(format!(
+                        "key `{keys}` is bound to multiple commands: {previous:?} and {command:?}",
+                    )));
+                }
+                keybindings.insert(keys, command);
+            }
+            Ok(keybindings)
+        }
+    }
+
+    deserializer.deserialize_map(UniqueKeysVisitor(PhantomData))
+}
+
+/// Key combinations pressed one after another, written as e.g. `"g g"`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeySequence(pub Vec<KeyCombination>);
+
+impl From<KeyCombination> for KeySequence {
+    fn from(key: KeyCombination) -> Self {
+        Self(vec![key])
+    }
+}
+
+impl Borrow<[KeyCombination]> for KeySequence {
+    fn borrow(&self) -> &[KeyCombination] {
+        &self.0
+    }
+}
+
+impl fmt::Display for KeySequence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, key) in self.0.iter().enumerate() {
+            if i != 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{key}")?;
+        }
+        Ok(())
+    }
+}
+
+impl<'de> Deserialize<'de> for KeySequence {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let sequence = String::deserialize(deserializer)?;
+        let keys = sequence
+            .split_whitespace()
+            .map(|key| key.parse().map_err(serde::de::Error::custom))
+            .collect::<Result<Vec<_>, _>>()?;
+        if keys.is_empty() {
+            return Err(serde::de::Error::custom("empty key sequence"));
+        }
+        Ok(Self(keys))
+    }
+}
+
+pub enum KeyLookup<C> {
+    Command(C),
+
+    /// The key is the start of a key sequence, wait for the next key
+    Pending,
+
+    Unbound,
+}
+
+/// Keys of a partially entered key sequence
+#[derive(Debug, Default)]
+pub struct PendingKeys {
+    keys: Vec<KeyCombination>,
+    last_key_at: Option<Instant>,
+}
+
+impl PendingKeys {
+    /// A partial key sequence is discarded if the next key is not pressed within this time
+    const TIMEOUT: Duration = Duration::from_secs(1);
+
+    /// Match the pressed key against the keybindings
+    ///
+    /// If a key sequence is also the start of a longer one, the shorter sequence wins.
+    pub fn lookup<C: Clone>(
+        &mut self,
+        keybindings: &HashMap<KeySequence, C>,
+        key: KeyCombination,
+    ) -> KeyLookup<C> {
+        if self
+            .last_key_at
+            .is_some_and(|last_key_at| last_key_at.elapsed() > Self::TIMEOUT)
+        {
+            self.keys.clear();
+        }
+        self.last_key_at = Some(Instant::now());
+        self.keys.push(key);
+
+        loop {
+            if let Some(command) = keybindings.get(self.keys.as_slice()) {
+                self.keys.clear();
+                return KeyLookup::Command(command.clone());
+            }
+            if keybindings
+                .keys()
+                .any(|sequence| sequence.0.starts_with(&self.keys))
+            {
+                return KeyLookup::Pending;
+            }
+            if self.keys.len() == 1 {
+                self.keys.clear();
+                return KeyLookup::Unbound;
+            }
+            // the partial sequence does not continue with this key, try the key on its own
+            self.keys.drain(..self.keys.len() - 1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sequence(keys: &str) -> KeySequence {
+        KeySequence(
+            keys.split_whitespace()
+                .map(|key| key.parse().unwrap())
+                .collect(),
+        )
+    }
+
+    fn key(key: &str) -> KeyCombination {
+        key.parse().unwrap()
+    }
+
+    #[test]
+    fn unbind_removes_a_default_keybinding() {
+        let config: Keybindings<Binding> =
+            toml::from_str("[normal]\nq = \"unbind\"\nctrl-q = \"quit\"\n").unwrap();
+        let mut keybindings = Keybindings::default();
+        assert!(matches!(
+            keybindings.normal.get(&sequence("q")),
+            Some(Command::Quit)
+        ));
+
+        keybindings.extend(config);
+        assert!(!keybindings.normal.contains_key(&sequence("q")));
+        assert!(matches!(
+            keybindings.normal.get(&sequence("ctrl-q")),
+            Some(Command::Quit)
+        ));
+        // other defaults are kept
+        assert!(keybindings.normal.contains_key(&sequence("j")));
+    }
+
+    #[test]
+    fn duplicate_keybinding_is_reported() {
+        let err = toml::from_str::<Keybindings<Binding>>(
+            "[normal]\nctrl-c = \"quit\"\nCtrl-c = \"go_top\"\n",
+        )
+        .unwrap_err();
+        assert!(
+            err.message().contains(
+                "key `Ctrl-c` is bound to multiple commands: Command(Quit) and Command(GoTop)"
+            ),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn unreachable_keybinding_is_reported() {
+        assert!(Keybindings::default().conflicts().is_empty());
+
+        let mut keybindings: Keybindings = Keybindings::empty();
+        keybindings.normal.insert(sequence("g"), Command::GoTop);
+        keybindings.normal.insert(sequence("g g"), Command::Quit);
+        assert_eq!(
+            keybindings.conflicts(),
+            ["normal keybinding `g g` (Quit) is unreachable, `g` is bound to GoTop",]
+        );
+    }
+
+    #[test]
+    fn example_config_parses() {
+        let config: Config = toml::from_str(&Config::example().unwrap()).unwrap();
+        let defaults = Keybindings::default();
+        assert_eq!(config.keybindings.normal.len(), defaults.normal.len());
+        assert_eq!(config.keybindings.insert.len(), defaults.insert.len());
+        for keys in defaults.normal.keys() {
+            assert!(
+                matches!(
+                    config.keybindings.normal.get(keys),
+                    Some(Binding::Command(_))
+                ),
+                "missing keybinding {keys}"
+            );
+        }
+    }
+
+    #[test]
+    fn key_sequence_lookup() {
+        let keybindings = HashMap::from([(sequence("g g"), 1), (sequence("d"), 2)]);
+        let mut pending = PendingKeys::default();
+        assert!(matches!(
+            pending.lookup(&keybindings, key("g")),
+            KeyLookup::Pending
+        ));
+        assert!(matches!(
+            pending.lookup(&keybindings, key("g")),
+            KeyLookup::Command(1)
+        ));
+        assert!(matches!(
+            pending.lookup(&keybindings, key("d")),
+            KeyLookup::Command(2)
+        ));
+        assert!(matches!(
+            pending.lookup(&keybindings, key("x")),
+            KeyLookup::Unbound
+        ));
+    }
+
+    #[test]
+    fn partial_sequence_falls_back_to_the_key() {
+        let keybindings = HashMap::from([(sequence("g g"), 1), (sequence("d"), 2)]);
+        let mut pending = PendingKeys::default();
+        assert!(matches!(
+            pending.lookup(&keybindings, key("g")),
+            KeyLookup::Pending
+        ));
+        assert!(matches!(
+            pending.lookup(&keybindings, key("d")),
+            KeyLookup::Command(2)
+        ));
+
+        assert!(matches!(
+            pending.lookup(&keybindings, key("g")),
+            KeyLookup::Pending
+        ));
+        assert!(matches!(
+            pending.lookup(&keybindings, key("x")),
+            KeyLookup::Unbound
+        ));
+        // the partial sequence was discarded
+        assert!(matches!(
+            pending.lookup(&keybindings, key("g")),
+            KeyLookup::Pending
+        ));
+    }
+
+    #[test]
+    fn shorter_sequence_wins() {
+        let keybindings = HashMap::from([(sequence("g"), 1), (sequence("g g"), 2)]);
+        let mut pending = PendingKeys::default();
+        assert!(matches!(
+            pending.lookup(&keybindings, key("g")),
+            KeyLookup::Command(1)
+        ));
+        assert!(matches!(
+            pending.lookup(&keybindings, key("g")),
+            KeyLookup::Command(1)
+        ));
+    }
 
-    pub fn extend(&mut self, other: Self) {
-        self.normal.extend(other.normal);
-        self.insert.extend(other.insert);
+    #[test]
+    fn partial_sequence_times_out() {
+        let keybindings = HashMap::from([(sequence("g g"), 1)]);
+        let mut pending = PendingKeys::default();
+        assert!(matches!(
+            pending.lookup(&keybindings, key("g")),
+            KeyLookup::Pending
+        ));
+        pending.last_key_at = Instant::now().checked_sub(PendingKeys::TIMEOUT * 2);
+        assert!(matches!(
+            pending.lookup(&keybindings, key("g")),
+            KeyLookup::Pending
+        ));
+        assert!(matches!(
+            pending.lookup(&keybindings, key("g")),
+            KeyLookup::Command(1)
+        ));
     }
 }
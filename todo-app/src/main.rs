@@ -1,4 +1,4 @@
-use std::{env, fs, io, path::PathBuf, time::Duration};
+use std::{env, fs, io, panic, path::PathBuf, time::Duration};
 
 use anyhow::{Context, Result};
 use config::Config;
@@ -12,6 +12,14 @@ mod model;
 mod todo;
 
 fn main() -> Result<()> {
+    if env::args_os()
+        .nth(1)
+        .is_some_and(|arg| arg == "--example-config")
+    {
+        print!("{}", Config::example()?);
+        return Ok(());
+    }
+
     let config = Config::load_env()?;
 
     let path: PathBuf = env::args_os()
@@ -30,7 +38,9 @@ fn main() -> Result<()> {
     let mut model: Model = toml::from_str(&data).context("parse data")?;
     model.path = path;
     model.keybindings.extend(config.keybindings);
+    model.keybindings.warn_conflicts();
     model.max_undo = config.undo_steps;
+    model.show_undo_status = config.show_undo_status;
 
     model.did_load();
 
@@ -76,20 +86,35 @@ fn read_event(timeout: Option<Duration>) -> Result<Option<Event>> {
 struct TtyModes(());
 
 impl TtyModes {
+    /// Enable the tty modes and install a panic hook, that resets the terminal before the panic
+    /// message is printed
     fn enable() -> Self {
         crossterm::execute!(io::stdout(), event::EnableFocusChange).expect("enable tty modes");
+
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            Self::disable();
+            ratatui::restore();
+            hook(info);
+        }));
+
         Self(())
     }
-}
 
-impl Drop for TtyModes {
-    fn drop(&mut self) {
+    /// Disable the tty modes, can be called multiple times
+    fn disable() {
         if let Err(err) = crossterm::execute!(io::stdout(), event::DisableFocusChange) {
             eprintln!("failed to disable tty modes: {err}");
         }
     }
 }
 
+impl Drop for TtyModes {
+    fn drop(&mut self) {
+        Self::disable();
+    }
+}
+
 trait CharToByteIndex {
     fn char_to_byte_index(&self, index: usize) -> usize;
 }
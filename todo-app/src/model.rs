@@ -3,19 +3,19 @@ use std::{
 };
 
 use anyhow::{Context, Result};
-use crokey::KeyCombination;
+use chrono::{DateTime, Utc};
 use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 use ratatui::{
     layout::{Constraint, Layout},
     style::Stylize,
-    text::Text,
+    text::{Line, Span, Text},
     widgets::{List, ListState},
     Frame,
 };
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    config::Keybindings,
+    config::{KeyLookup, KeySequence, Keybindings, PendingKeys},
     todo::{State, Todo},
     CharToByteIndex,
 };
@@ -24,6 +24,10 @@ pub fn default_undo_steps() -> usize {
     4096
 }
 
+pub fn default_show_undo_status() -> bool {
+    true
+}
+
 #[derive(Debug, Default, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct Model {
@@ -33,6 +37,9 @@ pub struct Model {
     #[serde(default, rename = "todo")]
     todos: Vec<Todo>,
 
+    #[serde(default, rename = "archive", skip_serializing_if = "Vec::is_empty")]
+    archived: Vec<Todo>,
+
     #[serde(skip)]
     pub path: PathBuf,
 
@@ -66,8 +73,21 @@ pub struct Model {
     #[serde(skip)]
     redo_buffer: Vec<UndoAction>,
 
+    #[serde(skip)]
+    pub show_undo_status: bool,
+
+    /// Description of the last undo or redo, shown until the next event
+    #[serde(skip)]
+    status: String,
+
     #[serde(skip)]
     paste_buffer: Option<Todo>,
+
+    #[serde(skip)]
+    hide_help: bool,
+
+    #[serde(skip)]
+    pending_keys: PendingKeys,
 }
 
 impl Model {
@@ -90,7 +110,7 @@ impl Model {
         }
 
         if self.todos.is_empty() {
-            self.todos.push(Todo::default());
+            self.todos.push(Todo::new(0));
             self.reselect();
         }
     }
@@ -108,6 +128,7 @@ impl Model {
     }
 
     pub fn update(&mut self, event: Option<Event>) -> Result<ControlFlow<()>> {
+        self.status = String::new();
         let result = if let Some(cursor_y) = self.cursor_y {
             if self.edit_title {
                 self.update_insert_title(event, cursor_y)
@@ -119,7 +140,7 @@ impl Model {
         };
 
         if self.todos.is_empty() {
-            self.todos.push(Todo::default());
+            self.todos.push(Todo::new(0));
             self.push_undo_delete();
             self.reselect();
         }
@@ -150,9 +171,12 @@ impl Model {
                 return Command::Unselect.run(self);
             }
             Event::Key(event) if event.kind == KeyEventKind::Press => {
-                let key: KeyCombination = event.into();
-                if let Some(command) = self.keybindings.normal.get(&key).copied() {
-                    return command.run(self);
+                match self
+                    .pending_keys
+                    .lookup(&self.keybindings.normal, event.into())
+                {
+                    KeyLookup::Command(command) => return command.run(self),
+                    KeyLookup::Pending | KeyLookup::Unbound => {}
                 }
             }
             Event::Key(_) => {}
@@ -194,9 +218,13 @@ impl Model {
             Event::FocusLost => {}
             Event::Key(event) => {
                 if event.kind == KeyEventKind::Press {
-                    let key: KeyCombination = event.into();
-                    if let Some(command) = self.keybindings.insert.get(&key) {
-                        return command.run(self);
+                    match self
+                        .pending_keys
+                        .lookup(&self.keybindings.insert, event.into())
+                    {
+                        KeyLookup::Command(command) => return command.run(self),
+                        // the key may also be text input, e.g. the first key of `j k`
+                        KeyLookup::Pending | KeyLookup::Unbound => {}
                     }
                 }
 
@@ -205,13 +233,7 @@ impl Model {
                     Some(None) => {
                         let level = todo.level;
                         self.change_selection(|model| {
-                            model.todos.insert(
-                                model.index + 1,
-                                Todo {
-                                    level,
-                                    ..Default::default()
-                                },
-                            );
+                            model.todos.insert(model.index + 1, Todo::new(level));
                             model.index += 1;
                             model.cursor_y = Some(0);
                         });
@@ -251,9 +273,13 @@ impl Model {
             Event::FocusLost => {}
             Event::Key(event) => {
                 if event.kind == KeyEventKind::Press {
-                    let key: KeyCombination = event.into();
-                    if let Some(command) = self.keybindings.insert.get(&key) {
-                        return command.run(self);
+                    match self
+                        .pending_keys
+                        .lookup(&self.keybindings.insert, event.into())
+                    {
+                        KeyLookup::Command(command) => return command.run(self),
+                        // the key may also be text input, e.g. the first key of `j k`
+                        KeyLookup::Pending | KeyLookup::Unbound => {}
                     }
                 }
                 if let Some(Some(y)) = Self::update_text(cursor_y, &mut self.title, chars, event) {
@@ -307,8 +333,13 @@ impl Model {
             Constraint::Length(1),
             Constraint::Length(2),
             Constraint::Fill(1),
+            Constraint::Length(if self.hide_help && self.status.is_empty() {
+                0
+            } else {
+                1
+            }),
         ]);
-        let [title_area, underline_area, main_area] = vertical.areas(frame.area());
+        let [title_area, underline_area, main_area, help_area] = vertical.areas(frame.area());
 
         let mut text = Text::raw(self.title.as_str()).bold();
         if self.title.is_empty() {
@@ -322,6 +353,33 @@ impl Model {
         let list = List::new(self.todos.iter().map(Todo::to_text));
 
         frame.render_stateful_widget(list, main_area, &mut self.list_state.borrow_mut());
+
+        if !self.status.is_empty() {
+            frame.render_widget(Line::raw(self.status.as_str()).italic(), help_area);
+        } else if !self.hide_help {
+            frame.render_widget(self.help_line(), help_area);
+        }
+    }
+
+    /// Keybindings of the current mode, sorted by command
+    fn help_line(&self) -> Line<'_> {
+        let keybindings = if self.cursor_y.is_some() {
+            &self.keybindings.insert
+        } else {
+            &self.keybindings.normal
+        };
+        let mut keybindings: Vec<_> = keybindings
+            .iter()
+            .map(|(key, command)| (*command, key.to_string()))
+            .collect();
+        keybindings.sort();
+
+        Line::from_iter(keybindings.into_iter().flat_map(|(command, key)| {
+            [
+                Span::raw(key).bold(),
+                Span::raw(format!(" {}  ", command.label())).dark_gray(),
+            ]
+        }))
     }
 
     pub fn cursor_position(&mut self) -> Option<(u16, u16)> {
@@ -380,12 +438,13 @@ impl Model {
     }
 }
 
-#[derive(Debug, Deserialize, Clone, Copy)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 #[serde(rename_all = "snake_case")]
 pub enum Command {
     Quit,
     GoDown,
     GoUp,
+    GoTop,
     Leave,
     Unselect,
     ToggleSelect,
@@ -405,10 +464,13 @@ pub enum Command {
     Copy,
     PasteAbove,
     PasteBelow,
+    Archive,
+    Restore,
+    ToggleHelp,
 }
 
 impl Command {
-    pub fn normal_keybindings() -> impl Iterator<Item = (KeyCombination, Self)> {
+    pub fn normal_keybindings() -> impl Iterator<Item = (KeySequence, Self)> {
         [
             (crokey::key! {q}, Self::Quit),
             (crokey::key! {j}, Self::GoDown),
@@ -430,17 +492,57 @@ impl Command {
             (crokey::key! {y}, Self::Copy),
             (crokey::key! {p}, Self::PasteBelow),
             (crokey::key! {shift-p}, Self::PasteAbove),
+            (crokey::key! {x}, Self::Archive),
+            (crokey::key! {shift-x}, Self::Restore),
+            (crokey::key! {'?'}, Self::ToggleHelp),
         ]
         .into_iter()
+        .map(|(key, command)| (key.into(), command))
+        .chain([(
+            KeySequence(vec![crokey::key! {g}, crokey::key! {g}]),
+            Self::GoTop,
+        )])
     }
 
-    pub fn insert_keybindings() -> impl Iterator<Item = (KeyCombination, Self)> {
+    pub fn insert_keybindings() -> impl Iterator<Item = (KeySequence, Self)> {
         [
             (crokey::key! {esc}, Self::Leave),
             (crokey::key! {alt-'>'}, Self::Indent),
             (crokey::key! {alt-'<'}, Self::Outdent),
         ]
         .into_iter()
+        .map(|(key, command)| (key.into(), command))
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Quit => "quit",
+            Self::GoDown => "down",
+            Self::GoUp => "up",
+            Self::GoTop => "top",
+            Self::Leave => "leave",
+            Self::Unselect => "unselect",
+            Self::ToggleSelect => "select",
+            Self::Toggle => "toggle",
+            Self::Indent => "indent",
+            Self::Outdent => "outdent",
+            Self::Insert => "insert",
+            Self::Append => "append",
+            Self::InsertAbove => "new above",
+            Self::InsertBelow => "new below",
+            Self::Delete => "delete",
+            Self::Save => "save",
+            Self::InsertTitle => "insert title",
+            Self::AppendTitle => "append title",
+            Self::Undo => "undo",
+            Self::Redo => "redo",
+            Self::Copy => "copy",
+            Self::PasteAbove => "paste above",
+            Self::PasteBelow => "paste below",
+            Self::Archive => "archive",
+            Self::Restore => "restore",
+            Self::ToggleHelp => "help",
+        }
     }
 
     fn run(self, model: &mut Model) -> Result<ControlFlow<()>> {
@@ -459,6 +561,11 @@ impl Command {
                     model.index = model.index.saturating_sub(1);
                 });
             }
+            Self::GoTop => {
+                model.change_selection(|model| {
+                    model.index = 0;
+                });
+            }
             Self::Leave => {
                 model.cursor_y = None;
             }
@@ -472,14 +579,15 @@ impl Command {
                 model.reselect();
             }
             Self::Toggle => {
-                if let Some(state) = model.with_selected_or_select(|t| {
-                    let state = t.state;
-                    t.state.next();
-                    state
+                if let Some((state, completed_at)) = model.with_selected_or_select(|t| {
+                    let previous = (t.state, t.completed_at);
+                    t.next_state();
+                    previous
                 }) {
                     model.push_undo(UndoAction::SetState {
                         index: model.index,
                         state,
+                        completed_at,
                     });
                 }
             }
@@ -524,13 +632,7 @@ impl Command {
             Self::InsertBelow => {
                 if let Some(level) = model.with_selected_or_select(|t| t.level) {
                     model.change_selection(|model| {
-                        model.todos.insert(
-                            model.index + 1,
-                            Todo {
-                                level,
-                                ..Default::default()
-                            },
-                        );
+                        model.todos.insert(model.index + 1, Todo::new(level));
                         model.index += 1;
                         model.cursor_y = Some(0);
                     });
@@ -540,13 +642,7 @@ impl Command {
             Self::InsertAbove => {
                 if let Some(level) = model.with_selected_or_select(|t| t.level) {
                     model.change_selection(|model| {
-                        model.todos.insert(
-                            model.index,
-                            Todo {
-                                level,
-                                ..Default::default()
-                            },
-                        );
+                        model.todos.insert(model.index, Todo::new(level));
                         model.cursor_y = Some(0);
                     });
                     model.push_undo_delete();
@@ -582,6 +678,9 @@ impl Command {
             }
             Self::Undo => loop {
                 if let Some(action) = model.undo_buffer.pop_back() {
+                    if model.show_undo_status {
+                        model.status = format!("undid {}", action.description(model));
+                    }
                     let redo = action.run(model);
                     model.redo_buffer.push(redo);
                     if model.todos.is_empty() {
@@ -593,6 +692,9 @@ impl Command {
             Self::Redo => loop {
                 if let Some(action) = model.redo_buffer.pop() {
                     let undo = action.run(model);
+                    if model.show_undo_status {
+                        model.status = format!("redid {}", undo.description(model));
+                    }
                     model.undo_buffer.push_back(undo);
                     if model.todos.is_empty() {
                         continue;
@@ -623,6 +725,26 @@ impl Command {
                     model.push_undo_delete();
                 }
             }
+            Self::ToggleHelp => {
+                model.hide_help ^= true;
+            }
+            Self::Archive => {
+                let indices: Vec<_> = (0..model.todos.len())
+                    .filter(|&index| model.todos[index].state == State::Done)
+                    .collect();
+                if !indices.is_empty() {
+                    let undo = UndoAction::Archive { indices }.run(model);
+                    model.push_undo(undo);
+                }
+            }
+            Self::Restore => {
+                if !model.archived.is_empty() {
+                    let len = model.todos.len();
+                    let indices = (len..len + model.archived.len()).collect();
+                    let undo = UndoAction::Unarchive { indices }.run(model);
+                    model.push_undo(undo);
+                }
+            }
         }
 
         Ok(ControlFlow::Continue(()))
@@ -632,19 +754,70 @@ impl Command {
 #[derive(Debug)]
 enum UndoAction {
     // undo of insert
-    Delete { index: usize },
+    Delete {
+        index: usize,
+    },
 
     // undo of delete
-    Insert { index: usize, todo: Todo },
-
-    SetText { index: usize, text: String },
-
-    SetLevel { index: usize, level: usize },
-
-    SetState { index: usize, state: State },
+    Insert {
+        index: usize,
+        todo: Todo,
+    },
+
+    SetText {
+        index: usize,
+        text: String,
+    },
+
+    SetLevel {
+        index: usize,
+        level: usize,
+    },
+
+    SetState {
+        index: usize,
+        state: State,
+        completed_at: Option<DateTime<Utc>>,
+    },
+
+    // move the todos at the (ascending) indices to the end of the archive
+    Archive {
+        indices: Vec<usize>,
+    },
+
+    // move the last todos of the archive back to the (ascending) indices
+    Unarchive {
+        indices: Vec<usize>,
+    },
 }
 
 impl UndoAction {
+    /// The change this action reverts, e.g. `delete of 'Buy milk'`
+    fn description(&self, model: &Model) -> String {
+        let text = |index: usize| {
+            model
+                .todos
+                .get(index)
+                .map_or_else(String::new, |todo| todo.text.clone())
+        };
+        match self {
+            Self::Delete { index } => format!("insert of '{}'", text(*index)),
+            Self::Insert { todo, .. } => format!("delete of '{}'", todo.text),
+            Self::SetText { index, .. } => format!("edit of '{}'", text(*index)),
+            Self::SetLevel { index, level } => {
+                let indented = model
+                    .todos
+                    .get(*index)
+                    .is_some_and(|todo| todo.level > *level);
+                let change = if indented { "indent" } else { "outdent" };
+                format!("{change} of '{}'", text(*index))
+            }
+            Self::SetState { index, .. } => format!("state change of '{}'", text(*index)),
+            Self::Archive { indices } => format!("restore of {} todos", indices.len()),
+            Self::Unarchive { indices } => format!("archive of {} todos", indices.len()),
+        }
+    }
+
     fn run(self, model: &mut Model) -> Self {
         model.unselect();
         model.is_selected = true;
@@ -673,13 +846,186 @@ impl UndoAction {
                 let level = mem::replace(&mut model.todos[index].level, level);
                 Self::SetLevel { index, level }
             }
-            Self::SetState { index, state } => {
+            Self::SetState {
+                index,
+                state,
+                completed_at,
+            } => {
                 model.index = index;
-                let state = mem::replace(&mut model.todos[index].state, state);
-                Self::SetState { index, state }
+                let todo = &mut model.todos[index];
+                let state = mem::replace(&mut todo.state, state);
+                let completed_at = mem::replace(&mut todo.completed_at, completed_at);
+                Self::SetState {
+                    index,
+                    state,
+                    completed_at,
+                }
+            }
+            Self::Archive { indices } => {
+                let mut todos: Vec<_> = indices
+                    .iter()
+                    .rev()
+                    .map(|&index| model.todos.remove(index))
+                    .collect();
+                todos.reverse();
+                model.archived.extend(todos);
+                model.index = indices[0].min(model.todos.len().saturating_sub(1));
+                Self::Unarchive { indices }
+            }
+            Self::Unarchive { indices } => {
+                let todos = model
+                    .archived
+                    .split_off(model.archived.len() - indices.len());
+                for (&index, todo) in indices.iter().zip(todos) {
+                    model.todos.insert(index, todo);
+                }
+                model.index = indices[0];
+                Self::Archive { indices }
             }
         };
         model.reselect();
         reverse
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn help_text(model: &Model) -> String {
+        model
+            .help_line()
+            .spans
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect()
+    }
+
+    #[test]
+    fn footer_shows_the_keybindings_of_the_mode() {
+        let mut model = Model::default();
+
+        let normal = help_text(&model);
+        assert!(normal.contains("q quit"), "{normal}");
+        assert!(normal.contains("u undo"), "{normal}");
+        assert!(!normal.contains("leave"), "{normal}");
+
+        model.cursor_y = Some(0);
+        let insert = help_text(&model);
+        assert!(insert.contains(" leave"), "{insert}");
+        assert!(!insert.contains("quit"), "{insert}");
+    }
+
+    fn texts(todos: &[Todo]) -> Vec<&str> {
+        todos.iter().map(|todo| todo.text.as_str()).collect()
+    }
+
+    fn run(model: &mut Model, command: Command) {
+        assert!(command.run(model).unwrap().is_continue());
+    }
+
+    #[test]
+    fn archive_moves_the_done_todos() {
+        let todo = |text: &str, state| Todo {
+            text: text.into(),
+            state,
+            ..Todo::new(0)
+        };
+        let mut model = Model {
+            todos: vec![
+                todo("open", State::Open),
+                todo("done 1", State::Done),
+                todo("wip", State::Wip),
+                todo("done 2", State::Done),
+            ],
+            max_undo: 10,
+            ..Default::default()
+        };
+
+        run(&mut model, Command::Archive);
+        assert_eq!(texts(&model.todos), ["open", "wip"]);
+        assert_eq!(texts(&model.archived), ["done 1", "done 2"]);
+
+        // the archive is saved in its own section
+        let saved = toml::to_string(&model).unwrap();
+        assert!(saved.contains("[[archive]]"), "{saved}");
+        let loaded: Model = toml::from_str(&saved).unwrap();
+        assert_eq!(loaded.archived, model.archived);
+
+        run(&mut model, Command::Undo);
+        assert_eq!(texts(&model.todos), ["open", "done 1", "wip", "done 2"]);
+        assert!(model.archived.is_empty());
+
+        run(&mut model, Command::Redo);
+        assert_eq!(texts(&model.todos), ["open", "wip"]);
+        assert_eq!(texts(&model.archived), ["done 1", "done 2"]);
+
+        // restored todos are appended to the active list
+        run(&mut model, Command::Restore);
+        assert_eq!(texts(&model.todos), ["open", "wip", "done 1", "done 2"]);
+        assert!(model.archived.is_empty());
+
+        run(&mut model, Command::Undo);
+        assert_eq!(texts(&model.todos), ["open", "wip"]);
+        assert_eq!(texts(&model.archived), ["done 1", "done 2"]);
+    }
+
+    #[test]
+    fn undo_descriptions() {
+        let milk = Todo {
+            text: "Buy milk".into(),
+            level: 1,
+            ..Todo::new(0)
+        };
+        let model = Model {
+            todos: vec![milk.clone()],
+            ..Default::default()
+        };
+        let description = |action: UndoAction| action.description(&model);
+
+        assert_eq!(
+            description(UndoAction::Delete { index: 0 }),
+            "insert of 'Buy milk'",
+        );
+        assert_eq!(
+            description(UndoAction::Insert {
+                index: 0,
+                todo: milk.clone(),
+            }),
+            "delete of 'Buy milk'",
+        );
+        assert_eq!(
+            description(UndoAction::SetText {
+                index: 0,
+                text: "Buy bread".into(),
+            }),
+            "edit of 'Buy milk'",
+        );
+        assert_eq!(
+            description(UndoAction::SetLevel { index: 0, level: 0 }),
+            "indent of 'Buy milk'",
+        );
+        assert_eq!(
+            description(UndoAction::SetLevel { index: 0, level: 2 }),
+            "outdent of 'Buy milk'",
+        );
+        assert_eq!(
+            description(UndoAction::SetState {
+                index: 0,
+                state: State::Done,
+                completed_at: None,
+            }),
+            "state change of 'Buy milk'",
+        );
+        assert_eq!(
+            description(UndoAction::Archive {
+                indices: vec![0, 2],
+            }),
+            "restore of 2 todos",
+        );
+        assert_eq!(
+            description(UndoAction::Unarchive { indices: vec![1] }),
+            "archive of 1 todos",
+        );
+    }
+}
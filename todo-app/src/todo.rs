@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use ratatui::{
     style::Stylize,
     text::{Line, Span, Text},
@@ -12,6 +13,10 @@ pub struct Todo {
     pub text: String,
     #[serde(default, skip_serializing_if = "State::is_open")]
     pub state: State,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<DateTime<Utc>>,
 
     #[serde(skip)]
     pub selected: bool,
@@ -20,7 +25,15 @@ pub struct Todo {
 impl Todo {
     const LEVEL_SPACE: &str = "                ";
 
-    pub fn to_text(&self) -> Text {
+    pub fn new(level: usize) -> Self {
+        Self {
+            level,
+            created_at: Some(Utc::now()),
+            ..Default::default()
+        }
+    }
+
+    pub fn to_text(&self) -> Text<'_> {
         let level = Span::raw(&Self::LEVEL_SPACE[..self.level * 2]);
         let state = Span::raw(self.state.as_str());
         let mut text = Span::raw(self.text.as_str());
@@ -34,6 +47,15 @@ impl Todo {
         Line::from_iter([level, state, text, marker]).into()
     }
 
+    /// Advance the state, `completed_at` is set when the todo becomes done
+    pub fn next_state(&mut self) {
+        self.state.next();
+        self.completed_at = match self.state {
+            State::Done => Some(Utc::now()),
+            State::Open | State::Wip => None,
+        };
+    }
+
     pub fn level_incr(&mut self) {
         if self.level < const { Self::LEVEL_SPACE.len() / 2 } {
             self.level += 1;
@@ -79,3 +101,36 @@ impl State {
 fn is_zero(n: &usize) -> bool {
     *n == 0
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn completed_at_is_set_when_done() {
+        let mut todo = Todo::new(0);
+        assert!(todo.created_at.is_some());
+        assert_eq!(todo.completed_at, None);
+
+        todo.next_state();
+        assert_eq!(todo.state, State::Wip);
+        assert_eq!(todo.completed_at, None);
+
+        todo.next_state();
+        assert_eq!(todo.state, State::Done);
+        assert!(todo.completed_at.is_some());
+
+        // reopening clears the completion time
+        todo.next_state();
+        assert_eq!(todo.state, State::Open);
+        assert_eq!(todo.completed_at, None);
+    }
+
+    #[test]
+    fn timestamps_are_optional_in_saved_todos() {
+        let todo: Todo = toml::from_str(r#"text = "old todo""#).unwrap();
+        assert_eq!(todo.created_at, None);
+        assert_eq!(todo.completed_at, None);
+        assert!(!toml::to_string(&todo).unwrap().contains("_at"));
+    }
+}
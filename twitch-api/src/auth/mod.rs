@@ -1,77 +1,290 @@
-use std::io;
+use std::{fmt, io, time::Duration};
+
+use rand::{Rng, distr::Alphanumeric};
+use url::Url;
 
 use anyhow::{Context, Result};
-use clap::Args;
+use chrono::{DateTime, TimeDelta, Utc};
+use clap::{Args, ValueEnum};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    client::{Client, FormEncoding, Request},
+    client::{Api, Client, FormEncoding, NoContent, Request},
     config::{ClientConfig, TokenConfig},
+    error::ApiError,
     secret::Secret,
 };
 
 mod token_manager;
 
-pub use self::token_manager::TokenManager;
+pub use self::token_manager::{TokenManager, ValidateResponse};
 
 #[derive(Debug, Args)]
 /// Authorize client against twitch api
-pub struct Auth {}
+pub struct Auth {
+    /// Also request access to the verified email address of the user
+    #[arg(long)]
+    read_email: bool,
+
+    /// Revoke the saved access token and delete the saved tokens instead
+    #[arg(long, conflicts_with = "read_email")]
+    revoke: bool,
+
+    /// How the user authorizes the client
+    #[arg(long, value_enum, default_value_t = AuthFlow::Device)]
+    flow: AuthFlow,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum AuthFlow {
+    /// Enter a code on the twitch website
+    Device,
+
+    /// Open the authorization url and paste the redirect url, needs `client_secret` and `redirect_uri`
+    Code,
+}
 
 impl Auth {
-    pub async fn run(self, scopes: impl IntoIterator<Item = Scope>) -> Result<()> {
+    pub async fn run(self, client: &Client, scopes: impl IntoIterator<Item = Scope>) -> Result<()> {
+        if self.revoke {
+            TokenManager::from_env()?
+                .revoke(client)
+                .await
+                .context("revoke access token")?;
+            TokenConfig::remove_from_env().context("delete tokens")?;
+            eprintln!("Ok");
+            return Ok(());
+        }
+
         let config = ClientConfig::load_from_env()?;
         eprintln!("{config:#?}");
 
-        let scopes = Scopes::from_iter(scopes);
+        let scopes = self.scopes(scopes);
 
-        let client = Client::new();
-
-        let res = client
-            .send(&DeviceRequest {
-                client_id: config.client_id.clone(),
-                scopes: scopes.clone(),
-            })
-            .await
-            .context("device request")?;
+        let res = match self.flow {
+            AuthFlow::Device => device_flow(client, config, scopes).await?,
+            AuthFlow::Code => code_flow(client, config, scopes).await?,
+        };
 
         eprintln!("{res:#?}");
-        println!("{}", res.verification_uri.access_secret_value());
 
-        {
-            eprint!("Press ENTER once authenticated using the provided URL: ");
-            let mut buf = String::new();
-            let result = io::stdin().read_line(&mut buf);
-            let nl = buf.ends_with("\n");
-            if !nl {
-                eprintln!();
-            }
-            result.context("receive ENTER from stdin")?;
-            anyhow::ensure!(nl, "authentication canceled");
+        TokenConfig {
+            expires_at: Some(res.expires_at()),
+            access_token: res.access_token,
+            refresh_token: res.refresh_token,
         }
+        .save_to_env()
+        .context("save tokens")?;
+
+        Ok(())
+    }
+
+    /// The requested scopes, the email scope is only added with `--read-email`
+    fn scopes(&self, scopes: impl IntoIterator<Item = Scope>) -> Scopes {
+        scopes
+            .into_iter()
+            .chain(self.read_email.then_some(Scope::UserReadEmail))
+            .collect()
+    }
+}
+
+fn read_line(prompt: &str) -> Result<String> {
+    eprint!("{prompt}");
+    let mut buf = String::new();
+    let result = io::stdin().read_line(&mut buf);
+    let nl = buf.ends_with("\n");
+    if !nl {
+        eprintln!();
+    }
+    result.context("read line from stdin")?;
+    anyhow::ensure!(nl, "authentication canceled");
+    Ok(buf.trim().into())
+}
+
+async fn device_flow(
+    client: &Client,
+    config: ClientConfig,
+    scopes: Scopes,
+) -> Result<TokenResponse> {
+    let res = request_device_code(client, config.client_id.clone(), scopes.clone()).await?;
+
+    eprintln!("{res:#?}");
+    println!("{}", res.verification_uri.access_secret_value());
 
-        eprintln!("Ok");
+    read_line("Press ENTER once authenticated using the provided URL: ")?;
 
-        let res = client
+    eprintln!("Ok");
+
+    client
+        .send(&TokenRequest {
+            client_id: config.client_id,
+            scopes,
+            device_code: res.device_code,
+            grant_type: TokenRequest::GRANT_TYPE.into(),
+        })
+        .await
+        .context("token request")
+}
+
+/// Start the device flow, the user enters [`DeviceResponse::user_code`] at
+/// [`DeviceResponse::verification_uri`]
+pub async fn request_device_code(
+    client: &Client,
+    client_id: Secret,
+    scopes: Scopes,
+) -> Result<DeviceResponse> {
+    client
+        .send(&DeviceRequest { client_id, scopes })
+        .await
+        .context("device request")
+}
+
+/// Wait until the user authorized the device code, without waiting for the user to press enter
+///
+/// The token is polled every [`DeviceResponse::interval`] seconds until the device code expires.
+pub async fn poll_device_token(
+    client: &Client,
+    client_id: Secret,
+    scopes: Scopes,
+    device: &DeviceResponse,
+) -> Result<TokenResponse> {
+    let interval = Duration::from_secs(device.interval.max(1).into());
+    let expires_at = Utc::now() + TimeDelta::seconds(device.expires_in.into());
+    loop {
+        tokio::time::sleep(interval).await;
+        anyhow::ensure!(Utc::now() < expires_at, "device code expired");
+        match client
             .send(&TokenRequest {
-                client_id: config.client_id,
-                scopes,
-                device_code: res.device_code,
+                client_id: client_id.clone(),
+                scopes: scopes.clone(),
+                device_code: device.device_code.clone(),
                 grant_type: TokenRequest::GRANT_TYPE.into(),
             })
             .await
-            .context("token request")?;
-
-        eprintln!("{res:#?}");
+        {
+            Err(ApiError::ErrorResponse(_, res)) if res.message == "authorization_pending" => {}
+            res => return res.context("token request"),
+        }
+    }
+}
 
-        TokenConfig {
-            access_token: res.access_token,
-            refresh_token: res.refresh_token,
+async fn code_flow(client: &Client, config: ClientConfig, scopes: Scopes) -> Result<TokenResponse> {
+    let client_secret = config
+        .client_secret
+        .context("the code flow needs a client_secret in the client config")?;
+    let redirect_uri = config
+        .redirect_uri
+        .context("the code flow needs a redirect_uri in the client config")?;
+
+    let req = AuthCodeRequest {
+        client_id: config.client_id.clone(),
+        redirect_uri: redirect_uri.clone(),
+        scopes,
+        state: rand::rng()
+            .sample_iter(Alphanumeric)
+            .take(32)
+            .map(char::from)
+            .collect(),
+    };
+    println!("{}", req.url(&client.base_urls().id)?);
+
+    let input = read_line("Paste the URL you were redirected to (or the code): ")?;
+    let code = match Url::parse(&input) {
+        Ok(url) => {
+            let param = |name: &str| {
+                url.query_pairs()
+                    .find(|(key, _)| key == name)
+                    .map(|(_, value)| value.into_owned())
+            };
+            if let Some(error) = param("error_description").or_else(|| param("error")) {
+                anyhow::bail!("authorization failed: {error}");
+            }
+            anyhow::ensure!(
+                param("state").as_ref() == Some(&req.state),
+                "state of the redirect does not match, the authorization was not requested here",
+            );
+            param("code").context("missing code in the redirect url")?
         }
-        .save_to_env()
-        .context("save tokens")?;
+        Err(_) => input,
+    };
 
-        Ok(())
+    eprintln!("Ok");
+
+    client
+        .send(&AuthCodeTokenRequest {
+            client_id: config.client_id,
+            client_secret,
+            code: Secret::new(code),
+            grant_type: AuthCodeTokenRequest::GRANT_TYPE.into(),
+            redirect_uri,
+        })
+        .await
+        .context("token request")
+}
+
+/// Authorization url the user opens in the browser, twitch redirects back with a code
+#[derive(Debug)]
+pub struct AuthCodeRequest {
+    /// Your app’s registered client ID.
+    client_id: Secret,
+
+    /// Your app’s registered redirect URI. The authorization code is sent to this URI.
+    redirect_uri: String,
+
+    /// A space-delimited list of scopes. The APIs that you’re calling identify the scopes you must list. You must URL encode the list.
+    scopes: Scopes,
+
+    /// Although optional, you are strongly encouraged to pass a state string to help prevent Cross-Site Request Forgery (CSRF) attacks. The server returns this string to you in your redirect URI (see the state parameter in the fragment portion of the URI). If this string doesn’t match the state string that you passed, ignore the response.
+    state: String,
+}
+
+impl AuthCodeRequest {
+    fn url(&self, base_url: &Url) -> Result<Url> {
+        let mut url = Url::parse(&format!(
+            "{}/oauth2/authorize",
+            base_url.as_str().trim_end_matches('/'),
+        ))
+        .context("parse authorize url")?;
+        url.query_pairs_mut()
+            .append_pair("client_id", self.client_id.access_secret_value())
+            .append_pair("redirect_uri", &self.redirect_uri)
+            .append_pair("response_type", "code")
+            .append_pair("scope", &self.scopes.to_string())
+            .append_pair("state", &self.state);
+        Ok(url)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuthCodeTokenRequest {
+    /// Your app’s registered client ID.
+    client_id: Secret,
+
+    /// Your app’s registered client secret.
+    client_secret: Secret,
+
+    /// The code that the /authorize response returned in the code query parameter.
+    code: Secret,
+
+    /// Must be set to authorization_code.
+    grant_type: String,
+
+    /// Your app’s registered redirect URI.
+    redirect_uri: String,
+}
+
+impl AuthCodeTokenRequest {
+    const GRANT_TYPE: &str = "authorization_code";
+}
+
+impl Request for AuthCodeTokenRequest {
+    type Encoding = FormEncoding;
+    type Response = TokenResponse;
+
+    const API: Api = Api::Id;
+
+    fn path(&self) -> &str {
+        "/oauth2/token"
     }
 }
 
@@ -88,8 +301,10 @@ impl Request for DeviceRequest {
     type Encoding = FormEncoding;
     type Response = DeviceResponse;
 
-    fn url(&self) -> impl reqwest::IntoUrl {
-        "https://id.twitch.tv/oauth2/device"
+    const API: Api = Api::Id;
+
+    fn path(&self) -> &str {
+        "/oauth2/device"
     }
 }
 
@@ -134,8 +349,10 @@ impl Request for TokenRequest {
     type Encoding = FormEncoding;
     type Response = TokenResponse;
 
-    fn url(&self) -> impl reqwest::IntoUrl {
-        "https://id.twitch.tv/oauth2/token"
+    const API: Api = Api::Id;
+
+    fn path(&self) -> &str {
+        "/oauth2/token"
     }
 }
 
@@ -157,6 +374,32 @@ pub struct TokenResponse {
     pub token_type: String,
 }
 
+impl TokenResponse {
+    pub fn expires_at(&self) -> DateTime<Utc> {
+        Utc::now() + TimeDelta::seconds(self.expires_in.into())
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct RevokeRequest {
+    /// Your app’s client ID.
+    client_id: Secret,
+
+    /// The access token to revoke.
+    token: Secret,
+}
+
+impl Request for RevokeRequest {
+    type Encoding = FormEncoding;
+    type Response = NoContent;
+
+    const API: Api = Api::Id;
+
+    fn path(&self) -> &str {
+        "/oauth2/revoke"
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Scopes(Vec<Scope>);
 
@@ -166,24 +409,30 @@ impl FromIterator<Scope> for Scopes {
     }
 }
 
+impl fmt::Display for Scopes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, scope) in self.0.iter().enumerate() {
+            if i != 0 {
+                f.write_str(" ")?;
+            }
+            f.write_str(scope.to_str())?;
+        }
+        Ok(())
+    }
+}
+
 impl Serialize for Scopes {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
     {
-        let mut scopes = self.0.iter().map(|s| s.to_str());
-        let mut s = scopes.next().unwrap_or_default().to_string();
-        for scope in scopes {
-            s.push(' ');
-            s.push_str(scope);
-        }
-        s.serialize(serializer)
+        self.to_string().serialize(serializer)
     }
 }
 
 macro_rules! scopes {
     ($($ident:ident => $str:literal,)*) => {
-        #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
         pub enum Scope {
             $(
                 #[serde(rename=$str)]
@@ -192,7 +441,7 @@ macro_rules! scopes {
         }
 
         impl Scope {
-            fn to_str(self) -> &'static str {
+            pub(crate) fn to_str(self) -> &'static str {
                 match self {
                     $(Self::$ident => $str,)*
                 }
@@ -206,4 +455,49 @@ scopes! {
     UserWriteChat => "user:write:chat",
     ModeratorManageAnnouncements => "moderator:manage:announcements",
     ModeratorReadFollowers => "moderator:read:followers",
+    ModeratorManageShoutouts => "moderator:manage:shoutouts",
+    ModeratorManageBannedUsers => "moderator:manage:banned_users",
+    ModeratorManageChatMessages => "moderator:manage:chat_messages",
+    ModeratorManageChatSettings => "moderator:manage:chat_settings",
+    ChannelReadStreamKey => "channel:read:stream_key",
+    ChannelReadEditors => "channel:read:editors",
+    ChannelManageBroadcast => "channel:manage:broadcast",
+    BitsRead => "bits:read",
+    ChannelReadRedemptions => "channel:read:redemptions",
+    ChannelReadPolls => "channel:read:polls",
+    ChannelReadPredictions => "channel:read:predictions",
+    UserReadEmail => "user:read:email",
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        auth: Auth,
+    }
+
+    fn scopes(args: &[&str]) -> String {
+        let cli = Cli::try_parse_from(["auth"].iter().chain(args)).unwrap();
+        cli.auth.scopes([Scope::UserReadChat]).to_string()
+    }
+
+    #[test]
+    fn email_scope_serializes() {
+        assert_eq!(
+            serde_json::to_string(&Scope::UserReadEmail).unwrap(),
+            r#""user:read:email""#,
+        );
+        assert_eq!(Scope::UserReadEmail.to_str(), "user:read:email");
+    }
+
+    #[test]
+    fn email_scope_is_opt_in() {
+        assert_eq!(scopes(&[]), "user:read:chat");
+        assert_eq!(scopes(&["--read-email"]), "user:read:chat user:read:email");
+    }
 }
@@ -1,35 +1,93 @@
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use reqwest::{StatusCode, header};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    client::{Client, FormEncoding, Request},
-    config::{ClientConfig, TokenConfig},
-    error::Result,
+    client::{Api, Client, FormEncoding, Request, UrlParamEncoding},
+    config::{self, ClientConfig, TokenConfig},
+    error::{ApiError, Result},
     secret::Secret,
 };
 
-use super::TokenResponse;
+use super::{Scope, TokenResponse};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TokenManager {
     client_id: Secret,
+
+    /// Tokens of the authorization code flow can only be refreshed with the client secret
+    client_secret: Option<Secret>,
     access_token: Secret,
     refresh_token: Secret,
+    expires_at: Option<DateTime<Utc>>,
+
+    #[serde(skip, default = "TokenManager::default_refresh_threshold")]
+    refresh_threshold: TimeDelta,
+
+    /// Profile the refreshed tokens are saved to, see [`config::set_profile`]
+    #[serde(skip)]
+    profile: Option<String>,
+
+    /// File the refreshed tokens are saved to instead of the token file of the profile
+    #[serde(skip)]
+    path: Option<PathBuf>,
 }
 
 impl TokenManager {
+    /// Access tokens expiring sooner are refreshed by [`TokenManager::ensure_valid`]
+    const MIN_EXPIRES_IN_SECS: u32 = 10 * 60;
+
     pub fn from_env() -> Result<Self> {
-        let client = ClientConfig::load_from_env()?;
-        let token = TokenConfig::load_from_env()?;
-        Ok(Self::with_config(client.client_id, token))
+        Self::from_profile(config::profile())
+    }
+
+    /// Load the tokens of the profile, independent of the selected profile
+    pub fn from_profile(profile: Option<String>) -> Result<Self> {
+        let client = ClientConfig::load_for_profile(profile.as_deref())?;
+        let token = TokenConfig::load_for_profile(profile.as_deref())?;
+        let mut this = Self::with_config(client.client_id, token);
+        this.client_secret = client.client_secret;
+        this.profile = profile;
+        Ok(this)
     }
 
     pub fn with_config(client_id: Secret, config: TokenConfig) -> Self {
         Self {
             client_id,
+            client_secret: None,
             access_token: config.access_token,
             refresh_token: config.refresh_token,
+            expires_at: config.expires_at,
+            refresh_threshold: Self::default_refresh_threshold(),
+            profile: config::profile(),
+            path: None,
         }
     }
+
+    /// Load the tokens from `path`, the refreshed tokens are saved to it as well
+    pub fn from_path(client_id: Secret, path: &Path) -> Result<Self> {
+        let mut this = Self::with_config(client_id, TokenConfig::load(path)?);
+        this.path = Some(path.into());
+        Ok(this)
+    }
+
+    fn default_refresh_threshold() -> TimeDelta {
+        TimeDelta::seconds(60)
+    }
+
+    /// Refresh the access token before a request if it expires within `threshold` (default 60s)
+    pub fn set_refresh_threshold(&mut self, threshold: TimeDelta) {
+        self.refresh_threshold = threshold;
+    }
+
+    /// Whether the access token is known to expire within the refresh threshold
+    pub fn expires_soon(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at - Utc::now() < self.refresh_threshold)
+    }
+
     pub fn access_token(&self) -> &Secret {
         &self.access_token
     }
@@ -42,11 +100,23 @@ impl TokenManager {
         TokenConfig {
             access_token: self.access_token.clone(),
             refresh_token: self.refresh_token.clone(),
+            expires_at: self.expires_at,
         }
     }
 
     fn save(&self) -> Result<()> {
-        self.config().save_to_env()
+        match &self.path {
+            Some(path) => self.config().save(path),
+            None => self.config().save_for_profile(self.profile.as_deref()),
+        }
+    }
+
+    /// Replace the tokens with newly authorized ones, e.g. with additional scopes
+    pub fn set_tokens(&mut self, res: TokenResponse) -> Result<()> {
+        self.expires_at = Some(res.expires_at());
+        self.access_token = res.access_token;
+        self.refresh_token = res.refresh_token;
+        self.save()
     }
 
     pub async fn update(&mut self, client: &mut Client) -> Result<()> {
@@ -54,13 +124,107 @@ impl TokenManager {
         let res = client
             .send(&TokenRequest {
                 client_id: self.client_id.clone(),
+                client_secret: self.client_secret.clone(),
                 grant_type: TokenRequest::GRANT_TYPE.into(),
                 refresh_token: self.refresh_token.clone(),
             })
             .await?;
-        self.access_token = res.access_token;
-        self.refresh_token = res.refresh_token;
-        self.save()
+        self.set_tokens(res)
+    }
+
+    /// Revoke the access token, it is not an error if the token is already invalid
+    pub async fn revoke(self, client: &Client) -> Result<()> {
+        match client
+            .send(&super::RevokeRequest {
+                client_id: self.client_id,
+                token: self.access_token,
+            })
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(ApiError::ErrorResponse(StatusCode::BAD_REQUEST, res)) => {
+                eprintln!(
+                    "token manager: access token already invalid: {}",
+                    res.message
+                );
+                Ok(())
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Check the access token and return its owner, scopes and remaining lifetime
+    pub async fn validate(&self, client: &Client) -> Result<ValidateResponse> {
+        client
+            .send(&ValidateRequest {
+                access_token: self.access_token.clone(),
+            })
+            .await
+    }
+
+    /// Validate the access token and refresh it if it is invalid or expires soon
+    ///
+    /// Returns whether the access token was refreshed.
+    pub async fn ensure_valid(&mut self, client: &mut Client) -> Result<bool> {
+        match self.validate(client).await {
+            Ok(res) if res.expires_in >= Self::MIN_EXPIRES_IN_SECS => {
+                self.expires_at = Some(Utc::now() + TimeDelta::seconds(res.expires_in.into()));
+                return Ok(false);
+            }
+            Ok(_) => {}
+            Err(ApiError::ErrorResponse(StatusCode::UNAUTHORIZED, _)) => {}
+            Err(err) => return Err(err),
+        }
+        self.update(client).await?;
+        Ok(true)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ValidateRequest {
+    #[serde(skip)]
+    access_token: Secret,
+}
+
+impl Request for ValidateRequest {
+    type Encoding = UrlParamEncoding;
+    type Response = ValidateResponse;
+
+    const API: Api = Api::Id;
+
+    fn path(&self) -> &str {
+        "/oauth2/validate"
+    }
+
+    fn modify_request(&self, req: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        req.header(header::AUTHORIZATION, self.access_token.bearer())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ValidateResponse {
+    /// The client ID of the app the access token was issued for.
+    pub client_id: Secret,
+
+    /// The login name of the user the access token belongs to.
+    pub login: String,
+
+    /// The ID of the user the access token belongs to.
+    pub user_id: String,
+
+    /// The scopes granted to the access token.
+    ///
+    /// Kept as strings, the token may have been granted scopes unknown to [`Scope`].
+    pub scopes: Vec<String>,
+
+    /// Seconds until the access token expires.
+    pub expires_in: u32,
+}
+
+impl ValidateResponse {
+    /// Whether the scope was granted to the access token
+    pub fn has_scope(&self, scope: Scope) -> bool {
+        self.scopes.iter().any(|granted| granted == scope.to_str())
     }
 }
 
@@ -69,9 +233,9 @@ pub struct TokenRequest {
     /// Your app’s client ID. See Registering your app.
     client_id: Secret,
 
-    #[expect(clippy::empty_line_after_doc_comments)]
     /// Your app’s client secret. See Registering your app.
-    // client_secret: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    client_secret: Option<Secret>,
 
     /// Must be set to `refresh_token`.
     grant_type: String,
@@ -91,7 +255,31 @@ impl Request for TokenRequest {
     type Encoding = FormEncoding;
     type Response = TokenResponse;
 
-    fn url(&self) -> impl reqwest::IntoUrl {
-        "https://id.twitch.tv/oauth2/token"
+    const API: Api = Api::Id;
+
+    fn path(&self) -> &str {
+        "/oauth2/token"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_response_with_unknown_scope() {
+        let res: ValidateResponse = serde_json::from_str(
+            r#"{
+                "client_id": "wbmytr93xzw8zbg0p1izqyzzc5mbiz",
+                "login": "twitchdev",
+                "scopes": ["user:read:chat", "channel:manage:vips"],
+                "user_id": "141981764",
+                "expires_in": 5520838
+            }"#,
+        )
+        .unwrap();
+        assert!(res.has_scope(Scope::UserReadChat));
+        assert!(!res.has_scope(Scope::UserWriteChat));
+        assert_eq!(res.expires_in, 5520838);
     }
 }
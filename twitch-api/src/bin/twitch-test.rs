@@ -1,7 +1,7 @@
 use anyhow::Result;
 use clap::Parser;
 use tokio::task::LocalSet;
-use twitch_api::auth;
+use twitch_api::{auth, client::Client};
 
 #[derive(Debug, Parser)]
 #[clap(version)]
@@ -24,7 +24,7 @@ async fn run() -> Result<()> {
 
     match cmd {
         Cmd::Version(cmd) => cmd.run(),
-        Cmd::Auth(cmd) => cmd.run([]).await,
+        Cmd::Auth(cmd) => cmd.run(&Client::new(), []).await,
     }
 }
 
@@ -0,0 +1,175 @@
+use chrono::{DateTime, Utc};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+use crate::client::{Request, UrlParamEncoding};
+
+#[derive(Debug, Serialize)]
+pub struct CheermotesRequest {
+    /// The ID of the broadcaster whose custom Cheermotes you want to get. Specify the broadcaster’s ID if you want to include the broadcaster’s Cheermotes in the response (not all broadcasters upload Cheermotes). If not specified, the response contains only global Cheermotes.
+    ///
+    /// If the broadcaster uploaded Cheermotes, the type field in the response is set to channel_custom.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub broadcaster_id: Option<String>,
+}
+
+impl Request for CheermotesRequest {
+    type Encoding = UrlParamEncoding;
+    type Response = CheermotesResponse;
+
+    fn path(&self) -> &str {
+        "/bits/cheermotes"
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CheermotesResponse {
+    /// The list of Cheermotes. The list is in ascending order by the order field’s value.
+    pub data: Vec<Cheermote>,
+}
+
+impl CheermotesResponse {
+    /// Cheermote with the prefix, prefixes are compared case-insensitively like in chat
+    pub fn find(&self, prefix: &str) -> Option<&Cheermote> {
+        self.data
+            .iter()
+            .find(|cheermote| cheermote.prefix.eq_ignore_ascii_case(prefix))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Cheermote {
+    /// The name portion of the Cheermote string that you use in chat to cheer Bits. The full Cheermote string is the concatenation of {prefix} + {number of Bits}. For example, if the prefix is “Cheer” and you want to cheer 100 Bits, the full Cheermote string is Cheer100. When the Cheermote string is entered in chat, Twitch converts it to the image associated with the Bits tier that was cheered.
+    pub prefix: String,
+
+    /// A list of tier levels that the Cheermote supports. Each tier identifies the range of Bits that you can cheer at that tier level and an image that graphically identifies the tier level.
+    pub tiers: Vec<CheermoteTier>,
+
+    /// The type of Cheermote. Possible values are:
+    ///
+    /// - global_first_party — A Twitch-defined Cheermote that is shown in the Bits card.
+    /// - global_third_party — A Twitch-defined Cheermote that is not shown in the Bits card.
+    /// - channel_custom — A broadcaster-defined Cheermote.
+    /// - display_only — Do not use; for internal use only.
+    /// - sponsored — A sponsor-defined Cheermote. When used, the sponsor adds additional Bits to the amount that the user cheered. For example, if the user cheered Terminator100, the broadcaster might receive 110 Bits, which includes the sponsor's 10 Bits contribution.
+    #[serde(rename = "type")]
+    pub type_: String,
+
+    /// The order that the Cheermotes are shown in the Bits card. The numbers may not be consecutive. For example, the numbers may jump from 1 to 7 to 13. The order numbers are unique within a Cheermote type (for example, global_first_party) but may not be unique amongst all Cheermotes in the response.
+    pub order: u32,
+
+    /// The date and time, in RFC3339 format, when this Cheermote was last updated.
+    pub last_updated: DateTime<Utc>,
+
+    /// A Boolean value that indicates whether this Cheermote provides a charitable contribution match during charity campaigns.
+    pub is_charitable: bool,
+}
+
+impl Cheermote {
+    /// The highest tier whose minimum is reached by the amount of bits
+    pub fn tier(&self, bits: u32) -> Option<&CheermoteTier> {
+        self.tiers
+            .iter()
+            .filter(|tier| tier.min_bits <= bits)
+            .max_by_key(|tier| tier.min_bits)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CheermoteTier {
+    /// The minimum number of Bits that you must cheer at this tier level. The maximum number of Bits that you can cheer at this level is determined by the required minimum Bits of the next tier level minus 1. For example, if min_bits is 1 and min_bits for the next tier is 100, the Bits range for this tier level is 1 through 99. The minimum Bits value of the last tier is the maximum number of Bits you can cheer using this Cheermote. For example, 10000.
+    pub min_bits: u32,
+
+    /// The tier level. Possible tiers are:
+    ///
+    /// - 1
+    /// - 100
+    /// - 500
+    /// - 1000
+    /// - 5000
+    /// - 10000
+    /// - 100000
+    pub id: String,
+
+    /// The hex code of the color associated with this tier level (for example, #979797).
+    pub color: String,
+
+    /// The animated and static image sets for the Cheermote. The dictionary of images is organized by theme, format, and size. The theme keys are dark and light. Each theme is a dictionary of formats: animated and static. Each format is a dictionary of sizes: 1, 1.5, 2, 3, and 4. The value of each size contains the URL to the image.
+    pub images: CheermoteImages,
+
+    /// A Boolean value that determines whether users can cheer at this tier level.
+    pub can_cheer: bool,
+
+    /// A Boolean value that determines whether this tier level is shown in the Bits card. Is true if this tier level is shown in the Bits card.
+    pub show_in_bits_card: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CheermoteImages {
+    pub dark: CheermoteThemeImages,
+    pub light: CheermoteThemeImages,
+}
+
+/// Image urls by size, e.g. `"1.5"`
+#[derive(Debug, Deserialize)]
+pub struct CheermoteThemeImages {
+    pub animated: IndexMap<String, String>,
+
+    #[serde(rename = "static")]
+    pub static_: IndexMap<String, String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tier(min_bits: u32, color: &str) -> serde_json::Value {
+        let images = serde_json::json!({
+            "animated": { "1": format!("https://example.com/dark/animated/{min_bits}/1.gif") },
+            "static": { "1": format!("https://example.com/dark/static/{min_bits}/1.png") },
+        });
+        serde_json::json!({
+            "min_bits": min_bits,
+            "id": min_bits.to_string(),
+            "color": color,
+            "images": { "dark": images, "light": images },
+            "can_cheer": true,
+            "show_in_bits_card": true,
+        })
+    }
+
+    #[test]
+    fn cheer_color_by_tier() {
+        let response: CheermotesResponse = serde_json::from_value(serde_json::json!({
+            "data": [
+                {
+                    "prefix": "Cheer",
+                    "tiers": [
+                        tier(1, "#979797"),
+                        tier(100, "#9c3ee8"),
+                        tier(1000, "#1db2a5"),
+                    ],
+                    "type": "global_first_party",
+                    "order": 1,
+                    "last_updated": "2018-05-22T00:06:04Z",
+                    "is_charitable": false,
+                },
+            ],
+        }))
+        .unwrap();
+
+        let cheer = response.find("cheer").unwrap();
+        assert_eq!(
+            cheer.tiers[0].images.dark.static_["1"],
+            "https://example.com/dark/static/1/1.png"
+        );
+        let color = |bits| cheer.tier(bits).map(|tier| tier.color.as_str());
+        assert_eq!(color(0), None);
+        assert_eq!(color(1), Some("#979797"));
+        assert_eq!(color(99), Some("#979797"));
+        assert_eq!(color(100), Some("#9c3ee8"));
+        assert_eq!(color(5000), Some("#1db2a5"));
+
+        assert!(response.find("Kappa").is_none());
+    }
+}
@@ -1,16 +1,30 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-use crate::client::{Request, UrlParamEncoding};
+use crate::{
+    client::{NoContent, PatchJsonEncoding, Request, UrlParamEncoding, limit_query_params},
+    error::Result,
+};
 
 #[derive(Debug, Serialize)]
 pub struct ChannelsRequest {
     /// The ID of the broadcaster whose channel you want to get. To specify more than one ID, include this parameter for each broadcaster you want to get. For example, broadcaster_id=1234&broadcaster_id=5678. You may specify a maximum of 100 IDs. The API ignores duplicate IDs and IDs that are not found.
-    broadcaster_id: String,
+    #[serde(skip)]
+    broadcaster_id: Vec<String>,
 }
 
 impl ChannelsRequest {
     pub fn id(id: String) -> Self {
-        Self { broadcaster_id: id }
+        Self {
+            broadcaster_id: vec![id],
+        }
+    }
+
+    /// Up to 100 channels by broadcaster id
+    pub fn ids(ids: impl IntoIterator<Item = String>) -> Result<Self> {
+        Ok(Self {
+            broadcaster_id: limit_query_params("broadcaster_id", ids)?,
+        })
     }
 }
 
@@ -18,8 +32,17 @@ impl Request for ChannelsRequest {
     type Encoding = UrlParamEncoding;
     type Response = ChannelsResponse;
 
-    fn url(&self) -> impl reqwest::IntoUrl {
-        twitch_helix!("/channels")
+    fn path(&self) -> &str {
+        "/channels"
+    }
+
+    fn modify_request(&self, req: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        let broadcaster_ids: Vec<_> = self
+            .broadcaster_id
+            .iter()
+            .map(|id| ("broadcaster_id", id))
+            .collect();
+        req.query(&broadcaster_ids)
     }
 }
 
@@ -36,9 +59,13 @@ impl ChannelsResponse {
         }
         self.data.pop()
     }
+
+    pub fn into_channels(self) -> Vec<Channel> {
+        self.data
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Channel {
     /// An ID that uniquely identifies the broadcaster.
     pub broadcaster_id: String,
@@ -73,3 +100,127 @@ pub struct Channel {
     /// Boolean flag indicating if the channel has branded content.
     pub is_branded_content: bool,
 }
+
+#[derive(Debug, Default, Serialize)]
+pub struct ModifyChannelInformationRequest {
+    /// The ID of the broadcaster whose channel you want to update. This ID must match the user ID in the user access token.
+    #[serde(skip)]
+    pub broadcaster_id: String,
+
+    /// The ID of the game that the user plays. The game is not updated if the ID isn’t a game ID that Twitch recognizes. To unset this field, use “0” or “” (an empty string).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub game_id: Option<String>,
+
+    /// The user’s preferred language. Set the value to an ISO 639-1 two-letter language code (for example, en for English). Set to “other” if the user’s preferred language is not a Twitch supported language. The language isn’t updated if the language code isn’t a Twitch supported language.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub broadcaster_language: Option<String>,
+
+    /// The title of the user’s stream. You may not set this field to an empty string.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+
+    /// A list of channel-defined tags to apply to the channel. To remove all tags from the channel, set tags to an empty array. Tags help identify the content that the channel streams.
+    ///
+    /// A channel may specify a maximum of 10 tags. Each tag is limited to a maximum of 25 characters and may not be an empty string or contain spaces or special characters. Tags are case insensitive. For readability, consider using camelCasing or PascalCasing.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+
+    /// List of labels that should be set as the Channel’s CCLs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_classification_labels: Option<Vec<ContentClassificationLabel>>,
+}
+
+impl Request for ModifyChannelInformationRequest {
+    type Encoding = PatchJsonEncoding;
+    type Response = NoContent;
+
+    fn path(&self) -> &str {
+        "/channels"
+    }
+
+    fn modify_request(&self, req: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        req.query(&[("broadcaster_id", &self.broadcaster_id)])
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ContentClassificationLabel {
+    /// ID of the Content Classification Labels that must be added/removed from the channel.
+    pub id: String,
+
+    /// Boolean flag indicating whether the label should be enabled (true) or disabled for the channel.
+    pub is_enabled: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChannelEditorsRequest {
+    /// The ID of the broadcaster that owns the channel. This ID must match the user ID in the access token.
+    pub broadcaster_id: String,
+}
+
+impl Request for ChannelEditorsRequest {
+    type Encoding = UrlParamEncoding;
+    type Response = ChannelEditorsResponse;
+
+    fn path(&self) -> &str {
+        "/channels/editors"
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChannelEditorsResponse {
+    /// A list of users that are editors for the specified broadcaster. The list is empty if the broadcaster doesn’t have editors.
+    pub data: Vec<ChannelEditor>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChannelEditor {
+    /// An ID that uniquely identifies a user with editor permissions.
+    pub user_id: String,
+
+    /// The user’s display name.
+    pub user_name: String,
+
+    /// The date and time, in RFC3339 format, when the user became one of the broadcaster’s editors.
+    pub created_at: DateTime<Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deserialize_channel_editors() {
+        let response: ChannelEditorsResponse = serde_json::from_str(
+            r#"{
+                "data": [
+                    {
+                        "user_id": "182891647",
+                        "user_name": "mauerbac",
+                        "created_at": "2019-02-15T21:19:50.380833Z"
+                    },
+                    {
+                        "user_id": "135093069",
+                        "user_name": "BlueLava",
+                        "created_at": "2018-03-07T16:28:29.872937Z"
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let [mauerbac, blue_lava] = &response.data[..] else {
+            panic!("expected two editors");
+        };
+        assert_eq!(mauerbac.user_id, "182891647");
+        assert_eq!(mauerbac.user_name, "mauerbac");
+        assert_eq!(
+            mauerbac.created_at.to_rfc3339(),
+            "2019-02-15T21:19:50.380833+00:00"
+        );
+        assert_eq!(blue_lava.user_name, "BlueLava");
+
+        let response: ChannelEditorsResponse = serde_json::from_str(r#"{"data":[]}"#).unwrap();
+        assert!(response.data.is_empty());
+    }
+}
@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 
-use crate::client::{JsonEncoding, NoContent, Request, UrlParamEncoding};
+use crate::client::{
+    JsonEncoding, NoContent, PatchJsonEncoding, PostUrlParamEncoding, Request, UrlParamEncoding,
+};
 
 #[derive(Debug, Serialize)]
 pub struct ChatColorsRequest {
@@ -20,8 +22,8 @@ impl Request for ChatColorsRequest {
     type Encoding = UrlParamEncoding;
     type Response = ChatColorsResponse;
 
-    fn url(&self) -> impl reqwest::IntoUrl {
-        twitch_helix!("/chat/color")
+    fn path(&self) -> &str {
+        "/chat/color"
     }
 }
 
@@ -75,8 +77,8 @@ impl Request for SendChatMessageRequest {
     type Encoding = JsonEncoding;
     type Response = SendChatMessagesResponse;
 
-    fn url(&self) -> impl reqwest::IntoUrl {
-        twitch_helix!("/chat/messages")
+    fn path(&self) -> &str {
+        "/chat/messages"
     }
 }
 
@@ -139,8 +141,8 @@ impl Request for SendChatAnnouncementRequest {
     type Encoding = JsonEncoding;
     type Response = NoContent;
 
-    fn url(&self) -> impl reqwest::IntoUrl {
-        twitch_helix!("/chat/announcements")
+    fn path(&self) -> &str {
+        "/chat/announcements"
     }
 
     fn modify_request(&self, req: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
@@ -169,3 +171,152 @@ pub enum ChatAnnouncementColor {
     #[serde(rename = "primary", alias = "PRIMARY")]
     Primary,
 }
+
+#[derive(Debug, Serialize)]
+pub struct SendShoutoutRequest {
+    /// The ID of the broadcaster that’s sending the Shoutout.
+    pub from_broadcaster_id: String,
+
+    /// The ID of the broadcaster that’s receiving the Shoutout.
+    pub to_broadcaster_id: String,
+
+    /// The ID of the broadcaster or a user that is one of the broadcaster’s moderators. This ID must match the user ID in the access token.
+    pub moderator_id: String,
+}
+
+impl Request for SendShoutoutRequest {
+    type Encoding = PostUrlParamEncoding;
+    type Response = NoContent;
+
+    fn path(&self) -> &str {
+        "/chat/shoutouts"
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChatSettingsRequest {
+    /// The ID of the broadcaster whose chat settings you want to get.
+    pub broadcaster_id: String,
+
+    /// The ID of the broadcaster or one of the broadcaster’s moderators. Required only to access the non_moderator_chat_delay or non_moderator_chat_delay_duration settings.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub moderator_id: Option<String>,
+}
+
+impl Request for ChatSettingsRequest {
+    type Encoding = UrlParamEncoding;
+    type Response = ChatSettingsResponse;
+
+    fn path(&self) -> &str {
+        "/chat/settings"
+    }
+}
+
+/// Only the fields that are set are changed, the other settings are kept
+#[derive(Debug, Default, Serialize)]
+pub struct UpdateChatSettingsRequest {
+    /// The ID of the broadcaster whose chat settings you want to update.
+    #[serde(skip)]
+    pub broadcaster_id: String,
+
+    /// The ID of a user that has permission to moderate the broadcaster’s chat room, or the broadcaster’s ID if they’re making the update. This ID must match the user ID in the user access token.
+    #[serde(skip)]
+    pub moderator_id: String,
+
+    /// A Boolean value that determines whether chat messages must contain only emotes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub emote_mode: Option<bool>,
+
+    /// A Boolean value that determines whether the broadcaster restricts the chat room to followers only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub follower_mode: Option<bool>,
+
+    /// The length of time, in minutes, that users must follow the broadcaster before being able to participate in the chat room. Set only if follower_mode is true. Possible values are: 0 (no restriction) through 129600 (3 months). The default is 0.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub follower_mode_duration: Option<u32>,
+
+    /// A Boolean value that determines whether the broadcaster limits how often users in the chat room are allowed to send messages.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slow_mode: Option<bool>,
+
+    /// The amount of time, in seconds, that users must wait between sending messages. Set only if slow_mode is true. Possible values are: 3 (3 second delay) through 120 (2 minute delay). The default is 30 seconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slow_mode_wait_time: Option<u32>,
+
+    /// A Boolean value that determines whether only users that subscribe to the broadcaster’s channel may talk in the chat room.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subscriber_mode: Option<bool>,
+
+    /// A Boolean value that determines whether the broadcaster requires users to post only unique messages in the chat room.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unique_chat_mode: Option<bool>,
+}
+
+impl Request for UpdateChatSettingsRequest {
+    type Encoding = PatchJsonEncoding;
+    type Response = ChatSettingsResponse;
+
+    fn path(&self) -> &str {
+        "/chat/settings"
+    }
+
+    fn modify_request(&self, req: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        req.query(&[
+            ("broadcaster_id", &self.broadcaster_id),
+            ("moderator_id", &self.moderator_id),
+        ])
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChatSettingsResponse {
+    data: Vec<ChatSettings>,
+}
+
+impl ChatSettingsResponse {
+    pub fn into_chat_settings(mut self) -> Option<ChatSettings> {
+        if self.data.len() > 1 {
+            unreachable!("mulitple chat settings returned");
+        }
+        self.data.pop()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChatSettings {
+    /// The ID of the broadcaster specified in the request.
+    pub broadcaster_id: String,
+
+    /// A Boolean value that determines whether chat messages must contain only emotes.
+    pub emote_mode: bool,
+
+    /// A Boolean value that determines whether the broadcaster restricts the chat room to followers only.
+    pub follower_mode: bool,
+
+    /// The length of time, in minutes, that users must follow the broadcaster before being able to participate in the chat room. Is null if follower_mode is false.
+    pub follower_mode_duration: Option<u32>,
+
+    /// The moderator’s ID. The response includes this field only if the request specifies a user access token that includes the moderator:read:chat_settings scope.
+    #[serde(default)]
+    pub moderator_id: Option<String>,
+
+    /// A Boolean value that determines whether the broadcaster adds a short delay before chat messages appear in the chat room.
+    #[serde(default)]
+    pub non_moderator_chat_delay: Option<bool>,
+
+    /// The amount of time, in seconds, that messages are delayed before appearing in chat. Is null if non_moderator_chat_delay is false.
+    #[serde(default)]
+    pub non_moderator_chat_delay_duration: Option<u32>,
+
+    /// A Boolean value that determines whether the broadcaster limits how often users in the chat room are allowed to send messages.
+    pub slow_mode: bool,
+
+    /// The amount of time, in seconds, that users must wait between sending messages. Is null if slow_mode is false.
+    pub slow_mode_wait_time: Option<u32>,
+
+    /// A Boolean value that determines whether only users that subscribe to the broadcaster’s channel may talk in the chat room.
+    pub subscriber_mode: bool,
+
+    /// A Boolean value that determines whether the broadcaster requires users to post only unique messages in the chat room.
+    pub unique_chat_mode: bool,
+}
@@ -1,22 +1,59 @@
-use reqwest::{IntoUrl, Method, RequestBuilder, Response, StatusCode, header};
+use std::{
+    env,
+    sync::Mutex,
+    time::{Duration, Instant, SystemTime},
+};
+
+use chrono::{DateTime, Utc};
+use futures::{Stream, TryStreamExt, stream};
+use reqwest::{
+    Certificate, Method, Proxy, RequestBuilder, Response, StatusCode,
+    header::{self, HeaderMap},
+};
 use serde::{Serialize, de::DeserializeOwned};
+use url::Url;
 
 use crate::{
-    auth::TokenManager,
+    auth::{TokenManager, ValidateResponse},
     error::{ApiError, ErrorResponse, Result},
+    pagination::PaginatedRequest,
     secret::Secret,
 };
 
 pub struct AuthenticatedClient {
     client: Client,
     token_manager: TokenManager,
+    token_refreshed_at: Option<DateTime<Utc>>,
 }
 
 impl AuthenticatedClient {
+    /// Send the request for every page and return the items of all pages
+    ///
+    /// The stream ends after the last page or after an empty page.
+    pub fn paginate<T>(&mut self, req: T) -> impl Stream<Item = Result<T::Item>>
+    where
+        T: PaginatedRequest,
+    {
+        stream::try_unfold((self, Some(req)), |(client, req)| async move {
+            let Some(mut req) = req else {
+                return Ok::<_, ApiError>(None);
+            };
+            let (items, next) = next_page(client.send(&req).await?, &mut req);
+            Ok(Some((items, (client, next.then_some(req)))))
+        })
+        .try_flatten()
+    }
+
     pub async fn send<T>(&mut self, req: &T) -> Result<T::Response>
     where
         T: Request,
     {
+        // refresh before the access token expires, the unauthorized retry below is the fallback
+        if self.token_manager.expires_soon() {
+            self.token_manager.update(&mut self.client).await?;
+            self.token_refreshed_at = Some(Utc::now());
+        }
+
         match self
             .client
             .send_inner(
@@ -32,6 +69,7 @@ impl AuthenticatedClient {
                 if res.status == StatusCode::UNAUTHORIZED =>
             {
                 self.token_manager.update(&mut self.client).await?;
+                self.token_refreshed_at = Some(Utc::now());
                 self.client
                     .send_inner(
                         req,
@@ -45,10 +83,108 @@ impl AuthenticatedClient {
             res => res,
         }
     }
+
+    /// Validate the access token and refresh it if it is invalid or expires soon
+    ///
+    /// Avoids sending the first request with an expired access token.
+    pub async fn ensure_valid_token(&mut self) -> Result<()> {
+        if self.token_manager.ensure_valid(&mut self.client).await? {
+            self.token_refreshed_at = Some(Utc::now());
+        }
+        Ok(())
+    }
+
+    /// Check the access token and return its owner and scopes
+    pub async fn validate_token(&self) -> Result<ValidateResponse> {
+        self.token_manager.validate(&self.client).await
+    }
+
+    pub fn token_manager_mut(&mut self) -> &mut TokenManager {
+        &mut self.token_manager
+    }
+
+    /// Rate limit reported by the last response
+    pub fn last_rate_limit(&self) -> Option<RateLimit> {
+        self.client.last_rate_limit()
+    }
+
+    /// Time of the last access token refresh, subscriptions created before may be revoked
+    pub fn token_refreshed_at(&self) -> Option<DateTime<Utc>> {
+        self.token_refreshed_at
+    }
 }
 
 pub struct Client {
     client: reqwest::Client,
+    base_urls: BaseUrls,
+    rate_limit: Mutex<Option<RateLimit>>,
+    max_rate_limit_retries: u32,
+    timeout: Duration,
+    max_retries: u32,
+    retry_backoff: Duration,
+}
+
+/// Twitch API a request is sent to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Api {
+    /// The helix API, `https://api.twitch.tv/helix`
+    Helix,
+
+    /// The authentication API, `https://id.twitch.tv`
+    Id,
+}
+
+/// Base urls the request paths are appended to, e.g. to send requests to a mock server
+#[derive(Debug, Clone)]
+pub struct BaseUrls {
+    pub helix: Url,
+    pub id: Url,
+}
+
+impl Default for BaseUrls {
+    fn default() -> Self {
+        Self {
+            helix: Url::parse("https://api.twitch.tv/helix").unwrap(),
+            id: Url::parse("https://id.twitch.tv").unwrap(),
+        }
+    }
+}
+
+impl BaseUrls {
+    pub fn get(&self, api: Api) -> &Url {
+        match api {
+            Api::Helix => &self.helix,
+            Api::Id => &self.id,
+        }
+    }
+}
+
+/// Token bucket state reported by the `Ratelimit-*` response headers
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimit {
+    /// The rate at which points are added to the bucket.
+    pub limit: u32,
+
+    /// The number of points remaining in the bucket.
+    pub remaining: u32,
+
+    /// The time when the bucket is reset to full.
+    pub reset: SystemTime,
+}
+
+impl RateLimit {
+    fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        fn header<T: std::str::FromStr>(headers: &HeaderMap, name: &str) -> Option<T> {
+            headers.get(name)?.to_str().ok()?.parse().ok()
+        }
+
+        Some(Self {
+            limit: header(headers, "Ratelimit-Limit")?,
+            remaining: header(headers, "Ratelimit-Remaining")?,
+            reset: SystemTime::UNIX_EPOCH
+                + Duration::from_secs(header(headers, "Ratelimit-Reset")?),
+        })
+    }
 }
 
 impl Default for Client {
@@ -58,9 +194,52 @@ impl Default for Client {
 }
 
 impl Client {
+    const DEFAULT_MAX_RATE_LIMIT_RETRIES: u32 = 3;
+
+    /// Longest wait for the rate limit to reset before a request is retried
+    const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(60);
+
+    const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+    const DEFAULT_MAX_RETRIES: u32 = 2;
+
+    /// Wait before the first retry of a failed request, doubled for every further retry
+    const RETRY_BACKOFF: Duration = Duration::from_secs(1);
+
     pub fn new() -> Self {
         Self {
             client: reqwest::Client::new(),
+            base_urls: BaseUrls::default(),
+            rate_limit: Mutex::new(None),
+            max_rate_limit_retries: Self::DEFAULT_MAX_RATE_LIMIT_RETRIES,
+            timeout: Self::DEFAULT_TIMEOUT,
+            max_retries: Self::DEFAULT_MAX_RETRIES,
+            retry_backoff: Self::RETRY_BACKOFF,
+        }
+    }
+
+    /// Send the requests to the given base urls instead of the twitch servers
+    pub fn with_base_url(helix: Url, id: Url) -> Self {
+        Self {
+            base_urls: BaseUrls { helix, id },
+            ..Self::new()
+        }
+    }
+
+    /// Configure the underlying http client (e.g. proxy and root certificates)
+    ///
+    /// The proxy defaults to the `HTTPS_PROXY` environment variable.
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder {
+            proxy: env::var("HTTPS_PROXY")
+                .or_else(|_| env::var("https_proxy"))
+                .ok()
+                .filter(|proxy| !proxy.is_empty()),
+            root_certificates: Vec::new(),
+            base_urls: BaseUrls::default(),
+            max_rate_limit_retries: Self::DEFAULT_MAX_RATE_LIMIT_RETRIES,
+            timeout: Self::DEFAULT_TIMEOUT,
+            max_retries: Self::DEFAULT_MAX_RETRIES,
+            retry_backoff: Self::RETRY_BACKOFF,
         }
     }
 
@@ -68,6 +247,7 @@ impl Client {
         AuthenticatedClient {
             client: self,
             token_manager,
+            token_refreshed_at: None,
         }
     }
 
@@ -75,6 +255,11 @@ impl Client {
         Ok(self.authenticated(TokenManager::from_env()?))
     }
 
+    /// Authenticate with the tokens of the profile instead of the selected one
+    pub fn authenticated_from_profile(self, profile: String) -> Result<AuthenticatedClient> {
+        Ok(self.authenticated(TokenManager::from_profile(Some(profile))?))
+    }
+
     pub async fn send<T>(&self, req: &T) -> Result<T::Response>
     where
         T: Request,
@@ -82,6 +267,38 @@ impl Client {
         self.send_inner(req, None).await
     }
 
+    /// Rate limit reported by the last response
+    pub fn last_rate_limit(&self) -> Option<RateLimit> {
+        *self.rate_limit.lock().unwrap()
+    }
+
+    pub fn base_urls(&self) -> &BaseUrls {
+        &self.base_urls
+    }
+
+    /// Send the request for every page and return the items of all pages
+    ///
+    /// The stream ends after the last page or after an empty page.
+    pub fn paginate<T>(&self, req: T) -> impl Stream<Item = Result<T::Item>>
+    where
+        T: PaginatedRequest,
+    {
+        stream::try_unfold((self, Some(req)), |(client, req)| async move {
+            let Some(mut req) = req else {
+                return Ok::<_, ApiError>(None);
+            };
+            let (items, next) = next_page(client.send(&req).await?, &mut req);
+            Ok(Some((items, (client, next.then_some(req)))))
+        })
+        .try_flatten()
+    }
+
+    /// Wait before a request that failed because of a network error or a gateway is retried
+    async fn retry_backoff(&self, failed_attempts: &mut u32) {
+        tokio::time::sleep(retry_delay(self.retry_backoff, *failed_attempts)).await;
+        *failed_attempts += 1;
+    }
+
     async fn send_inner<T>(
         &self,
         req: &T,
@@ -90,20 +307,109 @@ impl Client {
     where
         T: Request,
     {
-        let res = self
-            .client
-            .request(T::Encoding::METHOD, req.url())
-            .encode(req)
-            .access_token_and_client_id(access_token_and_client_id)
-            .send()
-            .await
-            .map_err(ApiError::SendRequest)?;
+        let mut retries = 0;
+        let mut failed_attempts = 0;
+        // a post may have been handled even if the response was lost, e.g. a duplicate message
+        let max_retries = if is_retryable::<T>() {
+            self.max_retries
+        } else {
+            0
+        };
+        let res = loop {
+            let started_at = Instant::now();
+            let res = match self
+                .client
+                .request(T::Encoding::METHOD, req.url(&self.base_urls))
+                .timeout(self.timeout)
+                .encode(req)
+                .access_token_and_client_id(access_token_and_client_id)
+                .send()
+                .await
+            {
+                Ok(res) => res,
+                Err(err) if err.is_timeout() || err.is_connect() => {
+                    if failed_attempts < max_retries {
+                        self.retry_backoff(&mut failed_attempts).await;
+                        continue;
+                    }
+                    if err.is_timeout() {
+                        return Err(ApiError::Timeout(self.timeout));
+                    }
+                    return Err(ApiError::SendRequest(err));
+                }
+                Err(err) => return Err(ApiError::SendRequest(err)),
+            };
+
+            let path = req.path().to_string();
+            metrics::counter!(
+                "twitch_api_requests_total",
+                "path" => path.clone(),
+                "status" => res.status().as_u16().to_string(),
+            )
+            .increment(1);
+            metrics::histogram!("twitch_api_request_duration_seconds", "path" => path)
+                .record(started_at.elapsed());
+
+            let rate_limit = RateLimit::from_headers(res.headers());
+            if let Some(rate_limit) = rate_limit {
+                *self.rate_limit.lock().unwrap() = Some(rate_limit);
+            }
+
+            // e.g. a restarting load balancer, the request was not handled
+            if matches!(
+                res.status(),
+                StatusCode::BAD_GATEWAY
+                    | StatusCode::SERVICE_UNAVAILABLE
+                    | StatusCode::GATEWAY_TIMEOUT
+            ) && failed_attempts < max_retries
+            {
+                self.retry_backoff(&mut failed_attempts).await;
+                continue;
+            }
+
+            if res.status() != StatusCode::TOO_MANY_REQUESTS
+                || retries >= self.max_rate_limit_retries
+            {
+                break res;
+            }
+            retries += 1;
+
+            // wait until the bucket is refilled, the reset is only precise to the second
+            let wait = rate_limit
+                .and_then(|rate_limit| rate_limit.reset.duration_since(SystemTime::now()).ok())
+                .unwrap_or_default()
+                + Duration::from_secs(1);
+            tokio::time::sleep(wait.min(Self::MAX_RATE_LIMIT_WAIT)).await;
+        };
 
         let status = res.status();
 
         if status.is_success() {
             T::Response::decode(res).await
         } else if status.is_client_error() || status.is_server_error() {
+            let is_json = res
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .and_then(|content_type| content_type.to_str().ok())
+                .is_some_and(|content_type| content_type.starts_with("application/json"));
+            if !is_json {
+                let body = res.text().await.unwrap_or_default();
+                let snippet: String = body.trim().chars().take(200).collect();
+                // e.g. a html maintenance page
+                if status.is_server_error() {
+                    return Err(ApiError::ServiceUnavailable(status, snippet));
+                }
+                // e.g. a 401 of a proxy, still handled like an error response of the api
+                return Err(ApiError::ErrorResponse(
+                    status,
+                    ErrorResponse {
+                        status,
+                        message: snippet,
+                        data: Default::default(),
+                    },
+                ));
+            }
+
             let res = res
                 .json::<ErrorResponse>()
                 .await
@@ -115,6 +421,115 @@ impl Client {
     }
 }
 
+pub struct ClientBuilder {
+    proxy: Option<String>,
+    root_certificates: Vec<Certificate>,
+    base_urls: BaseUrls,
+    max_rate_limit_retries: u32,
+    timeout: Duration,
+    max_retries: u32,
+    retry_backoff: Duration,
+}
+
+impl ClientBuilder {
+    /// Send all requests through the given proxy url
+    pub fn proxy(mut self, proxy: impl Into<String>) -> Self {
+        self.proxy = Some(proxy.into());
+        self
+    }
+
+    /// Do not use a proxy, even if one is set in the environment
+    pub fn no_proxy(mut self) -> Self {
+        self.proxy = None;
+        self
+    }
+
+    /// Trust an additional root certificate
+    pub fn root_certificate(mut self, certificate: Certificate) -> Self {
+        self.root_certificates.push(certificate);
+        self
+    }
+
+    /// Trust an additional PEM encoded root certificate
+    pub fn root_certificate_pem(self, pem: &[u8]) -> Result<Self> {
+        let certificate = Certificate::from_pem(pem).map_err(ApiError::BuildClient)?;
+        Ok(self.root_certificate(certificate))
+    }
+
+    /// Send the requests to the given base urls instead of the twitch servers
+    pub fn base_url(mut self, helix: Url, id: Url) -> Self {
+        self.base_urls = BaseUrls { helix, id };
+        self
+    }
+
+    /// Retry requests rejected because of the rate limit up to `retries` times (default 3)
+    ///
+    /// Each retry waits until the rate limit is reset, `0` returns the error immediately.
+    pub fn max_rate_limit_retries(mut self, retries: u32) -> Self {
+        self.max_rate_limit_retries = retries;
+        self
+    }
+
+    /// Abort requests without a response after `timeout` (default 30s)
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Retry requests that failed because of a timeout, a connection error or a bad gateway up to
+    /// `retries` times (default 2)
+    ///
+    /// Only idempotent requests (`GET`, `PUT` and `DELETE`) and the token requests of the
+    /// authentication API are retried.
+    pub fn max_retries(mut self, retries: u32) -> Self {
+        self.max_retries = retries;
+        self
+    }
+
+    /// Wait `backoff` before the first retry of a failed request (default 1s)
+    ///
+    /// The wait is doubled for every further retry.
+    pub fn retry_backoff(mut self, backoff: Duration) -> Self {
+        self.retry_backoff = backoff;
+        self
+    }
+
+    pub fn build(self) -> Result<Client> {
+        let mut builder = reqwest::Client::builder();
+        // the proxy of the environment is already read by `Client::builder`
+        builder = match self.proxy {
+            Some(proxy) => builder.proxy(Proxy::all(proxy).map_err(ApiError::BuildClient)?),
+            None => builder.no_proxy(),
+        };
+        for certificate in self.root_certificates {
+            builder = builder.add_root_certificate(certificate);
+        }
+        Ok(Client {
+            client: builder.build().map_err(ApiError::BuildClient)?,
+            base_urls: self.base_urls,
+            rate_limit: Mutex::new(None),
+            max_rate_limit_retries: self.max_rate_limit_retries,
+            timeout: self.timeout,
+            max_retries: self.max_retries,
+            retry_backoff: self.retry_backoff,
+        })
+    }
+}
+
+/// Whether the request can be sent again after it failed without a response
+fn is_retryable<T: Request>() -> bool {
+    T::API == Api::Id
+        || matches!(
+            T::Encoding::METHOD,
+            Method::GET | Method::PUT | Method::DELETE
+        )
+}
+
+/// Wait before the retry after `failed_attempts` failed attempts
+fn retry_delay(backoff: Duration, failed_attempts: u32) -> Duration {
+    backoff.saturating_mul(2u32.saturating_pow(failed_attempts))
+}
+
 trait RequestBuilderExt {
     fn encode<T>(self, req: &T) -> Self
     where
@@ -147,11 +562,41 @@ impl RequestBuilderExt for RequestBuilder {
     }
 }
 
+/// Split the response into a stream of its items and move the request to the next page
+///
+/// Returns whether there is a next page.
+fn next_page<T>(
+    response: T::Response,
+    req: &mut T,
+) -> (impl Stream<Item = Result<T::Item>> + use<T>, bool)
+where
+    T: PaginatedRequest,
+{
+    let (items, pagination) = T::into_page(response);
+    let next = match pagination.cursor {
+        Some(cursor) if !items.is_empty() => {
+            req.set_after(cursor);
+            true
+        }
+        _ => false,
+    };
+    (stream::iter(items.into_iter().map(Ok)), next)
+}
+
 pub trait Request: Serialize {
     type Encoding: Encoding;
     type Response: DecodeResponse;
 
-    fn url(&self) -> impl IntoUrl;
+    /// Api the path belongs to
+    const API: Api = Api::Helix;
+
+    /// Path relative to the base url of the api, e.g. `/users`
+    fn path(&self) -> &str;
+
+    fn url(&self, base_urls: &BaseUrls) -> String {
+        let base_url = base_urls.get(Self::API).as_str();
+        format!("{}{}", base_url.trim_end_matches('/'), self.path())
+    }
 
     fn modify_request(&self, req: RequestBuilder) -> RequestBuilder {
         req
@@ -181,6 +626,24 @@ where
     }
 }
 
+/// Collect the values of a repeated query parameter, at most 100 are accepted by twitch
+pub(crate) fn limit_query_params(
+    name: &'static str,
+    values: impl IntoIterator<Item = String>,
+) -> Result<Vec<String>> {
+    const MAX_QUERY_PARAMS: usize = 100;
+
+    let values: Vec<_> = values.into_iter().collect();
+    if values.len() > MAX_QUERY_PARAMS {
+        return Err(ApiError::TooManyQueryParams(
+            name,
+            values.len(),
+            MAX_QUERY_PARAMS,
+        ));
+    }
+    Ok(values)
+}
+
 pub enum UrlParamEncoding {}
 
 impl Encoding for UrlParamEncoding {
@@ -201,6 +664,16 @@ impl Encoding for DeleteUrlParamEncoding {
     }
 }
 
+pub enum PostUrlParamEncoding {}
+
+impl Encoding for PostUrlParamEncoding {
+    const METHOD: Method = Method::POST;
+
+    fn encode(builder: RequestBuilder, req: &impl Serialize) -> RequestBuilder {
+        builder.query(req)
+    }
+}
+
 pub enum FormEncoding {}
 
 impl Encoding for FormEncoding {
@@ -211,6 +684,36 @@ impl Encoding for FormEncoding {
     }
 }
 
+pub enum PutJsonEncoding {}
+
+impl Encoding for PutJsonEncoding {
+    const METHOD: Method = Method::PUT;
+
+    fn encode(builder: RequestBuilder, req: &impl Serialize) -> RequestBuilder {
+        builder.json(req)
+    }
+}
+
+pub enum DeleteJsonEncoding {}
+
+impl Encoding for DeleteJsonEncoding {
+    const METHOD: Method = Method::DELETE;
+
+    fn encode(builder: RequestBuilder, req: &impl Serialize) -> RequestBuilder {
+        builder.json(req)
+    }
+}
+
+pub enum PatchJsonEncoding {}
+
+impl Encoding for PatchJsonEncoding {
+    const METHOD: Method = Method::PATCH;
+
+    fn encode(builder: RequestBuilder, req: &impl Serialize) -> RequestBuilder {
+        builder.json(req)
+    }
+}
+
 pub enum JsonEncoding {}
 
 impl Encoding for JsonEncoding {
@@ -225,9 +728,335 @@ pub struct NoContent(());
 
 impl DecodeResponse for NoContent {
     async fn decode(res: Response) -> Result<Self> {
-        if res.status() != StatusCode::NO_CONTENT {
+        // some endpoints (e.g. token revocation) answer with an empty 200 instead of 204
+        if !matches!(res.status(), StatusCode::OK | StatusCode::NO_CONTENT) {
             return Err(ApiError::UnexpectedApiStatus(res.status()));
         }
         Ok(Self(()))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+        sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        },
+        thread,
+    };
+
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Get;
+
+    impl Request for Get {
+        type Encoding = UrlParamEncoding;
+        type Response = NoContent;
+
+        fn path(&self) -> &str {
+            "/test"
+        }
+    }
+
+    #[derive(Serialize)]
+    struct Post;
+
+    impl Request for Post {
+        type Encoding = JsonEncoding;
+        type Response = NoContent;
+
+        fn path(&self) -> &str {
+            "/test"
+        }
+    }
+
+    #[derive(Serialize)]
+    struct Token;
+
+    impl Request for Token {
+        type Encoding = FormEncoding;
+        type Response = NoContent;
+
+        const API: Api = Api::Id;
+
+        fn path(&self) -> &str {
+            "/oauth2/token"
+        }
+    }
+
+    /// Answer every request with the next status, returns the base url and the request count
+    fn serve(statuses: &'static [u16]) -> (Url, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+        let requests = Arc::new(AtomicUsize::new(0));
+        let count = requests.clone();
+        thread::spawn(move || {
+            for (stream, status) in listener.incoming().zip(statuses) {
+                let mut stream = BufReader::new(stream.unwrap());
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    stream.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    let line = line.to_ascii_lowercase();
+                    if let Some(value) = line.strip_prefix("content-length:") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+                stream.read_exact(&mut vec![0; content_length]).unwrap();
+                count.fetch_add(1, Ordering::SeqCst);
+                if *status == 429 {
+                    let reset = SystemTime::now()
+                        .duration_since(SystemTime::UNIX_EPOCH)
+                        .unwrap()
+                        .as_secs();
+                    let body = r#"{"status":429,"message":"Too Many Requests"}"#;
+                    write!(
+                        stream.get_mut(),
+                        "HTTP/1.1 429 Too Many Requests\r\n\
+                        ratelimit-limit: 800\r\n\
+                        ratelimit-remaining: 0\r\n\
+                        ratelimit-reset: {reset}\r\n\
+                        content-type: application/json\r\n\
+                        content-length: {}\r\n\
+                        connection: close\r\n\r\n{body}",
+                        body.len(),
+                    )
+                    .unwrap();
+                    continue;
+                }
+                // a maintenance page instead of a json error
+                let body = match status {
+                    503 => "<html><body>down for maintenance</body></html>",
+                    _ => "",
+                };
+                write!(
+                    stream.get_mut(),
+                    "HTTP/1.1 {status} Status\r\n\
+                    content-type: text/html\r\n\
+                    content-length: {}\r\n\
+                    connection: close\r\n\r\n{body}",
+                    body.len(),
+                )
+                .unwrap();
+            }
+        });
+        (url, requests)
+    }
+
+    fn send<T: Request>(req: T, statuses: &'static [u16]) -> (Result<T::Response>, usize) {
+        let (res, requests, _) = send_with(|builder| builder, req, statuses);
+        (res, requests)
+    }
+
+    /// Send the request with a client configured by `configure`, the client is returned
+    fn send_with<T: Request>(
+        configure: impl FnOnce(ClientBuilder) -> ClientBuilder,
+        req: T,
+        statuses: &'static [u16],
+    ) -> (Result<T::Response>, usize, Client) {
+        let (url, requests) = serve(statuses);
+        let client = configure(
+            Client::builder()
+                .no_proxy()
+                .base_url(url.clone(), url)
+                .retry_backoff(Duration::from_millis(1)),
+        )
+        .build()
+        .unwrap();
+        let res = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(client.send(&req));
+        (res, requests.load(Ordering::SeqCst), client)
+    }
+
+    #[test]
+    fn requests_are_sent_through_the_proxy() {
+        let (proxy, requests) = serve(&[204]);
+        let unreachable = Url::parse("http://twitch.invalid").unwrap();
+        let client = Client::builder()
+            .proxy(proxy)
+            .base_url(unreachable.clone(), unreachable)
+            .max_retries(0)
+            .build()
+            .unwrap();
+        let res = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(client.send(&Get));
+        assert!(res.is_ok(), "{:?}", res.err());
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn rate_limit_from_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("Ratelimit-Limit", "800".parse().unwrap());
+        headers.insert("Ratelimit-Remaining", "799".parse().unwrap());
+        headers.insert("Ratelimit-Reset", "1700000000".parse().unwrap());
+        let rate_limit = RateLimit::from_headers(&headers).unwrap();
+        assert_eq!(rate_limit.limit, 800);
+        assert_eq!(rate_limit.remaining, 799);
+        assert_eq!(
+            rate_limit.reset,
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000)
+        );
+
+        headers.insert("Ratelimit-Reset", "soon".parse().unwrap());
+        assert!(RateLimit::from_headers(&headers).is_none());
+        headers.remove("Ratelimit-Reset");
+        assert!(RateLimit::from_headers(&headers).is_none());
+    }
+
+    #[test]
+    fn rate_limited_request_is_retried() {
+        let (res, requests, client) = send_with(|builder| builder, Post, &[429, 204]);
+        assert!(res.is_ok());
+        assert_eq!(requests, 2);
+        assert_eq!(client.last_rate_limit().unwrap().remaining, 0);
+    }
+
+    #[test]
+    fn rate_limit_error_after_retries() {
+        let (res, requests, _) = send_with(
+            |builder| builder.max_rate_limit_retries(0),
+            Get,
+            &[429, 204],
+        );
+        assert!(matches!(
+            res,
+            Err(ApiError::ErrorResponse(StatusCode::TOO_MANY_REQUESTS, _))
+        ));
+        assert_eq!(requests, 1);
+    }
+
+    #[test]
+    fn retry_delay_doubles() {
+        let backoff = Duration::from_secs(1);
+        assert_eq!(retry_delay(backoff, 0), Duration::from_secs(1));
+        assert_eq!(retry_delay(backoff, 1), Duration::from_secs(2));
+        assert_eq!(retry_delay(backoff, 3), Duration::from_secs(8));
+        assert_eq!(retry_delay(backoff, 100), backoff * u32::MAX);
+    }
+
+    #[derive(Serialize)]
+    struct Body<E> {
+        id: u32,
+        #[serde(skip)]
+        encoding: std::marker::PhantomData<E>,
+    }
+
+    impl<E: Encoding> Request for Body<E> {
+        type Encoding = E;
+        type Response = NoContent;
+
+        fn path(&self) -> &str {
+            "/test"
+        }
+    }
+
+    /// Method, content type and body of the request built for an encoding
+    fn encoded<E: Encoding>() -> (Method, String, String) {
+        let req = Body::<E> {
+            id: 42,
+            encoding: std::marker::PhantomData,
+        };
+        let request = reqwest::Client::new()
+            .request(E::METHOD, "http://localhost/test")
+            .encode(&req)
+            .build()
+            .unwrap();
+        let content_type = request.headers()[header::CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let body = request.body().and_then(|body| body.as_bytes()).unwrap();
+        (
+            request.method().clone(),
+            content_type,
+            String::from_utf8(body.to_vec()).unwrap(),
+        )
+    }
+
+    #[test]
+    fn json_encodings() {
+        for (encoded, method) in [
+            (encoded::<JsonEncoding>(), Method::POST),
+            (encoded::<PutJsonEncoding>(), Method::PUT),
+            (encoded::<PatchJsonEncoding>(), Method::PATCH),
+            (encoded::<DeleteJsonEncoding>(), Method::DELETE),
+        ] {
+            assert_eq!(
+                encoded,
+                (method, "application/json".into(), r#"{"id":42}"#.into())
+            );
+        }
+    }
+
+    #[test]
+    fn retryable_requests() {
+        assert!(is_retryable::<Get>());
+        assert!(is_retryable::<Token>());
+        assert!(!is_retryable::<Post>());
+    }
+
+    #[test]
+    fn get_is_retried_after_bad_gateway() {
+        let (res, requests) = send(Get, &[502, 503, 204]);
+        assert!(res.is_ok());
+        assert_eq!(requests, 3);
+    }
+
+    #[test]
+    fn token_refresh_is_retried() {
+        let (res, requests) = send(Token, &[503, 200]);
+        assert!(res.is_ok());
+        assert_eq!(requests, 2);
+    }
+
+    #[test]
+    fn post_is_not_retried() {
+        let (res, requests) = send(Post, &[503, 204]);
+        assert!(matches!(
+            res,
+            Err(ApiError::ServiceUnavailable(
+                StatusCode::SERVICE_UNAVAILABLE,
+                _
+            ))
+        ));
+        assert_eq!(requests, 1);
+    }
+
+    #[test]
+    fn html_error_page() {
+        let (res, _) = send(Post, &[503]);
+        let Err(ApiError::ServiceUnavailable(StatusCode::SERVICE_UNAVAILABLE, snippet)) = res
+        else {
+            panic!("not a service unavailable error: {:?}", res.err());
+        };
+        assert_eq!(snippet, "<html><body>down for maintenance</body></html>");
+
+        // client errors without a json body still trigger the token refresh
+        let (res, _) = send(Get, &[401]);
+        assert!(res.err().unwrap().is_unauthorized());
+    }
+
+    #[test]
+    fn retries_are_limited() {
+        let (res, requests) = send(Get, &[502, 502, 502, 204]);
+        assert!(matches!(
+            res,
+            Err(ApiError::ServiceUnavailable(StatusCode::BAD_GATEWAY, _))
+        ));
+        assert_eq!(requests, 3);
+    }
+}
@@ -0,0 +1,88 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::client::{Request, UrlParamEncoding};
+
+#[derive(Debug, Serialize)]
+pub struct ClipsRequest {
+    /// An ID that identifies the clip to get. To specify more than one ID, include this parameter for each clip you want to get. For example, id=foo&id=bar. You may specify a maximum of 100 IDs. The API ignores duplicate IDs and IDs that aren’t found.
+    pub id: String,
+}
+
+impl Request for ClipsRequest {
+    type Encoding = UrlParamEncoding;
+    type Response = ClipsResponse;
+
+    fn path(&self) -> &str {
+        "/clips"
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClipsResponse {
+    /// The list of video clips. For clips returned by game_id or broadcaster_id, the list is in descending order by view count. For lists returned by id, the list is in the same order as the input IDs.
+    data: Vec<Clip>,
+}
+
+impl ClipsResponse {
+    pub fn into_clip(mut self) -> Option<Clip> {
+        if self.data.len() > 1 {
+            unreachable!("mulitple clips returned");
+        }
+        self.data.pop()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Clip {
+    /// An ID that uniquely identifies the clip.
+    pub id: String,
+
+    /// A URL to the clip.
+    pub url: String,
+
+    /// A URL that you can use in an iframe to embed the clip (see Embedding Video and Clips).
+    pub embed_url: String,
+
+    /// An ID that identifies the broadcaster that the video was clipped from.
+    pub broadcaster_id: String,
+
+    /// The broadcaster’s display name.
+    pub broadcaster_name: String,
+
+    /// An ID that identifies the user that created the clip.
+    pub creator_id: String,
+
+    /// The user’s display name.
+    pub creator_name: String,
+
+    /// An ID that identifies the video that the clip came from. This field contains an empty string if the video is not available.
+    pub video_id: String,
+
+    /// The ID of the game that was being played when the clip was created.
+    pub game_id: String,
+
+    /// The ISO 639-1 two-letter language code that the broadcaster broadcasts in. For example, en for English. The value is other if the broadcaster uses a language that Twitch doesn’t support.
+    pub language: String,
+
+    /// The title of the clip.
+    pub title: String,
+
+    /// The number of times the clip has been viewed.
+    pub view_count: u32,
+
+    /// The date and time of when the clip was created. The date and time is in RFC3339 format.
+    pub created_at: DateTime<Utc>,
+
+    /// A URL to a thumbnail image of the clip.
+    pub thumbnail_url: String,
+
+    /// The length of the clip, in seconds. Precision is 0.1.
+    pub duration: f32,
+
+    /// The zero-based offset, in seconds, to where the clip starts in the video (VOD). Is null if the video is not available or hasn’t been created yet from the live stream (see video_id).
+    pub vod_offset: Option<u32>,
+
+    /// A Boolean value that indicates if the clip is featured or not.
+    pub is_featured: bool,
+}
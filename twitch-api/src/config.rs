@@ -1,8 +1,10 @@
 use std::{
-    env, fs,
+    env, fs, io,
     path::{Path, PathBuf},
+    sync::OnceLock,
 };
 
+use chrono::{DateTime, Utc};
 use serde::{
     Deserialize, Serialize,
     de::{DeserializeOwned, Error as _},
@@ -18,6 +20,14 @@ use crate::{
 #[serde(deny_unknown_fields)]
 pub struct ClientConfig {
     pub client_id: Secret,
+
+    /// Only needed for the authorization code flow
+    #[serde(default)]
+    pub client_secret: Option<Secret>,
+
+    /// Only needed for the authorization code flow, must match a redirect url of the app
+    #[serde(default)]
+    pub redirect_uri: Option<String>,
 }
 
 impl ClientConfig {
@@ -26,7 +36,17 @@ impl ClientConfig {
     }
 
     pub(crate) fn load_from_env() -> Result<Self> {
-        Self::load(&from_env("TWITCH_CLIENT_CONFIG", "client-config.toml"))
+        Self::load_for_profile(profile().as_deref())
+    }
+
+    /// The client config of the profile, or the shared `client-config.toml` if the profile has none
+    pub fn load_for_profile(profile: Option<&str>) -> Result<Self> {
+        let path = from_env("TWITCH_CLIENT_CONFIG", "client-config", profile);
+        if !path.exists() && profile.is_some() && env::var_os("TWITCH_CLIENT_CONFIG").is_none() {
+            // the accounts of the profiles usually use the same app
+            return Self::load(Path::new("client-config.toml"));
+        }
+        Self::load(&path)
     }
 }
 
@@ -35,6 +55,10 @@ impl ClientConfig {
 pub struct TokenConfig {
     pub access_token: Secret,
     pub refresh_token: Secret,
+
+    /// Expiry of the access token, unknown for tokens saved by older versions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
 }
 
 impl TokenConfig {
@@ -43,11 +67,19 @@ impl TokenConfig {
     }
 
     fn env() -> PathBuf {
-        from_env("TWITCH_TOKEN_DATA", "token-data.toml")
+        Self::profile_path(profile().as_deref())
     }
 
-    pub(crate) fn load_from_env() -> Result<Self> {
-        Self::load(&Self::env())
+    fn profile_path(profile: Option<&str>) -> PathBuf {
+        from_env("TWITCH_TOKEN_DATA", "token-data", profile)
+    }
+
+    pub fn load_for_profile(profile: Option<&str>) -> Result<Self> {
+        Self::load(&Self::profile_path(profile))
+    }
+
+    pub fn save_for_profile(&self, profile: Option<&str>) -> Result<()> {
+        self.save(&Self::profile_path(profile))
     }
 
     pub fn save(&self, path: &Path) -> Result<()> {
@@ -57,12 +89,44 @@ impl TokenConfig {
     pub fn save_to_env(&self) -> Result<()> {
         self.save(&Self::env())
     }
+
+    /// Delete the saved tokens, it is not an error if there are none
+    pub fn remove_from_env() -> Result<()> {
+        match fs::remove_file(Self::env()) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(ApiError::RemoveConfig(err)),
+            _ => Ok(()),
+        }
+    }
+}
+
+static PROFILE: OnceLock<String> = OnceLock::new();
+
+/// Use the configs of a named profile, e.g. `token-data.bot.toml` for the profile `bot`
+///
+/// Defaults to the `TWITCH_PROFILE` environment variable. Paths set by environment variables,
+/// e.g. `TWITCH_TOKEN_DATA`, are used for every profile.
+pub fn set_profile(profile: String) -> Result<()> {
+    PROFILE.set(profile).map_err(ApiError::ProfileAlreadySet)
 }
 
-fn from_env(key: &str, default_value: &str) -> PathBuf {
-    env::var_os(key)
-        .unwrap_or_else(|| default_value.into())
-        .into()
+/// The selected profile, `None` for the default configs
+pub fn profile() -> Option<String> {
+    PROFILE
+        .get()
+        .cloned()
+        .or_else(|| env::var("TWITCH_PROFILE").ok())
+        .filter(|profile| !profile.is_empty())
+}
+
+/// Path from the environment variable, or `NAME.toml` and `NAME.PROFILE.toml` with a profile
+fn from_env(key: &str, name: &str, profile: Option<&str>) -> PathBuf {
+    if let Some(path) = env::var_os(key) {
+        return path.into();
+    }
+    match profile {
+        Some(profile) => format!("{name}.{profile}.toml").into(),
+        None => format!("{name}.toml").into(),
+    }
 }
 
 fn load_toml<T>(path: &Path) -> Result<T>
@@ -1,4 +1,4 @@
-use std::fmt;
+use std::{fmt, time::Duration};
 
 use indexmap::IndexMap;
 use reqwest::StatusCode;
@@ -16,9 +16,18 @@ pub enum ApiError {
     #[error("save config: {0}")]
     SaveConfig(#[source] toml::ser::Error),
 
+    #[error("remove config: {0}")]
+    RemoveConfig(#[source] std::io::Error),
+
+    #[error("build client: {0}")]
+    BuildClient(#[source] reqwest::Error),
+
     #[error("send request: {0}")]
     SendRequest(#[source] reqwest::Error),
 
+    #[error("request timed out after {0:?}")]
+    Timeout(Duration),
+
     #[error("parse response: {0}")]
     ParseReponse(#[source] reqwest::Error),
 
@@ -30,6 +39,25 @@ pub enum ApiError {
 
     #[error("unexpected api status: {0}")]
     UnexpectedApiStatus(reqwest::StatusCode),
+
+    #[error("service unavailable: {0} {1:?}")]
+    ServiceUnavailable(reqwest::StatusCode, String),
+
+    #[error("invalid webhook secret: {0}")]
+    InvalidWebhookSecret(&'static str),
+
+    #[error("too many {0} query parameters: {1}, the maximum is {2}")]
+    TooManyQueryParams(&'static str, usize, usize),
+
+    #[error("profile already set, can not use {0:?}")]
+    ProfileAlreadySet(String),
+}
+
+impl ApiError {
+    /// The request was rejected because the access token is invalid or lacks the required scope
+    pub fn is_unauthorized(&self) -> bool {
+        matches!(self, Self::ErrorResponse(StatusCode::UNAUTHORIZED, _))
+    }
 }
 
 #[derive(Debug, Deserialize)]
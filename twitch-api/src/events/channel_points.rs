@@ -0,0 +1,93 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::user::User;
+
+use super::types::Subscription;
+
+#[derive(Debug, Deserialize)]
+pub struct ChannelPointsRedemptionAdd {
+    /// The redemption identifier.
+    pub id: String,
+
+    /// The requested broadcaster ID.
+    pub broadcaster_user_id: String,
+
+    /// The requested broadcaster login.
+    pub broadcaster_user_login: String,
+
+    /// The requested broadcaster display name.
+    pub broadcaster_user_name: String,
+
+    /// User ID of the user that redeemed the reward.
+    pub user_id: String,
+
+    /// Login of the user that redeemed the reward.
+    pub user_login: String,
+
+    /// Display name of the user that redeemed the reward.
+    pub user_name: String,
+
+    /// The user input provided. Empty string if not provided.
+    pub user_input: String,
+
+    /// Defaults to unfulfilled. Possible values are unknown, unfulfilled, fulfilled, and canceled.
+    pub status: RedemptionStatus,
+
+    /// Basic information about the reward that was redeemed, at the time it was redeemed.
+    pub reward: RedemptionReward,
+
+    /// RFC3339 timestamp of when the reward was redeemed.
+    pub redeemed_at: DateTime<Utc>,
+}
+
+impl Subscription for ChannelPointsRedemptionAdd {
+    const TYPE: &'static str = "channel.channel_points_custom_reward_redemption.add";
+    const VERSION: &'static str = "1";
+
+    type Condition = ChannelPointsRedemptionAddCondition;
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChannelPointsRedemptionAddCondition {
+    /// The broadcaster user ID for the channel you want to receive channel points custom reward redemption add notifications for.
+    pub broadcaster_user_id: String,
+
+    /// Optional. Specify a reward id to only receive notifications for a specific reward.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reward_id: Option<String>,
+}
+
+impl From<&User> for ChannelPointsRedemptionAddCondition {
+    /// Redemptions of all rewards of the user's channel
+    fn from(user: &User) -> Self {
+        Self {
+            broadcaster_user_id: user.id.clone(),
+            reward_id: None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RedemptionStatus {
+    Unknown,
+    Unfulfilled,
+    Fulfilled,
+    Canceled,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RedemptionReward {
+    /// The reward identifier.
+    pub id: String,
+
+    /// The reward name.
+    pub title: String,
+
+    /// The reward cost.
+    pub cost: u32,
+
+    /// The reward description.
+    pub prompt: String,
+}
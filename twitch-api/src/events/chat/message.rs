@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use crate::events::types::Subscription;
+use crate::{events::types::Subscription, user::User};
 
 use super::{ChatMessageBadge, ChatMessageMessage};
 
@@ -89,6 +89,16 @@ pub struct ChatMessageCondition {
     pub user_id: String,
 }
 
+impl From<&User> for ChatMessageCondition {
+    /// Read the chat of the user's channel as the user
+    fn from(user: &User) -> Self {
+        Self {
+            broadcaster_user_id: user.id.clone(),
+            user_id: user.id.clone(),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ChatMessageCheer {
     /// The amount of Bits the user cheered.
@@ -145,3 +155,15 @@ pub enum ChatMessageType {
     #[serde(rename = "power_ups_gigantified_emote")]
     PowerUpsGigantifiedEmote,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn condition_from_user() {
+        let condition = ChatMessageCondition::from(&User::test("1337"));
+        assert_eq!(condition.broadcaster_user_id, "1337");
+        assert_eq!(condition.user_id, "1337");
+    }
+}
@@ -1,3 +1,5 @@
+use std::borrow::Cow;
+
 use serde::Deserialize;
 
 pub mod message;
@@ -12,6 +14,25 @@ pub struct ChatMessageMessage {
     pub fragments: Vec<ChatMessageFragment>,
 }
 
+impl ChatMessageMessage {
+    /// The plain text, or the text of the fragments if the plain text is empty
+    pub fn plain_text(&self) -> Cow<'_, str> {
+        if self.text.is_empty() {
+            self.fragments
+                .iter()
+                .map(ChatMessageFragment::text)
+                .collect()
+        } else {
+            Cow::Borrowed(&self.text)
+        }
+    }
+
+    /// Whether neither the plain text nor any fragment contains text
+    pub fn is_empty(&self) -> bool {
+        self.plain_text().trim().is_empty()
+    }
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(tag = "type")]
 pub enum ChatMessageFragment {
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use crate::{chat::ChatAnnouncementColor, events::types::Subscription};
+use crate::{chat::ChatAnnouncementColor, events::types::Subscription, user::User};
 
 use super::{ChatMessageBadge, ChatMessageMessage};
 
@@ -81,6 +81,16 @@ pub struct ChatNotificationCondition {
     pub user_id: String,
 }
 
+impl From<&User> for ChatNotificationCondition {
+    /// Read the chat notifications of the user's channel as the user
+    fn from(user: &User) -> Self {
+        Self {
+            broadcaster_user_id: user.id.clone(),
+            user_id: user.id.clone(),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(tag = "notice_type")]
 pub enum ChatNotificationType {
@@ -421,3 +431,15 @@ pub struct ChatNotificationCharityDonationAmount {
     /// The ISO-4217 three-letter currency code that identifies the type of currency in value.
     pub currency: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn condition_from_user() {
+        let condition = ChatNotificationCondition::from(&User::test("1337"));
+        assert_eq!(condition.broadcaster_user_id, "1337");
+        assert_eq!(condition.user_id, "1337");
+    }
+}
@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+
+use crate::user::User;
+
+use super::types::Subscription;
+
+#[derive(Debug, Deserialize)]
+pub struct Cheer {
+    /// Whether the user cheered anonymously or not.
+    pub is_anonymous: bool,
+
+    /// The user ID for the user who cheered on the specified channel. This is null if is_anonymous is true.
+    pub user_id: Option<String>,
+
+    /// The user login for the user who cheered on the specified channel. This is null if is_anonymous is true.
+    pub user_login: Option<String>,
+
+    /// The user display name for the user who cheered on the specified channel. This is null if is_anonymous is true.
+    pub user_name: Option<String>,
+
+    /// The requested broadcaster ID.
+    pub broadcaster_user_id: String,
+
+    /// The requested broadcaster login.
+    pub broadcaster_user_login: String,
+
+    /// The requested broadcaster display name.
+    pub broadcaster_user_name: String,
+
+    /// The message sent with the cheer.
+    pub message: String,
+
+    /// The number of bits cheered.
+    pub bits: u32,
+}
+
+impl Cheer {
+    /// Display name of the user, `"anonymous"` for anonymous cheers
+    pub fn user_name(&self) -> &str {
+        self.user_name.as_deref().unwrap_or("anonymous")
+    }
+}
+
+impl Subscription for Cheer {
+    const TYPE: &'static str = "channel.cheer";
+    const VERSION: &'static str = "1";
+
+    type Condition = CheerCondition;
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CheerCondition {
+    /// The broadcaster user ID for the channel you want to get cheer notifications for.
+    pub broadcaster_user_id: String,
+}
+
+impl From<&User> for CheerCondition {
+    fn from(user: &User) -> Self {
+        Self {
+            broadcaster_user_id: user.id.clone(),
+        }
+    }
+}
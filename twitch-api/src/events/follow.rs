@@ -1,6 +1,8 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::user::User;
+
 use super::types::Subscription;
 
 #[derive(Debug, Deserialize)]
@@ -42,3 +44,25 @@ pub struct FollowCondition {
     /// The ID of the moderator of the channel you want to get follow notifications for. If you have authorization from the broadcaster rather than a moderator, specify the broadcaster’s user ID here.
     pub moderator_user_id: String,
 }
+
+impl From<&User> for FollowCondition {
+    /// Follows of the user's channel, with the broadcaster as moderator
+    fn from(user: &User) -> Self {
+        Self {
+            broadcaster_user_id: user.id.clone(),
+            moderator_user_id: user.id.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn condition_from_user() {
+        let condition = FollowCondition::from(&User::test("1337"));
+        assert_eq!(condition.broadcaster_user_id, "1337");
+        assert_eq!(condition.moderator_user_id, "1337");
+    }
+}
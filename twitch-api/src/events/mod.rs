@@ -1,6 +1,90 @@
+pub mod channel_points;
 pub mod chat;
+pub mod cheer;
 pub mod follow;
+pub mod raid;
 pub mod stream;
 pub mod subscription;
 pub mod types;
+#[cfg(feature = "webhook")]
+pub mod webhook;
 pub mod ws;
+
+use types::SubscriptionInfo;
+
+macro_rules! subscriptions {
+    ($($ty:ty,)*) => {
+        /// All supported subscription types, with their version
+        pub static ALL_SUBSCRIPTIONS: &[SubscriptionInfo] = &[$(SubscriptionInfo::new::<$ty>(),)*];
+    };
+}
+
+subscriptions! {
+    channel_points::ChannelPointsRedemptionAdd,
+    chat::message::ChatMessage,
+    chat::notification::ChatNotification,
+    cheer::Cheer,
+    follow::Follow,
+    raid::Raid,
+    stream::StreamOnline,
+    stream::StreamOffline,
+}
+
+/// The info of a supported subscription type and version
+pub fn find_subscription(type_: &str, version: &str) -> Option<&'static SubscriptionInfo> {
+    ALL_SUBSCRIPTIONS
+        .iter()
+        .find(|info| info.type_ == type_ && info.version == version)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use super::*;
+
+    /// Type and version of the `Subscription` impls in the files of the directory
+    fn subscription_impls(dir: &Path, impls: &mut Vec<(String, String)>) {
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                subscription_impls(&path, impls);
+                continue;
+            }
+            let source = fs::read_to_string(&path).unwrap();
+            let mut lines = source.lines();
+            while lines.any(|line| line.starts_with("impl Subscription for ")) {
+                let mut value = |name: &str| {
+                    let line = lines.next().unwrap().trim();
+                    let prefix = format!("const {name}: &'static str = \"");
+                    let value = line
+                        .strip_prefix(&prefix)
+                        .and_then(|v| v.strip_suffix("\";"));
+                    value
+                        .unwrap_or_else(|| panic!("{path:?}: {line}"))
+                        .to_string()
+                };
+                let type_ = value("TYPE");
+                let version = value("VERSION");
+                impls.push((type_, version));
+            }
+        }
+    }
+
+    #[test]
+    fn all_subscriptions_are_listed() {
+        let mut impls = Vec::new();
+        subscription_impls(
+            &Path::new(env!("CARGO_MANIFEST_DIR")).join("src"),
+            &mut impls,
+        );
+        impls.sort();
+
+        let mut listed: Vec<_> = ALL_SUBSCRIPTIONS
+            .iter()
+            .map(|info| (info.type_.to_string(), info.version.to_string()))
+            .collect();
+        listed.sort();
+        assert_eq!(listed, impls);
+    }
+}
@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+
+use super::types::Subscription;
+
+#[derive(Debug, Deserialize)]
+pub struct Raid {
+    /// The broadcaster ID that created the raid.
+    pub from_broadcaster_user_id: String,
+
+    /// The broadcaster login that created the raid.
+    pub from_broadcaster_user_login: String,
+
+    /// The broadcaster display name that created the raid.
+    pub from_broadcaster_user_name: String,
+
+    /// The broadcaster ID that received the raid.
+    pub to_broadcaster_user_id: String,
+
+    /// The broadcaster login that received the raid.
+    pub to_broadcaster_user_login: String,
+
+    /// The broadcaster display name that received the raid.
+    pub to_broadcaster_user_name: String,
+
+    /// The number of viewers in the raid.
+    pub viewers: u32,
+}
+
+impl Subscription for Raid {
+    const TYPE: &'static str = "channel.raid";
+    const VERSION: &'static str = "1";
+
+    type Condition = RaidCondition;
+}
+
+/// Specify exactly one of the broadcaster ids
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RaidCondition {
+    /// Optional. The broadcaster user ID that created the channel raid you want to get notifications for. Use this parameter if you want to know when a specific broadcaster raids another broadcaster. The channel raid condition must include either from_broadcaster_user_id or to_broadcaster_user_id.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from_broadcaster_user_id: Option<String>,
+
+    /// Optional. The broadcaster user ID that received the channel raid you want to get notifications for. Use this parameter if you want to know when a specific broadcaster is raided by another broadcaster. The channel raid condition must include either from_broadcaster_user_id or to_broadcaster_user_id.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to_broadcaster_user_id: Option<String>,
+}
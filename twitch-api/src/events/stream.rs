@@ -1,6 +1,8 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::user::User;
+
 use super::types::Subscription;
 
 #[derive(Debug, Deserialize)]
@@ -38,6 +40,14 @@ pub struct StreamOnlineCondition {
     pub broadcaster_user_id: String,
 }
 
+impl From<&User> for StreamOnlineCondition {
+    fn from(user: &User) -> Self {
+        Self {
+            broadcaster_user_id: user.id.clone(),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct StreamOffline {
     /// The broadcaster’s user id.
@@ -63,6 +73,14 @@ pub struct StreamOfflineCondition {
     pub broadcaster_user_id: String,
 }
 
+impl From<&User> for StreamOfflineCondition {
+    fn from(user: &User) -> Self {
+        Self {
+            broadcaster_user_id: user.id.clone(),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub enum StreamType {
     #[serde(rename = "live")]
@@ -80,3 +98,21 @@ pub enum StreamType {
     #[serde(rename = "rerun")]
     Rerun,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conditions_from_user() {
+        let user = User::test("1337");
+        assert_eq!(
+            StreamOnlineCondition::from(&user).broadcaster_user_id,
+            "1337"
+        );
+        assert_eq!(
+            StreamOfflineCondition::from(&user).broadcaster_user_id,
+            "1337"
+        );
+    }
+}
@@ -5,8 +5,8 @@ use serde_json::Value;
 
 use crate::{
     client::{DeleteUrlParamEncoding, JsonEncoding, NoContent, Request, UrlParamEncoding},
-    pagination::Pagination,
-    secret::Secret,
+    pagination::{PaginatedRequest, Pagination},
+    secret::{Secret, WebhookSecret},
 };
 
 use super::types::Subscription;
@@ -45,8 +45,20 @@ impl Request for CreateSubscriptionRequest {
     type Encoding = JsonEncoding;
     type Response = CreateSubscriptionResponse;
 
-    fn url(&self) -> impl reqwest::IntoUrl {
-        twitch_helix!("/eventsub/subscriptions")
+    fn path(&self) -> &str {
+        "/eventsub/subscriptions"
+    }
+}
+
+impl PaginatedRequest for GetSubscriptionsRequest {
+    type Item = SubscriptionInfo;
+
+    fn set_after(&mut self, cursor: Secret) {
+        self.after = Some(cursor.access_secret_value().into());
+    }
+
+    fn into_page(response: Self::Response) -> (Vec<Self::Item>, Pagination) {
+        (response.data, response.pagination)
     }
 }
 
@@ -61,7 +73,7 @@ pub enum TransportRequest {
         callback: Secret,
 
         /// The secret used to verify the signature. The secret must be an ASCII string that’s a minimum of 10 characters long and a maximum of 100 characters long. For information about how the secret is used, see Verifying the event message. Specify this field only if method is set to webhook.
-        secret: Secret,
+        secret: WebhookSecret,
     },
 
     #[serde(rename = "websocket")]
@@ -101,8 +113,8 @@ impl Request for GetSubscriptionsRequest {
     type Encoding = UrlParamEncoding;
     type Response = GetSubscriptionsResponse;
 
-    fn url(&self) -> impl reqwest::IntoUrl {
-        twitch_helix!("/eventsub/subscriptions")
+    fn path(&self) -> &str {
+        "/eventsub/subscriptions"
     }
 }
 #[derive(Debug, Serialize)]
@@ -115,8 +127,8 @@ impl Request for DeleteSubscriptionRequest {
     type Encoding = DeleteUrlParamEncoding;
     type Response = NoContent;
 
-    fn url(&self) -> impl reqwest::IntoUrl {
-        twitch_helix!("/eventsub/subscriptions")
+    fn path(&self) -> &str {
+        "/eventsub/subscriptions"
     }
 }
 
@@ -1,4 +1,5 @@
 use serde::{Serialize, de::DeserializeOwned};
+use serde_json::Value;
 
 pub trait Subscription: DeserializeOwned {
     const TYPE: &'static str;
@@ -6,3 +7,34 @@ pub trait Subscription: DeserializeOwned {
 
     type Condition: Serialize + DeserializeOwned;
 }
+
+/// Type and version of a [`Subscription`], see [`ALL_SUBSCRIPTIONS`](super::ALL_SUBSCRIPTIONS)
+#[derive(Debug, Clone, Copy)]
+pub struct SubscriptionInfo {
+    pub type_: &'static str,
+    pub version: &'static str,
+
+    /// Parse an untyped condition with the condition type of the subscription, the result is
+    /// the condition as it is sent to twitch
+    pub condition: fn(Value) -> serde_json::Result<Value>,
+}
+
+impl SubscriptionInfo {
+    pub const fn new<T: Subscription>() -> Self {
+        Self {
+            type_: T::TYPE,
+            version: T::VERSION,
+            condition: parse_condition::<T>,
+        }
+    }
+
+    /// Whether this is the info of the subscription `T`
+    pub fn is<T: Subscription>(&self) -> bool {
+        self.type_ == T::TYPE && self.version == T::VERSION
+    }
+}
+
+fn parse_condition<T: Subscription>(condition: Value) -> serde_json::Result<Value> {
+    let condition: T::Condition = serde_json::from_value(condition)?;
+    serde_json::to_value(condition)
+}
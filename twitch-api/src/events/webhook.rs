@@ -0,0 +1,415 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    convert::Infallible,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, TimeDelta, Utc};
+use hmac::{Hmac, Mac};
+use http_body_util::{BodyExt, Full};
+use hyper::{
+    Request, Response, StatusCode,
+    body::{Bytes, Incoming as Body},
+    header::{self, HeaderMap},
+    server::conn::http1,
+    service::service_fn,
+};
+use hyper_util::rt::TokioIo;
+use serde::Deserialize;
+use sha2::Sha256;
+use tokio::{
+    net::{TcpListener, ToSocketAddrs},
+    sync::mpsc,
+    task::JoinHandle,
+};
+
+use crate::secret::{Secret, WebhookSecret};
+
+use super::ws::{Incoming, NotificationMessage, RevocationMessage};
+
+/// Receive notifications over the webhook transport
+///
+/// Twitch only sends callbacks to https urls on port 443, so the server is usually run behind a
+/// reverse proxy that terminates tls.
+pub struct WebHook {
+    local_addr: SocketAddr,
+    rx: mpsc::UnboundedReceiver<(DateTime<Utc>, Incoming)>,
+    accept: JoinHandle<()>,
+}
+
+impl WebHook {
+    /// Start the http server, `secret` is the secret used when creating the subscriptions
+    pub async fn bind(addr: impl ToSocketAddrs, secret: WebhookSecret) -> Result<Self> {
+        let listener = TcpListener::bind(addr)
+            .await
+            .context("bind webhook listener")?;
+        let local_addr = listener
+            .local_addr()
+            .context("get webhook listener address")?;
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let handler = Arc::new(Handler {
+            secret: secret.secret().clone(),
+            seen: Mutex::default(),
+            tx,
+        });
+        let accept = tokio::spawn(accept(listener, handler));
+
+        Ok(Self {
+            local_addr,
+            rx,
+            accept,
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Wait for the next verified notification or revocation
+    pub async fn next(&mut self) -> Result<Option<(DateTime<Utc>, Incoming)>> {
+        Ok(self.rx.recv().await)
+    }
+}
+
+impl Drop for WebHook {
+    fn drop(&mut self) {
+        self.accept.abort();
+    }
+}
+
+async fn accept(listener: TcpListener, handler: Arc<Handler>) {
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _addr)) => stream,
+            Err(err) => {
+                eprintln!("failed to accept webhook connection: {err}");
+                continue;
+            }
+        };
+
+        let handler = handler.clone();
+        tokio::spawn(async move {
+            let service = service_fn(|req| {
+                let handler = handler.clone();
+                async move { Ok::<_, Infallible>(handler.handle(req).await) }
+            });
+            if let Err(err) = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                eprintln!("failed to serve webhook connection: {err}");
+            }
+        });
+    }
+}
+
+struct Handler {
+    secret: Secret,
+    seen: Mutex<SeenMessages>,
+    tx: mpsc::UnboundedSender<(DateTime<Utc>, Incoming)>,
+}
+
+impl Handler {
+    /// Messages older (or newer) than this are rejected to prevent replay attacks
+    const MAX_MESSAGE_AGE: TimeDelta = TimeDelta::minutes(10);
+
+    async fn handle(&self, req: Request<Body>) -> Response<Full<Bytes>> {
+        match self.try_handle(req).await {
+            Ok(res) => res,
+            Err(err) => {
+                eprintln!("rejected webhook request: {err:#}");
+                response(StatusCode::BAD_REQUEST, "")
+            }
+        }
+    }
+
+    async fn try_handle(&self, req: Request<Body>) -> Result<Response<Full<Bytes>>> {
+        let (parts, body) = req.into_parts();
+        let body = body
+            .collect()
+            .await
+            .context("read request body")?
+            .to_bytes();
+        self.handle_message(&parts.headers, &body)
+    }
+
+    /// Verify the message and pass on the notification or revocation, or answer the challenge
+    fn handle_message(&self, headers: &HeaderMap, body: &[u8]) -> Result<Response<Full<Bytes>>> {
+        let message_id = header_str(headers, "Twitch-Eventsub-Message-Id")?;
+        let timestamp = header_str(headers, "Twitch-Eventsub-Message-Timestamp")?;
+        let signature = header_str(headers, "Twitch-Eventsub-Message-Signature")?;
+        let message_type = header_str(headers, "Twitch-Eventsub-Message-Type")?;
+
+        if !verify_signature(&self.secret, message_id, timestamp, body, signature) {
+            return Ok(response(StatusCode::FORBIDDEN, ""));
+        }
+
+        let timestamp: DateTime<Utc> = timestamp.parse().context("parse message timestamp")?;
+        anyhow::ensure!(
+            (Utc::now() - timestamp).abs() <= Self::MAX_MESSAGE_AGE,
+            "message timestamp out of range: {timestamp}",
+        );
+
+        let incoming = match message_type {
+            "webhook_callback_verification" => {
+                #[derive(Deserialize)]
+                struct Verification {
+                    challenge: String,
+                }
+
+                let verification: Verification =
+                    serde_json::from_slice(body).context("parse verification message")?;
+                return Ok(response(StatusCode::OK, verification.challenge));
+            }
+            "notification" => Incoming::Notification(
+                serde_json::from_slice::<NotificationMessage>(body)
+                    .context("parse notification message")?,
+            ),
+            "revocation" => Incoming::Revocation(
+                serde_json::from_slice::<RevocationMessage>(body)
+                    .context("parse revocation message")?,
+            ),
+            message_type => anyhow::bail!("unknown message type: {message_type:?}"),
+        };
+
+        // twitch resends messages if it is unsure whether they were received, a message that
+        // failed to parse is not remembered, such that it is handled if it is sent again
+        if !self.seen.lock().unwrap().insert(message_id) {
+            return Ok(response(StatusCode::NO_CONTENT, ""));
+        }
+
+        // the receiver is gone if the webhook is shutting down
+        let _ = self.tx.send((timestamp, incoming));
+        Ok(response(StatusCode::NO_CONTENT, ""))
+    }
+}
+
+/// Ids of the recently received messages
+#[derive(Default)]
+struct SeenMessages {
+    ids: HashSet<String>,
+    order: VecDeque<String>,
+}
+
+impl SeenMessages {
+    const CAPACITY: usize = 1024;
+
+    /// Remember the message id, returns `false` if it was already seen
+    fn insert(&mut self, id: &str) -> bool {
+        if !self.ids.insert(id.into()) {
+            return false;
+        }
+        self.order.push_back(id.into());
+        if self.order.len() > Self::CAPACITY
+            && let Some(id) = self.order.pop_front()
+        {
+            self.ids.remove(&id);
+        }
+        true
+    }
+}
+
+/// Check the `sha256=<hex>` signature of the message id, timestamp and body in constant time
+fn verify_signature(
+    secret: &Secret,
+    message_id: &str,
+    timestamp: &str,
+    body: &[u8],
+    signature: &str,
+) -> bool {
+    let Some(signature) = signature
+        .strip_prefix("sha256=")
+        .and_then(|signature| hex::decode(signature).ok())
+    else {
+        return false;
+    };
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.access_secret_value().as_bytes())
+        .expect("hmac accepts keys of any length");
+    mac.update(message_id.as_bytes());
+    mac.update(timestamp.as_bytes());
+    mac.update(body);
+    mac.verify_slice(&signature).is_ok()
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &str) -> Result<&'a str> {
+    headers
+        .get(name)
+        .with_context(|| format!("missing header: {name}"))?
+        .to_str()
+        .with_context(|| format!("invalid header: {name}"))
+}
+
+fn response(status: StatusCode, body: impl Into<Bytes>) -> Response<Full<Bytes>> {
+    let mut res = Response::new(Full::new(body.into()));
+    *res.status_mut() = status;
+    res.headers_mut().insert(
+        header::CONTENT_TYPE,
+        header::HeaderValue::from_static("text/plain"),
+    );
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "s3cr3t-for-tests";
+
+    fn handler() -> (Handler, mpsc::UnboundedReceiver<(DateTime<Utc>, Incoming)>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let handler = Handler {
+            secret: Secret::new(SECRET),
+            seen: Mutex::default(),
+            tx,
+        };
+        (handler, rx)
+    }
+
+    fn headers(
+        message_id: &str,
+        message_type: &str,
+        timestamp: DateTime<Utc>,
+        body: &str,
+    ) -> HeaderMap {
+        let timestamp = timestamp.to_rfc3339();
+        let mut mac = Hmac::<Sha256>::new_from_slice(SECRET.as_bytes()).unwrap();
+        mac.update(message_id.as_bytes());
+        mac.update(timestamp.as_bytes());
+        mac.update(body.as_bytes());
+        let signature = format!("sha256={}", hex::encode(mac.finalize().into_bytes()));
+
+        let mut headers = HeaderMap::new();
+        for (name, value) in [
+            ("Twitch-Eventsub-Message-Id", message_id),
+            ("Twitch-Eventsub-Message-Timestamp", &timestamp),
+            ("Twitch-Eventsub-Message-Signature", &signature),
+            ("Twitch-Eventsub-Message-Type", message_type),
+        ] {
+            headers.insert(name, value.parse().unwrap());
+        }
+        headers
+    }
+
+    fn body(res: Response<Full<Bytes>>) -> Bytes {
+        futures::executor::block_on(res.into_body().collect())
+            .unwrap()
+            .to_bytes()
+    }
+
+    const NOTIFICATION: &str = r#"{
+        "subscription": {
+            "id": "f1c2a387-161a-49f9-a165-0f21d7a4e1c4",
+            "status": "enabled",
+            "type": "channel.follow",
+            "version": "2",
+            "cost": 0,
+            "condition": {"broadcaster_user_id": "1337", "moderator_user_id": "1337"},
+            "transport": {"method": "webhook", "callback": "https://example.com/webhooks/callback"},
+            "created_at": "2019-11-16T10:11:12.634234626Z"
+        },
+        "event": {}
+    }"#;
+
+    #[test]
+    fn answer_challenge() {
+        let (handler, _rx) = handler();
+        let message = r#"{"challenge": "pogchamp-kappa-360noscope-vohiyo", "subscription": {}}"#;
+        let headers = headers("1", "webhook_callback_verification", Utc::now(), message);
+        let res = handler
+            .handle_message(&headers, message.as_bytes())
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(body(res), "pogchamp-kappa-360noscope-vohiyo");
+    }
+
+    #[test]
+    fn verify_signatures() {
+        let secret = Secret::new(SECRET);
+        let headers = headers("1", "notification", Utc::now(), "body");
+        let timestamp = header_str(&headers, "Twitch-Eventsub-Message-Timestamp").unwrap();
+        let signature = header_str(&headers, "Twitch-Eventsub-Message-Signature").unwrap();
+
+        assert!(verify_signature(
+            &secret, "1", timestamp, b"body", signature
+        ));
+        assert!(!verify_signature(
+            &secret, "2", timestamp, b"body", signature
+        ));
+        assert!(!verify_signature(
+            &secret, "1", timestamp, b"bodY", signature
+        ));
+        assert!(!verify_signature(
+            &Secret::new("other-secret"),
+            "1",
+            timestamp,
+            b"body",
+            signature
+        ));
+        assert!(!verify_signature(
+            &secret,
+            "1",
+            timestamp,
+            b"body",
+            &signature[7..]
+        ));
+        assert!(!verify_signature(
+            &secret,
+            "1",
+            timestamp,
+            b"body",
+            "sha256=zz"
+        ));
+    }
+
+    #[test]
+    fn reject_invalid_signature() {
+        let (handler, mut rx) = handler();
+        let headers = headers("1", "notification", Utc::now(), NOTIFICATION);
+        let tampered = NOTIFICATION.replace("1337", "1338");
+        let res = handler
+            .handle_message(&headers, tampered.as_bytes())
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn reject_old_and_future_messages() {
+        let (handler, mut rx) = handler();
+        for timestamp in [
+            Utc::now() - TimeDelta::hours(1),
+            Utc::now() + TimeDelta::hours(1),
+        ] {
+            let headers = headers("1", "notification", timestamp, NOTIFICATION);
+            assert!(
+                handler
+                    .handle_message(&headers, NOTIFICATION.as_bytes())
+                    .is_err()
+            );
+        }
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn deduplicate_handled_messages() {
+        let (handler, mut rx) = handler();
+
+        // a malformed message is not remembered
+        let malformed = headers("1", "notification", Utc::now(), "{}");
+        assert!(handler.handle_message(&malformed, b"{}").is_err());
+
+        let headers = headers("1", "notification", Utc::now(), NOTIFICATION);
+        for _ in 0..2 {
+            let res = handler
+                .handle_message(&headers, NOTIFICATION.as_bytes())
+                .unwrap();
+            assert_eq!(res.status(), StatusCode::NO_CONTENT);
+        }
+        assert!(matches!(rx.try_recv(), Ok((_, Incoming::Notification(_)))));
+        assert!(rx.try_recv().is_err());
+    }
+}
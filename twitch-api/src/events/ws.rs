@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use futures::{SinkExt, StreamExt};
@@ -15,14 +17,46 @@ type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
 pub struct WebSocket {
     stream: WsStream,
     session_info: SessionInfo,
+    keepalive_grace: Duration,
 }
 
+/// Error returned by [`WebSocket::next`] if the connection was silent for longer than the keepalive timeout
+#[derive(Debug, thiserror::Error)]
+#[error("no websocket message received within {0:?}, the connection is considered dead")]
+pub struct KeepaliveTimeout(pub Duration);
+
 impl WebSocket {
+    const DEFAULT_KEEPALIVE_GRACE: Duration = Duration::from_secs(5);
+
     pub async fn connect() -> Result<Self> {
-        let (mut stream, _response) =
-            tokio_tungstenite::connect_async("wss://eventsub.wss.twitch.tv/ws")
-                .await
-                .context("connect to ws server")?;
+        let (stream, session_info) = Self::connect_to("wss://eventsub.wss.twitch.tv/ws").await?;
+        Ok(Self {
+            stream,
+            session_info,
+            keepalive_grace: Self::DEFAULT_KEEPALIVE_GRACE,
+        })
+    }
+
+    /// Time to wait in addition to the keepalive timeout before the connection is considered dead
+    pub fn set_keepalive_grace(&mut self, grace: Duration) {
+        self.keepalive_grace = grace;
+    }
+
+    /// Interval in which twitch sends at least one message, a keepalive if there is no event
+    pub fn keepalive_interval(&self) -> Duration {
+        Duration::from_secs(self.session_info.keepalive_timeout_seconds.into())
+    }
+
+    fn keepalive_timeout(&self) -> Duration {
+        self.keepalive_interval() + self.keepalive_grace
+    }
+
+    /// Connect and wait for the welcome message
+    async fn connect_to(url: &str) -> Result<(WsStream, SessionInfo)> {
+        let (mut stream, _response) = tokio_tungstenite::connect_async(url)
+            .await
+            .context("connect to ws server")?;
+        metrics::counter!("twitch_api_websocket_connects_total").increment(1);
 
         let (_, message) = Self::next_message(&mut stream)
             .await?
@@ -31,35 +65,57 @@ impl WebSocket {
             anyhow::bail!("expected welcome message, got: {message:?}");
         };
 
-        Ok(Self {
-            stream,
-            session_info: message.session,
-        })
+        Ok((stream, message.session))
+    }
+
+    /// Migrate to the connection at the reconnect url
+    ///
+    /// The subscriptions are kept by Twitch, the old connection is closed after the new one is welcomed.
+    async fn reconnect(&mut self, session_info: SessionInfo) -> Result<()> {
+        let url = session_info
+            .reconnect_url
+            .context("reconnect message without reconnect url")?;
+        let (stream, session_info) = Self::connect_to(url.access_secret_value())
+            .await
+            .context("reconnect to ws server")?;
+        let mut old_stream = std::mem::replace(&mut self.stream, stream);
+        self.session_info = session_info;
+        if let Err(err) = old_stream.close(None).await {
+            eprintln!("failed to close old web socket connection: {err}");
+        }
+        Ok(())
     }
 
     pub fn session_id(&self) -> &Secret {
         &self.session_info.id
     }
 
-    pub async fn next(&mut self) -> Result<Option<(DateTime<Utc>, NotificationMessage)>> {
-        while let Some((timestamp, message)) = Self::next_message(&mut self.stream).await? {
-            match message {
-                Message::SessionWelcome(message) => {
-                    anyhow::bail!("unexpected welcome message: {message:?}")
-                }
-                Message::SessionKeepalive(_message) => {
-                    // eprintln!("session keepalive message");
-                }
-                Message::Notification(message) => {
-                    // eprintln!("{message:#?}");
-                    return Ok(Some((timestamp, message)));
-                }
+    /// Next notification, revocation or keepalive, fails with [`KeepaliveTimeout`] if the connection
+    /// went silent
+    pub async fn next(&mut self) -> Result<Option<(DateTime<Utc>, Incoming)>> {
+        let timeout = self.keepalive_timeout();
+        let Some((timestamp, message)) =
+            tokio::time::timeout(timeout, Self::next_message(&mut self.stream))
+                .await
+                .map_err(|_| KeepaliveTimeout(timeout))??
+        else {
+            eprintln!("end of web socket stream: {:#?}", self.session_info);
+            return Ok(None);
+        };
+        let incoming = match message {
+            Message::SessionWelcome(message) => {
+                anyhow::bail!("unexpected welcome message: {message:?}")
             }
-        }
-
-        eprintln!("end of web socket stream: {:#?}", self.session_info);
-
-        Ok(None)
+            Message::SessionKeepalive(_message) => Incoming::Keepalive,
+            Message::Notification(message) => Incoming::Notification(message),
+            Message::Revocation(message) => Incoming::Revocation(message),
+            Message::SessionReconnect(message) => {
+                self.reconnect(message.session).await?;
+                Incoming::Reconnected
+            }
+            Message::Closed(reason) => Incoming::Closed(reason),
+        };
+        Ok(Some((timestamp, incoming)))
     }
 
     async fn next_message(stream: &mut WsStream) -> Result<Option<(DateTime<Utc>, Message)>> {
@@ -96,12 +152,9 @@ impl WebSocket {
                     break;
                 }
                 WsMessage::Close(Some(close_frame)) => {
-                    eprintln!(
-                        "close with close frame: {} {:?}",
-                        close_frame.code,
-                        close_frame.reason.as_str(),
-                    );
-                    break;
+                    let reason =
+                        CloseReason::new(close_frame.code.into(), close_frame.reason.as_str());
+                    return Ok(Some((Utc::now(), Message::Closed(reason))));
                 }
                 WsMessage::Frame(_) => unreachable!("raw websocket frame"),
             }
@@ -156,6 +209,94 @@ pub enum Message {
     SessionWelcome(SessionWelcomeMessage),
     SessionKeepalive(SessionKeepaliveMessage),
     Notification(NotificationMessage),
+    Revocation(RevocationMessage),
+    SessionReconnect(SessionReconnectMessage),
+
+    /// Close frame received from the server
+    Closed(CloseReason),
+}
+
+/// Messages returned by [`WebSocket::next`] (and `WebHook::next`)
+#[derive(Debug)]
+pub enum Incoming {
+    Notification(NotificationMessage),
+    Revocation(RevocationMessage),
+
+    /// The web socket migrated to a new connection, only returned by [`WebSocket::next`]
+    Reconnected,
+
+    /// No event happened within the keepalive interval, only returned by [`WebSocket::next`]
+    Keepalive,
+
+    /// Twitch closed the web socket, only returned by [`WebSocket::next`]
+    Closed(CloseReason),
+}
+
+/// Close codes sent by the EventSub WebSocket server
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum CloseReason {
+    /// Indicates a problem with the server (similar to an HTTP 500 status code).
+    #[error("internal server error")]
+    InternalServerError,
+
+    /// Sending outgoing messages to the server is prohibited with the exception of pong messages.
+    #[error("client sent inbound traffic")]
+    ClientSentInboundTraffic,
+
+    /// You must respond to ping messages with a pong message.
+    #[error("client failed ping-pong")]
+    ClientFailedPingPong,
+
+    /// When you connect to the server, you must create a subscription within 10 seconds or the connection is closed. The time limit is subject to change.
+    #[error("connection unused")]
+    ConnectionUnused,
+
+    /// When you receive a session_reconnect message, you have 30 seconds to reconnect to the server and close the old connection.
+    #[error("reconnect grace time expired")]
+    ReconnectGraceTimeExpired,
+
+    /// Transient network timeout.
+    #[error("network timeout")]
+    NetworkTimeout,
+
+    /// Transient network error.
+    #[error("network error")]
+    NetworkError,
+
+    /// The reconnect URL is invalid.
+    #[error("invalid reconnect")]
+    InvalidReconnect,
+
+    /// Close code not documented by Twitch
+    #[error("close code {code}: {reason:?}")]
+    Other { code: u16, reason: String },
+}
+
+impl CloseReason {
+    fn new(code: u16, reason: &str) -> Self {
+        match code {
+            4000 => Self::InternalServerError,
+            4001 => Self::ClientSentInboundTraffic,
+            4002 => Self::ClientFailedPingPong,
+            4003 => Self::ConnectionUnused,
+            4004 => Self::ReconnectGraceTimeExpired,
+            4005 => Self::NetworkTimeout,
+            4006 => Self::NetworkError,
+            4007 => Self::InvalidReconnect,
+            code => Self::Other {
+                code,
+                reason: reason.into(),
+            },
+        }
+    }
+
+    /// Whether the problem is on the side of Twitch or the network, such that connecting again may help
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            Self::InternalServerError | Self::NetworkTimeout | Self::NetworkError
+        )
+    }
 }
 
 impl Message {
@@ -166,6 +307,8 @@ impl Message {
                 "session_welcome" => Self::SessionWelcome(message.payload()?),
                 "session_keepalive" => Self::SessionKeepalive(message.payload()?),
                 "notification" => Self::Notification(message.payload()?),
+                "revocation" => Self::Revocation(message.payload()?),
+                "session_reconnect" => Self::SessionReconnect(message.payload()?),
                 message_type => anyhow::bail!("unknown message type: {message_type:?}"),
             },
         ))
@@ -205,6 +348,13 @@ pub struct SessionInfo {
 #[serde(deny_unknown_fields)]
 pub struct SessionKeepaliveMessage {}
 
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SessionReconnectMessage {
+    /// An object that contains information about the connection.
+    session: SessionInfo,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct NotificationMessage {
@@ -216,6 +366,11 @@ pub struct NotificationMessage {
 }
 
 impl NotificationMessage {
+    /// Subscription type of the event, e.g. `channel.follow`
+    pub fn subscription_type(&self) -> &str {
+        &self.subscription.type_
+    }
+
     pub fn event<T>(&self) -> Result<Option<T>>
     where
         T: Subscription,
@@ -236,6 +391,13 @@ impl NotificationMessage {
     }
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RevocationMessage {
+    /// An object that contains information about your subscription. The status field contains the reason why Twitch revoked your subscription.
+    pub subscription: SubscriptionInfo,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NotificationMessageEvent {
     type_: String,
@@ -302,9 +464,14 @@ pub struct SubscriptionInfo {
 #[derive(Debug, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct TransportInfo {
-    /// The transport method, which is set to websocket.
+    /// The transport method, which is set to websocket or webhook.
     pub method: String,
 
-    /// An ID that uniquely identifies the WebSocket connection.
-    pub session_id: Secret,
+    /// An ID that uniquely identifies the WebSocket connection. Included only if method is set to websocket.
+    #[serde(default)]
+    pub session_id: Option<Secret>,
+
+    /// The callback URL where the notifications are sent. Included only if method is set to webhook.
+    #[serde(default)]
+    pub callback: Option<Secret>,
 }
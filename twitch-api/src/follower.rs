@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     client::{Request, UrlParamEncoding},
-    pagination::Pagination,
+    pagination::{PaginatedRequest, Pagination},
     secret::Secret,
 };
 
@@ -42,8 +42,20 @@ impl Request for ChannelFollowersRequest {
     type Encoding = UrlParamEncoding;
     type Response = ChannelFollowersResponse;
 
-    fn url(&self) -> impl reqwest::IntoUrl {
-        twitch_helix!("/channels/followers")
+    fn path(&self) -> &str {
+        "/channels/followers"
+    }
+}
+
+impl PaginatedRequest for ChannelFollowersRequest {
+    type Item = ChannelFollower;
+
+    fn set_after(&mut self, cursor: Secret) {
+        self.after = Some(cursor);
+    }
+
+    fn into_page(response: Self::Response) -> (Vec<Self::Item>, Pagination) {
+        (response.data, response.pagination)
     }
 }
 
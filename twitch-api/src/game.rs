@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::client::{Request, UrlParamEncoding};
+
+#[derive(Debug, Default)]
+pub struct GamesRequest {
+    /// The ID of the category or game to get. Include this parameter for each category or game you want to get. For example, &id=1234&id=5678. You may specify a maximum of 100 IDs. The endpoint ignores duplicate and invalid IDs or IDs that weren’t found.
+    pub id: Vec<String>,
+
+    /// The name of the category or game to get. The name must exactly match the category’s or game’s title. Include this parameter for each category or game you want to get. For example, &name=foo&name=bar. You may specify a maximum of 100 names. The endpoint ignores duplicate names and names that weren’t found.
+    pub name: Vec<String>,
+
+    /// The IGDB ID of the game to get. Include this parameter for each game you want to get. For example, &igdb_id=1234&igdb_id=5678. You may specify a maximum of 100 IDs. The endpoint ignores duplicate and invalid IDs or IDs that weren’t found.
+    pub igdb_id: Vec<String>,
+}
+
+impl GamesRequest {
+    pub fn id(id: String) -> Self {
+        Self {
+            id: vec![id],
+            ..Default::default()
+        }
+    }
+
+    pub fn name(name: String) -> Self {
+        Self {
+            name: vec![name],
+            ..Default::default()
+        }
+    }
+}
+
+/// Repeated `id`, `name` and `igdb_id` parameters, serialized as key value pairs
+impl Serialize for GamesRequest {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let ids = self.id.iter().map(|id| ("id", id));
+        let names = self.name.iter().map(|name| ("name", name));
+        let igdb_ids = self.igdb_id.iter().map(|id| ("igdb_id", id));
+        serializer.collect_seq(ids.chain(names).chain(igdb_ids))
+    }
+}
+
+impl Request for GamesRequest {
+    type Encoding = UrlParamEncoding;
+    type Response = GamesResponse;
+
+    fn path(&self) -> &str {
+        "/games"
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GamesResponse {
+    /// The list of categories and games. The list is empty if the specified categories and games weren’t found.
+    pub data: Vec<Game>,
+}
+
+impl GamesResponse {
+    pub fn into_game(mut self) -> Option<Game> {
+        if self.data.len() > 1 {
+            unreachable!("mulitple games returned");
+        }
+        self.data.pop()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Game {
+    /// An ID that identifies the category or game.
+    pub id: String,
+
+    /// The category’s or game’s name.
+    pub name: String,
+
+    /// A URL to the category’s or game’s box art. You must replace the {width}x{height} placeholder with the size of image you want.
+    pub box_art_url: String,
+
+    /// The ID that IGDB uses to identify this game. If the IGDB ID is not available to Twitch, this field is set to an empty string.
+    pub igdb_id: String,
+}
@@ -1,17 +1,21 @@
-#[macro_use]
-mod macros;
-
 pub mod auth;
+pub mod bits;
 pub mod channel;
 pub mod chat;
 pub mod client;
+pub mod clip;
 pub mod config;
 pub mod error;
 pub mod events;
 pub mod follower;
+pub mod game;
+pub mod moderation;
 pub mod pagination;
+pub mod poll;
+pub mod prediction;
 pub mod secret;
 pub mod stream;
 pub mod user;
+pub mod video;
 
 pub use serde_json::json;
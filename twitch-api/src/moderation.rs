@@ -0,0 +1,157 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::client::{DeleteUrlParamEncoding, JsonEncoding, NoContent, Request};
+
+#[derive(Debug)]
+pub struct BanUserRequest {
+    /// The ID of the broadcaster whose chat room the user is being banned from.
+    pub broadcaster_id: String,
+
+    /// The ID of the broadcaster or a user that has permission to moderate the broadcaster’s chat room. This ID must match the user ID in the user access token.
+    pub moderator_id: String,
+
+    /// The ID of the user to ban or put in a timeout.
+    pub user_id: String,
+
+    /// To ban a user indefinitely, don’t include this field.
+    ///
+    /// To put a user in a timeout, include this field and specify the timeout period, in seconds. The minimum timeout is 1 second and the maximum is 1,209,600 seconds (2 weeks).
+    ///
+    /// To end a user’s timeout early, set this field to 1, or use the Unban user endpoint.
+    pub duration: Option<u32>,
+
+    /// The reason you’re banning the user or putting them in a timeout. The text is user defined and is limited to a maximum of 500 characters.
+    pub reason: Option<String>,
+}
+
+#[derive(Serialize)]
+struct BanUserBody<'a> {
+    data: BanUserData<'a>,
+}
+
+#[derive(Serialize)]
+struct BanUserData<'a> {
+    user_id: &'a str,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration: Option<u32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<&'a str>,
+}
+
+/// The body is wrapped in a `data` object, the ids of the channel are query parameters
+impl Serialize for BanUserRequest {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        BanUserBody {
+            data: BanUserData {
+                user_id: &self.user_id,
+                duration: self.duration,
+                reason: self.reason.as_deref(),
+            },
+        }
+        .serialize(serializer)
+    }
+}
+
+impl Request for BanUserRequest {
+    type Encoding = JsonEncoding;
+    type Response = BanResponse;
+
+    fn path(&self) -> &str {
+        "/moderation/bans"
+    }
+
+    fn modify_request(&self, req: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        req.query(&[
+            ("broadcaster_id", &self.broadcaster_id),
+            ("moderator_id", &self.moderator_id),
+        ])
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BanResponse {
+    /// A list that contains the user you successfully banned or put in a timeout.
+    pub data: Vec<BanInfo>,
+}
+
+impl BanResponse {
+    pub fn into_ban(mut self) -> Option<BanInfo> {
+        if self.data.len() > 1 {
+            unreachable!("mulitple bans returned");
+        }
+        self.data.pop()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BanInfo {
+    /// The broadcaster whose chat room the user was banned from chatting in.
+    pub broadcaster_id: String,
+
+    /// The moderator that banned or put the user in the timeout.
+    pub moderator_id: String,
+
+    /// The user that was banned or put in a timeout.
+    pub user_id: String,
+
+    /// The UTC date and time (in RFC3339 format) that the ban or timeout was placed.
+    pub created_at: DateTime<Utc>,
+
+    /// The UTC date and time (in RFC3339 format) that the timeout will end. Is null if the user was banned instead of being put in a timeout.
+    pub end_time: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UnbanUserRequest {
+    /// The ID of the broadcaster whose chat room the user is banned from chatting in.
+    pub broadcaster_id: String,
+
+    /// The ID of the broadcaster or a user that has permission to moderate the broadcaster’s chat room. This ID must match the user ID in the user access token.
+    pub moderator_id: String,
+
+    /// The ID of the user to remove the ban or timeout from.
+    pub user_id: String,
+}
+
+impl Request for UnbanUserRequest {
+    type Encoding = DeleteUrlParamEncoding;
+    type Response = NoContent;
+
+    fn path(&self) -> &str {
+        "/moderation/bans"
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeleteChatMessageRequest {
+    /// The ID of the broadcaster that owns the chat room to remove messages from.
+    pub broadcaster_id: String,
+
+    /// The ID of the broadcaster or a user that has permission to moderate the broadcaster’s chat room. This ID must match the user ID in the user access token.
+    pub moderator_id: String,
+
+    /// The ID of the message to remove. The id tag in the PRIVMSG tag contains the message’s ID. Restrictions:
+    ///
+    /// - The message must have been created within the last 6 hours.
+    /// - The message must not belong to the broadcaster.
+    /// - The message must not belong to another moderator.
+    ///
+    /// If not specified, the request removes all messages in the broadcaster’s chat room.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_id: Option<String>,
+}
+
+impl Request for DeleteChatMessageRequest {
+    type Encoding = DeleteUrlParamEncoding;
+    type Response = NoContent;
+
+    fn path(&self) -> &str {
+        "/moderation/chat"
+    }
+}
@@ -1,6 +1,6 @@
 use serde::Deserialize;
 
-use crate::secret::Secret;
+use crate::{client::Request, secret::Secret};
 
 #[derive(Debug, Deserialize)]
 pub struct Pagination {
@@ -8,3 +8,14 @@ pub struct Pagination {
     #[serde(default)]
     pub cursor: Option<Secret>,
 }
+
+/// Request that returns its results in pages, see `Client::paginate`
+pub trait PaginatedRequest: Request {
+    type Item;
+
+    /// Request the page after the cursor
+    fn set_after(&mut self, cursor: Secret);
+
+    /// Split the response into the items of the page and the pagination
+    fn into_page(response: Self::Response) -> (Vec<Self::Item>, Pagination);
+}
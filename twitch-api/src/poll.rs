@@ -0,0 +1,203 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    client::{Request, UrlParamEncoding},
+    pagination::{PaginatedRequest, Pagination},
+    secret::Secret,
+};
+
+#[derive(Debug, Serialize)]
+pub struct GetPollsRequest {
+    /// The ID of the broadcaster that created the polls. This ID must match the user ID in the user access token.
+    pub broadcaster_id: String,
+
+    /// The ID of a poll to get. The maximum number of IDs that you may specify is 20. If you don’t specify an ID, the request returns the polls in the order that they were created, with the most recent poll first.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+
+    /// The maximum number of items to return per page in the response. The minimum page size is 1 item per page and the maximum is 20 items per page. The default is 20.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first: Option<usize>,
+
+    /// The cursor used to get the next page of results. The Pagination object in the response contains the cursor’s value.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<Secret>,
+}
+
+impl GetPollsRequest {
+    pub fn broadcaster_id(broadcaster_id: String) -> Self {
+        Self {
+            broadcaster_id,
+            id: None,
+            first: None,
+            after: None,
+        }
+    }
+}
+
+impl Request for GetPollsRequest {
+    type Encoding = UrlParamEncoding;
+    type Response = GetPollsResponse;
+
+    fn path(&self) -> &str {
+        "/polls"
+    }
+}
+
+impl PaginatedRequest for GetPollsRequest {
+    type Item = Poll;
+
+    fn set_after(&mut self, cursor: Secret) {
+        self.after = Some(cursor);
+    }
+
+    fn into_page(response: Self::Response) -> (Vec<Self::Item>, Pagination) {
+        (response.data, response.pagination)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GetPollsResponse {
+    /// A list of polls. The polls are returned in descending order of start time unless you specify IDs in the request, in which case they’re returned in the same order as you passed them in the request. The list is empty if the broadcaster hasn’t created polls.
+    pub data: Vec<Poll>,
+
+    /// Contains the information used to page through the list of results. The object is empty if there are no more pages left to page through.
+    pub pagination: Pagination,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Poll {
+    /// An ID that identifies the poll.
+    pub id: String,
+
+    /// An ID that identifies the broadcaster that created the poll.
+    pub broadcaster_id: String,
+
+    /// The broadcaster’s display name.
+    pub broadcaster_name: String,
+
+    /// The broadcaster’s login name.
+    pub broadcaster_login: String,
+
+    /// The question that viewers are voting on. For example, What game should I play next? The title may contain a maximum of 60 characters.
+    pub title: String,
+
+    /// A list of choices that viewers can choose from. The list will contain a minimum of two choices and up to a maximum of five choices.
+    pub choices: Vec<PollChoice>,
+
+    /// A Boolean value that indicates whether viewers may cast additional votes using Channel Points.
+    pub channel_points_voting_enabled: bool,
+
+    /// The number of points the viewer must spend to cast one additional vote.
+    pub channel_points_per_vote: u32,
+
+    /// The poll’s status.
+    pub status: PollStatus,
+
+    /// The length of time (in seconds) that the poll will run for.
+    pub duration: u32,
+
+    /// The UTC date and time (in RFC3339 format) of when the poll began.
+    pub started_at: DateTime<Utc>,
+
+    /// The UTC date and time (in RFC3339 format) of when the poll ended. If status is ACTIVE, this field is set to null.
+    #[serde(default)]
+    pub ended_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PollChoice {
+    /// An ID that identifies this choice.
+    pub id: String,
+
+    /// The choice’s title. The title may contain a maximum of 25 characters.
+    pub title: String,
+
+    /// The total number of votes cast for this choice.
+    pub votes: u32,
+
+    /// The number of votes cast using Channel Points.
+    pub channel_points_votes: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PollStatus {
+    /// The poll is running.
+    Active,
+
+    /// The poll ended on schedule.
+    Completed,
+
+    /// The poll was terminated before its scheduled end.
+    Terminated,
+
+    /// The poll has been archived and is no longer visible on the channel.
+    Archived,
+
+    /// The poll was deleted.
+    Moderated,
+
+    /// Something went wrong while determining the state.
+    Invalid,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deserialize_active_poll() {
+        let response: GetPollsResponse = serde_json::from_str(
+            r#"{
+                "data": [
+                    {
+                        "id": "ed961efd-8a3f-4cf5-a9d0-e616c590cd2a",
+                        "broadcaster_id": "55696719",
+                        "broadcaster_name": "TwitchDev",
+                        "broadcaster_login": "twitchdev",
+                        "title": "Heads or Tails?",
+                        "choices": [
+                            {
+                                "id": "4c123012-1351-4f33-84b7-43856e7a0f47",
+                                "title": "Heads",
+                                "votes": 0,
+                                "channel_points_votes": 0,
+                                "bits_votes": 0
+                            },
+                            {
+                                "id": "279087e3-54a7-467e-bcd0-c1393fcea4f0",
+                                "title": "Tails",
+                                "votes": 0,
+                                "channel_points_votes": 0,
+                                "bits_votes": 0
+                            }
+                        ],
+                        "bits_voting_enabled": false,
+                        "bits_per_vote": 0,
+                        "channel_points_voting_enabled": false,
+                        "channel_points_per_vote": 0,
+                        "status": "ACTIVE",
+                        "duration": 1800,
+                        "started_at": "2021-03-19T06:08:33.871278372Z",
+                        "ended_at": null
+                    }
+                ],
+                "pagination": {}
+            }"#,
+        )
+        .unwrap();
+
+        let [poll] = &response.data[..] else {
+            panic!("expected one poll");
+        };
+        assert_eq!(poll.title, "Heads or Tails?");
+        assert_eq!(poll.status, PollStatus::Active);
+        assert_eq!(poll.duration, 1800);
+        assert_eq!(poll.ended_at, None);
+        let choices: Vec<_> = poll.choices.iter().map(|choice| &choice.title).collect();
+        assert_eq!(choices, ["Heads", "Tails"]);
+        assert!(response.pagination.cursor.is_none());
+    }
+}
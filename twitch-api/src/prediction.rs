@@ -0,0 +1,209 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    client::{Request, UrlParamEncoding},
+    pagination::{PaginatedRequest, Pagination},
+    secret::Secret,
+};
+
+#[derive(Debug, Serialize)]
+pub struct GetPredictionsRequest {
+    /// The ID of the broadcaster whose predictions you want to get. This ID must match the user ID in the user access token.
+    pub broadcaster_id: String,
+
+    /// The ID of the prediction to get. The maximum number of IDs that you may specify is 25. If you don’t specify an ID, the request returns the predictions in the order that they were created, with the most recent prediction first.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+
+    /// The maximum number of items to return per page in the response. The minimum page size is 1 item per page and the maximum is 25 items per page. The default is 20.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first: Option<usize>,
+
+    /// The cursor used to get the next page of results. The Pagination object in the response contains the cursor’s value.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub after: Option<Secret>,
+}
+
+impl GetPredictionsRequest {
+    pub fn broadcaster_id(broadcaster_id: String) -> Self {
+        Self {
+            broadcaster_id,
+            id: None,
+            first: None,
+            after: None,
+        }
+    }
+}
+
+impl Request for GetPredictionsRequest {
+    type Encoding = UrlParamEncoding;
+    type Response = GetPredictionsResponse;
+
+    fn path(&self) -> &str {
+        "/predictions"
+    }
+}
+
+impl PaginatedRequest for GetPredictionsRequest {
+    type Item = Prediction;
+
+    fn set_after(&mut self, cursor: Secret) {
+        self.after = Some(cursor);
+    }
+
+    fn into_page(response: Self::Response) -> (Vec<Self::Item>, Pagination) {
+        (response.data, response.pagination)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GetPredictionsResponse {
+    /// The broadcaster’s list of Channel Points Predictions. The list is sorted in descending ordered by when the prediction began (the most recent prediction is first). The list is empty if the broadcaster hasn’t created predictions.
+    pub data: Vec<Prediction>,
+
+    /// Contains the information used to page through the list of results. The object is empty if there are no more pages left to page through.
+    pub pagination: Pagination,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Prediction {
+    /// An ID that identifies this prediction.
+    pub id: String,
+
+    /// An ID that identifies the broadcaster that created the prediction.
+    pub broadcaster_id: String,
+
+    /// The broadcaster’s display name.
+    pub broadcaster_name: String,
+
+    /// The broadcaster’s login name.
+    pub broadcaster_login: String,
+
+    /// The question that the prediction asks. For example, Will I finish this entire pizza?
+    pub title: String,
+
+    /// The ID of the winning outcome. Is null unless status is RESOLVED.
+    #[serde(default)]
+    pub winning_outcome_id: Option<String>,
+
+    /// The list of possible outcomes for the prediction.
+    pub outcomes: Vec<PredictionOutcome>,
+
+    /// The length of time (in seconds) that the prediction will run for.
+    pub prediction_window: u32,
+
+    /// The prediction’s status.
+    pub status: PredictionStatus,
+
+    /// The UTC date and time of when the Prediction began.
+    pub created_at: DateTime<Utc>,
+
+    /// The UTC date and time of when the Prediction ended. If status is ACTIVE, this is set to null.
+    #[serde(default)]
+    pub ended_at: Option<DateTime<Utc>>,
+
+    /// The UTC date and time of when the Prediction was locked. If status is not LOCKED, this is set to null.
+    #[serde(default)]
+    pub locked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PredictionOutcome {
+    /// An ID that identifies this outcome.
+    pub id: String,
+
+    /// The outcome’s text.
+    pub title: String,
+
+    /// The number of unique viewers that chose this outcome.
+    pub users: u32,
+
+    /// The number of Channel Points spent by viewers on this outcome.
+    pub channel_points: u64,
+
+    /// The color that visually identifies this outcome in the UX. If the number of outcomes is two, the color is BLUE for the first outcome and PINK for the second outcome. If there are more than two outcomes, the color is BLUE for all outcomes.
+    pub color: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PredictionStatus {
+    /// The Prediction is running and viewers can make predictions.
+    Active,
+
+    /// The broadcaster canceled the Prediction and refunded the Channel Points to the participants.
+    Canceled,
+
+    /// The broadcaster locked the Prediction, which means viewers can no longer make predictions.
+    Locked,
+
+    /// The winning outcome was determined and the Channel Points were distributed to the viewers who predicted the correct outcome.
+    Resolved,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deserialize_active_prediction() {
+        let response: GetPredictionsResponse = serde_json::from_str(
+            r#"{
+                "data": [
+                    {
+                        "id": "d6676d5c-c86e-44d2-bfc4-100fb48f0656",
+                        "broadcaster_id": "55696719",
+                        "broadcaster_name": "TwitchDev",
+                        "broadcaster_login": "twitchdev",
+                        "title": "Will there be any leaks today?",
+                        "winning_outcome_id": null,
+                        "outcomes": [
+                            {
+                                "id": "021e9234-5893-49b4-982e-cfe9a0aaddd9",
+                                "title": "Yes",
+                                "users": 0,
+                                "channel_points": 0,
+                                "top_predictors": null,
+                                "color": "BLUE"
+                            },
+                            {
+                                "id": "ded84c26-13cb-4b48-8cb5-5bae3ec3a66e",
+                                "title": "No",
+                                "users": 0,
+                                "channel_points": 0,
+                                "top_predictors": null,
+                                "color": "PINK"
+                            }
+                        ],
+                        "prediction_window": 600,
+                        "status": "ACTIVE",
+                        "created_at": "2021-04-28T16:03:06.320848689Z",
+                        "ended_at": null,
+                        "locked_at": null
+                    }
+                ],
+                "pagination": {
+                    "cursor": "eyJiIjpudWxsLCJhIjp7IkN1cnNvciI6IiJ9fQ"
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let [prediction] = &response.data[..] else {
+            panic!("expected one prediction");
+        };
+        assert_eq!(prediction.title, "Will there be any leaks today?");
+        assert_eq!(prediction.status, PredictionStatus::Active);
+        assert_eq!(prediction.prediction_window, 600);
+        assert_eq!(prediction.winning_outcome_id, None);
+        assert_eq!(prediction.locked_at, None);
+        let outcomes: Vec<_> = prediction
+            .outcomes
+            .iter()
+            .map(|outcome| (outcome.title.as_str(), outcome.color.as_str()))
+            .collect();
+        assert_eq!(outcomes, [("Yes", "BLUE"), ("No", "PINK")]);
+        assert!(response.pagination.cursor.is_some());
+    }
+}
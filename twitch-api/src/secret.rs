@@ -1,7 +1,11 @@
 use std::fmt;
 
+use rand::{Rng, distr::Alphanumeric};
 use reqwest::header::HeaderValue;
 use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
+
+use crate::error::{ApiError, Result};
 
 #[derive(Default, Clone, Serialize, Deserialize)]
 #[serde(transparent)]
@@ -16,14 +20,34 @@ impl Secret {
         &self.0
     }
 
-    pub fn bearer(&self) -> Bearer {
+    /// Compare in constant time, such that the timing does not reveal how many bytes match
+    pub fn ct_eq(&self, other: &Secret) -> bool {
+        let a = self.0.as_bytes();
+        let b = other.0.as_bytes();
+        let len = a.len().max(b.len());
+        let diff = (0..len).fold(a.len() ^ b.len(), |diff, i| {
+            let x = a.get(i).copied().unwrap_or(0);
+            let y = b.get(i).copied().unwrap_or(0);
+            diff | usize::from(x ^ y)
+        });
+        std::hint::black_box(diff) == 0
+    }
+
+    pub fn bearer(&self) -> Bearer<'_> {
         Bearer(self)
     }
 }
 
+/// Overwrite the value, such that it does not linger in freed memory
+impl Drop for Secret {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
 impl fmt::Debug for Secret {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        f.write_str(&"*".repeat(self.0.len()))
+        f.write_str("Secret(\"***\")")
     }
 }
 
@@ -35,6 +59,49 @@ impl TryFrom<&Secret> for HeaderValue {
     }
 }
 
+/// Secret used to sign webhook notifications, an ASCII string of 10 to 100 characters
+#[derive(Debug, Clone, Serialize)]
+#[serde(transparent)]
+pub struct WebhookSecret(Secret);
+
+impl WebhookSecret {
+    const MIN_LEN: usize = 10;
+    const MAX_LEN: usize = 100;
+    const GENERATED_LEN: usize = 64;
+
+    pub fn new(value: impl ToString) -> Result<Self> {
+        let value = value.to_string();
+        if !value.is_ascii() {
+            return Err(ApiError::InvalidWebhookSecret("must be an ascii string"));
+        }
+        if value.len() < Self::MIN_LEN {
+            return Err(ApiError::InvalidWebhookSecret(
+                "must be at least 10 characters long",
+            ));
+        }
+        if value.len() > Self::MAX_LEN {
+            return Err(ApiError::InvalidWebhookSecret(
+                "must be at most 100 characters long",
+            ));
+        }
+        Ok(Self(Secret(value)))
+    }
+
+    /// Random alphanumeric secret from a cryptographically secure generator
+    pub fn generate() -> Self {
+        let value = rand::rng()
+            .sample_iter(Alphanumeric)
+            .take(Self::GENERATED_LEN)
+            .map(char::from)
+            .collect();
+        Self(Secret(value))
+    }
+
+    pub fn secret(&self) -> &Secret {
+        &self.0
+    }
+}
+
 pub struct Bearer<'a>(&'a Secret);
 
 impl TryFrom<Bearer<'_>> for HeaderValue {
@@ -44,3 +111,55 @@ impl TryFrom<Bearer<'_>> for HeaderValue {
         format!("Bearer {}", value.0.access_secret_value()).try_into()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn equal_secrets_compare_equal() {
+        assert!(Secret::new("s3cr3t-value").ct_eq(&Secret::new("s3cr3t-value")));
+        assert!(Secret::new("").ct_eq(&Secret::new("")));
+        assert!(!Secret::new("s3cr3t-value").ct_eq(&Secret::new("s3cr3t-valuf")));
+    }
+
+    #[test]
+    fn different_lengths_compare_unequal() {
+        // the shorter secret is compared as if padded with zeros, the lengths still differ
+        assert!(!Secret::new("secret").ct_eq(&Secret::new("secret\0")));
+        assert!(!Secret::new("secret\0").ct_eq(&Secret::new("secret")));
+        assert!(!Secret::new("secret").ct_eq(&Secret::new("secret-but-longer")));
+        assert!(!Secret::new("").ct_eq(&Secret::new("secret")));
+    }
+
+    #[test]
+    fn debug_hides_value_and_length() {
+        assert_eq!(format!("{:?}", Secret::new("a")), r#"Secret("***")"#);
+        assert_eq!(
+            format!("{:?}", Secret::new("a much longer secret value")),
+            r#"Secret("***")"#,
+        );
+    }
+
+    #[test]
+    fn webhook_secret_rejects_invalid() {
+        assert!(WebhookSecret::new("too-short").is_err());
+        assert!(WebhookSecret::new("").is_err());
+        assert!(WebhookSecret::new("x".repeat(101)).is_err());
+        // long enough in bytes, but not ascii
+        assert!(WebhookSecret::new("geheimnisß").is_err());
+        assert!(WebhookSecret::new("🔑🔑🔑🔑🔑🔑🔑🔑🔑🔑").is_err());
+    }
+
+    #[test]
+    fn webhook_secret_accepts_valid() {
+        let secret = WebhookSecret::new("0123456789").unwrap();
+        assert_eq!(secret.secret().access_secret_value(), "0123456789");
+        assert!(WebhookSecret::new("x".repeat(100)).is_ok());
+
+        let generated = WebhookSecret::generate();
+        let value = generated.secret().access_secret_value();
+        assert!(WebhookSecret::new(value).is_ok());
+        assert!(!generated.secret().ct_eq(WebhookSecret::generate().secret()));
+    }
+}
@@ -2,16 +2,17 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    client::{Request, UrlParamEncoding},
-    pagination::Pagination,
+    client::{Request, UrlParamEncoding, limit_query_params},
+    error::Result,
+    pagination::{PaginatedRequest, Pagination},
     secret::Secret,
 };
 
 #[derive(Debug, Serialize)]
 pub struct StreamsRequest {
     /// A user ID used to filter the list of streams. Returns only the streams of those users that are broadcasting. You may specify a maximum of 100 IDs. To specify multiple IDs, include the user_id parameter for each user. For example, &user_id=1234&user_id=5678.
-    #[serde(skip_serializing_if = "Option::is_none")]
-    user_id: Option<String>,
+    #[serde(skip)]
+    user_id: Vec<String>,
 
     /// A user login name used to filter the list of streams. Returns only the streams of those users that are broadcasting. You may specify a maximum of 100 login names. To specify multiple names, include the user_login parameter for each user. For example, &user_login=foo&user_login=bar.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -50,7 +51,7 @@ pub struct StreamsRequest {
 
 impl StreamsRequest {
     const EMPTY: Self = Self {
-        user_id: None,
+        user_id: Vec::new(),
         user_login: None,
         game_id: None,
         type_: None,
@@ -62,18 +63,43 @@ impl StreamsRequest {
 
     pub fn user_id(user_id: String) -> Self {
         Self {
-            user_id: Some(user_id),
+            user_id: vec![user_id],
             ..Self::EMPTY
         }
     }
+
+    /// The streams of up to 100 users
+    pub fn user_ids(user_ids: impl IntoIterator<Item = String>) -> Result<Self> {
+        Ok(Self {
+            user_id: limit_query_params("user_id", user_ids)?,
+            ..Self::EMPTY
+        })
+    }
 }
 
 impl Request for StreamsRequest {
     type Encoding = UrlParamEncoding;
     type Response = StreamsResponse;
 
-    fn url(&self) -> impl reqwest::IntoUrl {
-        twitch_helix!("/streams")
+    fn path(&self) -> &str {
+        "/streams"
+    }
+
+    fn modify_request(&self, req: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        let user_ids: Vec<_> = self.user_id.iter().map(|id| ("user_id", id)).collect();
+        req.query(&user_ids)
+    }
+}
+
+impl PaginatedRequest for StreamsRequest {
+    type Item = Stream;
+
+    fn set_after(&mut self, cursor: Secret) {
+        self.after = Some(cursor);
+    }
+
+    fn into_page(response: Self::Response) -> (Vec<Self::Item>, Pagination) {
+        (response.data, response.pagination)
     }
 }
 
@@ -93,9 +119,13 @@ impl StreamsResponse {
         }
         self.data.pop()
     }
+
+    pub fn into_streams(self) -> Vec<Stream> {
+        self.data
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Stream {
     /// An ID that identifies the stream. You can use this ID later to look up the video on demand (VOD).
     pub id: String,
@@ -149,3 +179,72 @@ pub struct Stream {
     /// A Boolean value that indicates whether the stream is meant for mature audiences.
     pub is_mature: bool,
 }
+
+/// Requires a user access token that includes the channel:read:stream_key scope.
+///
+/// The response is 401 Unauthorized if the access token is not the broadcaster's token, see [`ApiError::is_unauthorized`](crate::error::ApiError::is_unauthorized).
+#[derive(Debug, Serialize)]
+pub struct StreamKeyRequest {
+    /// The ID of the broadcaster that owns the channel. The ID must match the user ID in the access token.
+    pub broadcaster_id: String,
+}
+
+impl Request for StreamKeyRequest {
+    type Encoding = UrlParamEncoding;
+    type Response = StreamKeyResponse;
+
+    fn path(&self) -> &str {
+        "/streams/key"
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StreamKeyResponse {
+    /// A list that contains the channel’s stream key.
+    data: Vec<StreamKey>,
+}
+
+impl StreamKeyResponse {
+    pub fn into_stream_key(mut self) -> Option<Secret> {
+        if self.data.len() > 1 {
+            unreachable!("mulitple stream keys returned");
+        }
+        self.data.pop().map(|data| data.stream_key)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StreamKey {
+    /// The channel’s stream key.
+    pub stream_key: Secret,
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::StatusCode;
+
+    use super::*;
+    use crate::error::{ApiError, ErrorResponse};
+
+    #[test]
+    fn stream_key_stays_masked() {
+        let res: StreamKeyResponse =
+            serde_json::from_str(r#"{"data":[{"stream_key":"live_44322889_a34ub37c8ajv98a0"}]}"#)
+                .unwrap();
+        assert!(!format!("{res:?}").contains("live_44322889"), "{res:?}");
+
+        let key = res.into_stream_key().unwrap();
+        assert_eq!(format!("{key:?}"), r#"Secret("***")"#);
+        assert_eq!(key.access_secret_value(), "live_44322889_a34ub37c8ajv98a0");
+    }
+
+    #[test]
+    fn stream_key_of_another_user_is_unauthorized() {
+        let res: ErrorResponse = serde_json::from_str(
+            r#"{"error":"Unauthorized","status":401,"message":"The ID in broadcaster_id must match the user ID found in the request's OAuth token."}"#,
+        )
+        .unwrap();
+        assert!(ApiError::ErrorResponse(StatusCode::UNAUTHORIZED, res).is_unauthorized());
+        assert!(!ApiError::UnexpectedApiStatus(StatusCode::FORBIDDEN).is_unauthorized());
+    }
+}
@@ -1,51 +1,78 @@
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, Serializer};
 
 use crate::{
-    client::{Request, UrlParamEncoding},
+    client::{Request, UrlParamEncoding, limit_query_params},
+    error::Result,
     secret::Secret,
 };
 
-#[derive(Debug, Serialize)]
+#[derive(Debug)]
 pub struct UsersRequest {
     /// The ID of the user to get. To specify more than one user, include the id parameter for each user to get. For example, id=1234&id=5678. The maximum number of IDs you may specify is 100.
-    #[serde(skip_serializing_if = "Option::is_none")]
-    id: Option<String>,
+    id: Vec<String>,
 
     /// The login name of the user to get. To specify more than one user, include the login parameter for each user to get. For example, login=foo&login=bar. The maximum number of login names you may specify is 100.
-    #[serde(skip_serializing_if = "Option::is_none")]
-    login: Option<String>,
+    login: Vec<String>,
 }
 
 impl UsersRequest {
     pub fn me() -> Self {
         Self {
-            id: None,
-            login: None,
+            id: Vec::new(),
+            login: Vec::new(),
         }
     }
 
     pub fn id(id: String) -> Self {
         Self {
-            id: Some(id),
-            login: None,
+            id: vec![id],
+            login: Vec::new(),
         }
     }
 
     pub fn login(login: String) -> Self {
         Self {
-            id: None,
-            login: Some(login),
+            id: Vec::new(),
+            login: vec![login],
         }
     }
+
+    /// Up to 100 users by id
+    pub fn ids(ids: impl IntoIterator<Item = String>) -> Result<Self> {
+        Ok(Self {
+            id: limit_query_params("id", ids)?,
+            login: Vec::new(),
+        })
+    }
+
+    /// Up to 100 users by login name
+    pub fn logins(logins: impl IntoIterator<Item = String>) -> Result<Self> {
+        Ok(Self {
+            id: Vec::new(),
+            login: limit_query_params("login", logins)?,
+        })
+    }
+}
+
+/// Repeated `id` and `login` parameters, serialized as key value pairs
+impl Serialize for UsersRequest {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let ids = self.id.iter().map(|id| ("id", id));
+        let logins = self.login.iter().map(|login| ("login", login));
+        serializer.collect_seq(ids.chain(logins))
+    }
 }
 
 impl Request for UsersRequest {
     type Encoding = UrlParamEncoding;
     type Response = UsersResponse;
 
-    fn url(&self) -> impl reqwest::IntoUrl {
-        twitch_helix!("/users")
+    fn path(&self) -> &str {
+        "/users"
     }
 }
 
@@ -62,6 +89,10 @@ impl UsersResponse {
         }
         self.data.pop()
     }
+
+    pub fn into_users(self) -> Vec<User> {
+        self.data
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -142,3 +173,23 @@ pub enum BroadcasterType {
     #[serde(rename = "partner ")]
     Partner,
 }
+
+#[cfg(test)]
+impl User {
+    /// User with the given id, for tests of values built from a user
+    pub(crate) fn test(id: &str) -> Self {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "login": "cool_user",
+            "display_name": "Cool_User",
+            "type": "",
+            "broadcaster_type": "",
+            "description": "",
+            "profile_image_url": "",
+            "offline_image_url": "",
+            "view_count": 0,
+            "created_at": "2016-12-14T20:32:28Z",
+        }))
+        .unwrap()
+    }
+}
@@ -0,0 +1,73 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::client::{Request, UrlParamEncoding};
+
+#[derive(Debug, Serialize)]
+pub struct VideosRequest {
+    /// A list of IDs that identify the videos you want to get. To get more than one video, include this parameter for each video you want to get. For example, id=1234&id=5678. You may specify a maximum of 100 IDs. The endpoint ignores duplicate IDs and IDs that weren't found (if there's at least one valid ID).
+    pub id: String,
+}
+
+impl Request for VideosRequest {
+    type Encoding = UrlParamEncoding;
+    type Response = VideosResponse;
+
+    fn path(&self) -> &str {
+        "/videos"
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VideosResponse {
+    /// The list of published videos that match the filter criteria.
+    data: Vec<Video>,
+}
+
+impl VideosResponse {
+    pub fn into_video(mut self) -> Option<Video> {
+        if self.data.len() > 1 {
+            unreachable!("mulitple videos returned");
+        }
+        self.data.pop()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Video {
+    /// An ID that identifies the video.
+    pub id: String,
+
+    /// The ID of the stream that the video originated from if the video's type is "archive;" otherwise, null.
+    pub stream_id: Option<String>,
+
+    /// The ID of the broadcaster that owns the video.
+    pub user_id: String,
+
+    /// The broadcaster's login name.
+    pub user_login: String,
+
+    /// The broadcaster's display name.
+    pub user_name: String,
+
+    /// The video's title.
+    pub title: String,
+
+    /// The video's description.
+    pub description: String,
+
+    /// The date and time, in UTC, of when the video was created. The timestamp is in RFC3339 format.
+    pub created_at: DateTime<Utc>,
+
+    /// The date and time, in UTC, of when the video was published. The timestamp is in RFC3339 format.
+    pub published_at: DateTime<Utc>,
+
+    /// The video's URL.
+    pub url: String,
+
+    /// The number of times that users have watched the video.
+    pub view_count: u32,
+
+    /// The video's length in ISO 8601 duration format. For example, 3m21s represents 3 minutes, 21 seconds.
+    pub duration: String,
+}
@@ -0,0 +1,137 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use crate::config::AwayConfig;
+
+/// Away mode, toggled with `/away` and `/back`
+///
+/// Messages received while away are counted and tagged, chatters that address the broadcaster
+/// get an automatic reply, at most once per cooldown.
+pub struct Away {
+    reply: Option<String>,
+    reply_cooldown: Duration,
+    period: Option<AwayPeriod>,
+    replied_at: HashMap<String, Instant>,
+}
+
+/// Messages received since the broadcaster went away
+#[derive(Debug, Default)]
+pub struct AwayPeriod {
+    /// Overrides the configured reply for this period
+    reply: Option<String>,
+    pub messages: usize,
+    pub mentions: usize,
+}
+
+impl Away {
+    pub fn new(config: AwayConfig) -> Self {
+        Self {
+            reply_cooldown: config.reply_cooldown(),
+            reply: config.reply,
+            period: None,
+            replied_at: HashMap::new(),
+        }
+    }
+
+    /// Messages received since the broadcaster went away, `None` if not away
+    pub fn period(&self) -> Option<&AwayPeriod> {
+        self.period.as_ref()
+    }
+
+    /// Go away or change the reply while away, the reply overrides the configured one
+    pub fn start(&mut self, reply: Option<String>) {
+        self.period.get_or_insert_default().reply = reply;
+    }
+
+    /// Return from away mode, returns the messages received while away
+    pub fn stop(&mut self) -> Option<AwayPeriod> {
+        self.period.take()
+    }
+
+    /// Reply sent to chatters that address the broadcaster, `None` if not away or disabled
+    pub fn reply(&self) -> Option<&str> {
+        let period = self.period.as_ref()?;
+        period.reply.as_deref().or(self.reply.as_deref())
+    }
+
+    /// Count a message received while away, returns the reply if the chatter addressed the
+    /// broadcaster and did not get a reply within the cooldown
+    pub fn message(&mut self, user_id: &str, addressed: bool) -> Option<String> {
+        let period = self.period.as_mut()?;
+        period.messages += 1;
+        if !addressed {
+            return None;
+        }
+        period.mentions += 1;
+
+        let reply = self.reply()?.to_string();
+        let now = Instant::now();
+        if self
+            .replied_at
+            .get(user_id)
+            .is_some_and(|&replied_at| now < replied_at + self.reply_cooldown)
+        {
+            return None;
+        }
+        self.replied_at.insert(user_id.into(), now);
+        Some(reply)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_cooldown(reply_cooldown_secs: u64) -> Away {
+        Away::new(AwayConfig {
+            reply: Some("brb".into()),
+            reply_cooldown_secs,
+        })
+    }
+
+    #[test]
+    fn messages_are_only_counted_while_away() {
+        let mut away = with_cooldown(600);
+        assert_eq!(away.message("1234", true), None);
+        assert!(away.period().is_none());
+
+        away.start(None);
+        assert_eq!(away.message("1234", false), None);
+        assert_eq!(away.message("1234", true).as_deref(), Some("brb"));
+        let period = away.stop().unwrap();
+        assert_eq!(period.messages, 2);
+        assert_eq!(period.mentions, 1);
+        assert!(away.period().is_none());
+    }
+
+    #[test]
+    fn replies_are_limited_per_chatter() {
+        let mut away = with_cooldown(600);
+        away.start(None);
+        assert_eq!(away.message("1234", true).as_deref(), Some("brb"));
+        assert_eq!(away.message("1234", true), None);
+        assert_eq!(away.message("5678", true).as_deref(), Some("brb"));
+        assert_eq!(away.period().unwrap().mentions, 3);
+
+        let mut away = with_cooldown(0);
+        away.start(None);
+        assert_eq!(away.message("1234", true).as_deref(), Some("brb"));
+        assert_eq!(away.message("1234", true).as_deref(), Some("brb"));
+    }
+
+    #[test]
+    fn reply_overrides_config() {
+        let mut away = with_cooldown(600);
+        assert_eq!(away.reply(), None);
+        away.start(Some("back at 5".into()));
+        assert_eq!(away.reply(), Some("back at 5"));
+        assert_eq!(away.message("1234", true).as_deref(), Some("back at 5"));
+
+        let mut away = Away::new(AwayConfig::default());
+        away.start(None);
+        assert_eq!(away.message("1234", true), None);
+        assert_eq!(away.period().unwrap().mentions, 1);
+    }
+}
@@ -2,157 +2,299 @@ use std::{
     collections::HashMap,
     fmt::Write,
     hash::{DefaultHasher, Hash, Hasher},
-    iter,
     num::NonZeroUsize,
     ops::ControlFlow,
-    pin::pin,
-    sync::LazyLock,
+    pin::Pin,
+    sync::{LazyLock, OnceLock},
+    time::Duration,
 };
 
 use anyhow::{Context, Result};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, TimeDelta, Utc};
 use crokey::KeyCombination;
 use crossterm::event::{
     Event as InputEvent, EventStream, KeyCode, KeyEventKind, KeyModifiers, MouseEventKind,
 };
-use futures::{
-    StreamExt,
-    future::{self, Either},
-};
+use futures::StreamExt;
 use nucleo::{Config, Utf32String};
 use ratatui::{
     DefaultTerminal, Frame,
-    buffer::Buffer,
     layout::{Constraint, Layout, Rect},
     style::{Color, Stylize},
     text::{Line, Span, Text},
-    widgets::{Block, Borders, Paragraph, StatefulWidget, Widget, Wrap},
+    widgets::{Block, Borders, Paragraph, Wrap},
 };
-use serde::Deserialize;
-use serde_json::Value;
-use tokio::sync::mpsc;
+use serde::{Deserialize, Serialize};
+use tokio::{sync::mpsc, time::Instant};
 use twitch_api::{
-    channel::{Channel, ChannelsRequest},
-    chat::{ChatAnnouncementColor, SendChatAnnouncementRequest, SendChatMessageRequest},
-    client::AuthenticatedClient,
+    auth::{self, Scopes, TokenResponse},
+    bits::{CheermotesRequest, CheermotesResponse},
+    channel::{Channel, ChannelEditorsRequest},
+    chat::{
+        ChatAnnouncementColor, ChatSettings, SendChatAnnouncementRequest, SendChatMessageRequest,
+        SendShoutoutRequest, UpdateChatSettingsRequest,
+    },
+    client::{AuthenticatedClient, Client},
+    clip::ClipsRequest,
     events::{
         chat::{
-            ChatMessageFragment, ChatMessageMessage, message::ChatMessage,
-            notification::ChatNotification,
+            ChatMessageCheermote, ChatMessageEmoteFormat, ChatMessageFragment, ChatMessageMessage,
+            message::ChatMessage,
         },
-        follow::Follow,
-        stream::{StreamOffline, StreamOnline},
-        ws::{NotificationMessage, WebSocket},
+        ws::{Incoming, WebSocket},
     },
-    stream::{Stream, StreamsRequest},
-    user::User,
+    moderation::{BanUserRequest, DeleteChatMessageRequest, UnbanUserRequest},
+    stream::Stream,
+    user::{User, UsersRequest},
+    video::VideosRequest,
 };
 
 use crate::{
-    config::{Event as SoundEvent, Keybindings},
-    sound_system::SoundSystem,
-    store::{Event, Store},
+    config::{Event as SoundEvent, KeyLookup, KeySequence, Keybindings, PendingKeys, UiConfig},
+    links::TwitchLink,
+    session::{Connection, Poll, Session, Status, connection_lost},
+    store::{Event, Extra, Notification, Store, combo_follows},
 };
 
 pub async fn run(
     mut terminal: DefaultTerminal,
     keybindings: Keybindings,
-    store: Store,
-    client: &mut AuthenticatedClient,
-    user: User,
-    mut ws: WebSocket,
-    sound_system: SoundSystem,
+    ui: UiConfig,
+    session: &mut Session<'_>,
+    ws: WebSocket,
 ) -> Result<()> {
-    let mut state = State {
-        keybindings,
-        store,
-        client,
-        user,
-        sound_system,
-        offset: None,
-        focus: FocusState::None,
-        search: String::new(),
-        message: String::new(),
-        error: String::new(),
-        poll: None,
-    };
+    if !ui.palette.is_empty() {
+        let _ = PALETTE.set(ui.palette.clone());
+    }
 
-    state.store.push(Event::Started {
-        started_at: Utc::now(),
-    })?;
+    let mut state = State::new(keybindings, &ui, session);
 
-    let (sender, mut receiver) = mpsc::unbounded_channel();
-    tokio::task::spawn_local(async move {
-        while let Some(notification) = ws.next().await.transpose() {
-            if sender.send(notification).is_err() {
-                break;
-            }
+    state.session.start()?;
+    state.session.connected(&ws);
+    let expired_events = state.session.store.expired_events();
+    if expired_events > 0 {
+        state.info = format!("removed {expired_events} expired events");
+    }
+    if let Err(err) = state.session.update_follower_total().await {
+        state.set_error(format!("{err:?}"));
+    }
+    if let Err(err) = state.session.update_stream().await {
+        state.set_error(format!("{err:?}"));
+    }
+    if let Err(err) = state.session.update_polls().await {
+        state.set_error(format!("{err:?}"));
+    }
+    match state
+        .session
+        .client
+        .send(&CheermotesRequest {
+            broadcaster_id: Some(state.session.user.id.clone()),
+        })
+        .await
+        .context("load cheermotes")
+    {
+        Ok(cheermotes) => {
+            let _ = CHEERMOTES.set(cheermotes);
         }
-    });
+        Err(err) => state.set_error(format!("{err:?}")),
+    }
+
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    forward_incoming(ws, sender.clone());
 
     let mut events = EventStream::new();
-    let mut events_next = events.next();
+    let mut reauth_poll = None;
 
     loop {
-        state.store.tick();
+        state.session.store.tick();
+        state.clear_expired_error();
+        state.report_store_errors();
+        state.update_date();
+        state.load_older_day();
+
+        match &mut state.reauth {
+            Some(reauth) => {
+                if let Some(poll) = reauth.poll.take() {
+                    reauth_poll = Some(poll);
+                }
+            }
+            // canceled or done
+            None => reauth_poll = None,
+        }
 
         terminal
             .draw(|frame| state.draw(frame))
             .context("draw frame")?;
 
-        match future::select(
-            events_next,
-            future::select(pin!(receiver.recv()), pin!(state.store.search_changed())),
-        )
-        .await
-        {
-            Either::Left((event, _)) => {
-                let event = event.unwrap().context("read input event")?;
+        // the futures are dropped before a branch is handled, they may borrow the state
+        tokio::select! {
+            event = events.next() => {
+                let event = event.context("input event stream ended")?.context("read input event")?;
                 if state.update(event).await?.is_break() {
                     break Ok(());
                 }
-                events_next = events.next();
-            }
-            Either::Right((inner, fut)) => {
-                match inner {
-                    Either::Left((notification, _)) => {
-                        let (timestamp, notification) =
-                            notification.context("unreachable: web socket connection closed")??;
-                        state.handle(timestamp, notification).await?;
-                    }
-                    Either::Right(((), _)) => {
-                        // nothing to do, tick is called anyway
+            }
+            next = receiver.recv() => {
+                let next = next.context("unreachable: web socket task stopped")?;
+                if connection_lost(&next) {
+                    state.session.status.connection = Connection::Reconnecting;
+                    state.info = "web socket connection lost, reconnecting".into();
+                    terminal
+                        .draw(|frame| state.draw(frame))
+                        .context("draw frame")?;
+                    forward_incoming(state.session.reconnect().await?, sender.clone());
+                    state.info = "reconnected and resubscribed".into();
+                } else {
+                    let (timestamp, incoming) = next?.context("web socket connection closed")?;
+                    if let Incoming::Reconnected = incoming {
+                        state.info = "event sub: reconnected web socket".into();
                     }
+                    state.session.handle_incoming(timestamp, incoming).await?;
                 }
-                events_next = fut;
             }
+            () = state.session.due() => {
+                if let Err(err) = state.session.handle_due().await {
+                    state.set_error(format!("{err:#}"));
+                }
+            }
+            res = async { reauth_poll.as_mut().unwrap().await }, if reauth_poll.is_some() => {
+                reauth_poll = None;
+                state.finish_reauth(res).await;
+            }
+            // nothing to do, the error and the search results are updated before drawing
+            () = state.session.store.search_changed() => {}
+            () = state.error_expired() => {}
+            () = state.uptime_changed() => {}
         }
     }
 }
 
-struct State<'a> {
+type Next = Result<Option<(DateTime<Utc>, Incoming)>>;
+
+/// Pass the messages of the web socket to the ui loop until it is closed or the connection is lost
+fn forward_incoming(mut ws: WebSocket, sender: mpsc::UnboundedSender<Next>) {
+    tokio::task::spawn_local(async move {
+        loop {
+            let next = ws.next().await;
+            let last = matches!(next, Ok(None)) || connection_lost(&next);
+            if sender.send(next).is_err() || last {
+                break;
+            }
+        }
+    });
+}
+
+struct State<'a, 's> {
     keybindings: Keybindings,
-    store: Store,
-    client: &'a mut AuthenticatedClient,
-    user: User,
-    sound_system: SoundSystem,
+    pending_keys: PendingKeys,
+    error_timeout: Option<Duration>,
+    error_set_at: Option<Instant>,
+    session: &'s mut Session<'a>,
     offset: Option<NonZeroUsize>,
     focus: FocusState,
     search: String,
     message: String,
     error: String,
-    poll: Option<Poll>,
+
+    /// Output of a command, e.g. `/editors`, shown until the next key press
+    info: String,
+
+    /// Id of the chat message jumped to, highlighted until the next key press
+    highlight: Option<String>,
+    show_status: bool,
+    render_cache: RenderCache,
+
+    /// Number of events when the view was cleared, older events are hidden below a divider
+    cleared_at: Option<usize>,
+    clear_view_scrollback: bool,
+
+    /// Number of events when the terminal lost focus, newer events are shown below a divider
+    unread_at: Option<usize>,
+    scroll_to_unread: bool,
+
+    /// Enter inserts a newline into the message, it is sent with [`Command::SendMessage`]
+    multiline: bool,
+    expand_pasted_links: bool,
+
+    /// The chat message the message is sent as a reply to, see [`Command::Reply`]
+    reply_to: Option<ReplyTo>,
+
+    /// Pending device flow of `/reauth`, canceled with [`Command::Leave`]
+    reauth: Option<Reauth>,
 }
 
-impl State<'_> {
+struct Reauth {
+    verification_uri: String,
+    user_code: String,
+
+    /// Taken by [`run`], which waits for the token next to the other events
+    poll: Option<Pin<Box<dyn Future<Output = Result<TokenResponse>>>>>,
+}
+
+struct ReplyTo {
+    message_id: String,
+    user_name: String,
+}
+
+impl<'a, 's> State<'a, 's> {
+    fn new(keybindings: Keybindings, ui: &UiConfig, session: &'s mut Session<'a>) -> Self {
+        Self {
+            keybindings,
+            pending_keys: PendingKeys::default(),
+            error_timeout: ui.error_timeout(),
+            error_set_at: None,
+            session,
+            offset: None,
+            focus: FocusState::None,
+            search: String::new(),
+            message: String::new(),
+            error: String::new(),
+            info: String::new(),
+            highlight: None,
+            show_status: true,
+            render_cache: RenderCache::default(),
+            cleared_at: None,
+            clear_view_scrollback: ui.clear_view_scrollback,
+            unread_at: None,
+            scroll_to_unread: ui.scroll_to_unread,
+            multiline: false,
+            expand_pasted_links: ui.expand_pasted_links,
+            reply_to: None,
+            reauth: None,
+        }
+    }
+
     fn draw(&mut self, frame: &mut Frame) {
         let mut area = frame.area();
 
+        if self.show_status {
+            let status_area;
+            (status_area, area) = top_area(area, 1);
+            frame.render_widget(self.status_line(), status_area);
+
+            let block_area;
+            (block_area, area) = top_area(area, 1);
+            let block = Block::new().borders(Borders::TOP).dark_gray();
+            frame.render_widget(block, block_area);
+        }
+
         if !self.message.is_empty() || self.focus.is_message() {
+            let lines = self.message.split('\n').enumerate().map(|(i, line)| {
+                let prefix = match i {
+                    0 if self.multiline => "Lines:   ",
+                    0 if self.reply_to.is_some() => "Reply:   ",
+                    0 => "Message: ",
+                    _ => "         ",
+                };
+                Line::from_iter([Span::raw(prefix).dark_gray(), Span::raw(line)])
+            });
+            let reply_to = self.reply_to.as_ref().map(|reply_to| {
+                Line::from(format!("replying to {}", reply_to.user_name)).dark_gray()
+            });
+            let widget = Text::from_iter(reply_to.into_iter().chain(lines));
+
             let message_area;
-            (area, message_area) = bottom_area(area, 1);
-            let widget =
-                Line::from_iter([Span::raw("Message: ").dark_gray(), Span::raw(&self.message)]);
+            (area, message_area) = bottom_area(area, widget.height());
             frame.render_widget(widget, message_area);
 
             let block_area;
@@ -161,10 +303,47 @@ impl State<'_> {
             frame.render_widget(block, block_area);
 
             if let FocusState::Message(offset) = self.focus {
-                frame.set_cursor_position((9 + u16::try_from(offset).unwrap(), message_area.y));
+                let (row, column) = cursor_position(&self.message, offset);
+                frame.set_cursor_position((
+                    9 + u16::try_from(column).unwrap(),
+                    message_area.y + u16::try_from(row).unwrap(),
+                ));
             }
         }
 
+        if let Some(reauth) = &self.reauth {
+            let prompt = Paragraph::new(format!(
+                "Open {} and enter the code {} to authorize again, press esc to cancel",
+                reauth.verification_uri, reauth.user_code,
+            ))
+            .yellow()
+            .wrap(Wrap { trim: false });
+            let height = prompt.line_count(area.width);
+
+            let prompt_area;
+            (area, prompt_area) = bottom_area(area, height);
+            frame.render_widget(prompt, prompt_area);
+
+            let block_area;
+            (area, block_area) = bottom_area(area, 1);
+            let block = Block::new().borders(Borders::TOP).dark_gray();
+            frame.render_widget(block, block_area);
+        }
+
+        if !self.info.is_empty() {
+            let info = Paragraph::new(self.info.as_str()).wrap(Wrap { trim: false });
+            let height = info.line_count(area.width);
+
+            let info_area;
+            (area, info_area) = bottom_area(area, height);
+            frame.render_widget(info, info_area);
+
+            let block_area;
+            (area, block_area) = bottom_area(area, 1);
+            let block = Block::new().borders(Borders::TOP).dark_gray();
+            frame.render_widget(block, block_area);
+        }
+
         if !self.error.is_empty() {
             let error = Paragraph::new(self.error.as_str())
                 .red()
@@ -184,8 +363,15 @@ impl State<'_> {
         if !self.search.is_empty() || self.focus.is_search() {
             let search_area;
             (area, search_area) = bottom_area(area, 1);
-            let widget =
-                Line::from_iter([Span::raw("Search: ").dark_gray(), Span::raw(&self.search)]);
+            // previous days are searched in the background
+            let progress = self.session.store.search_progress().map(|(loaded, days)| {
+                Span::raw(format!("  (searching {loaded}/{days} days)")).dark_gray()
+            });
+            let widget = Line::from_iter(
+                [Span::raw("Search: ").dark_gray(), Span::raw(&self.search)]
+                    .into_iter()
+                    .chain(progress),
+            );
             frame.render_widget(widget, search_area);
 
             let block_area;
@@ -198,40 +384,150 @@ impl State<'_> {
             }
         }
 
-        let events = self.store.events(&mut self.offset);
-        for event in events {
-            frame.render_stateful_widget(event, area, &mut area);
-            if area.height == 0 {
-                break;
-            }
+        let len = self.session.store.events_len();
+        let cleared_at = self.cleared_at.filter(|_| self.search.is_empty());
+        if let (Some(cleared_at), Some(offset)) = (cleared_at, self.offset)
+            && !self.clear_view_scrollback
+            && offset.get() <= cleared_at
+        {
+            self.offset = NonZeroUsize::new(cleared_at + 1);
+        }
+
+        let unread_at = self
+            .unread_at
+            .filter(|&unread_at| unread_at < len && self.search.is_empty());
+
+        let events = self.session.store.events(&mut self.offset);
+        let dividers = Dividers {
+            end: self.offset.map_or(len, NonZeroUsize::get),
+            unread_at,
+            cleared_at,
+            // the events before the marker are only reachable by scrolling up, if enabled
+            show_cleared: self.offset.is_some() && self.clear_view_scrollback,
+        };
+        let Some(area) = draw_events(
+            frame,
+            area,
+            events,
+            &dividers,
+            &mut self.render_cache,
+            self.highlight.as_deref(),
+        ) else {
+            return;
+        };
+
+        if self.session.store.history_limited() {
+            let limit_area;
+            (_, limit_area) = bottom_area(area, 1);
+            let widget = Line::from("history limit reached").dark_gray().italic();
+            frame.render_widget(widget, limit_area);
         }
     }
 
-    fn keybinding(&self, key: KeyCombination) -> Option<Command> {
+    fn status_line(&self) -> Line<'static> {
+        let mut spans = status_spans(&self.session.status, Utc::now());
+
+        let mut field = |name: &str, value: String| {
+            spans.extend(status_field(name, value));
+        };
+
+        if let Some(period) = self.session.away.period() {
+            field(
+                "away",
+                format!("{} messages, {} mentions", period.messages, period.mentions),
+            );
+        }
+        field(
+            "rate limit",
+            match self.session.client.last_rate_limit() {
+                Some(rate_limit) => format!("{}/{}", rate_limit.remaining, rate_limit.limit),
+                None => "-".into(),
+            },
+        );
+        let mut muted = self
+            .session
+            .sound_system
+            .muted_outputs()
+            .collect::<Vec<_>>();
+        if self.session.sound_system.is_muted() {
+            field("sound", "off".into());
+        } else if !muted.is_empty() {
+            muted.sort();
+            field("muted", muted.join(", "));
+        }
+        let skipped_lines = self.session.store.skipped_lines();
+        if skipped_lines > 0 {
+            field("skipped lines", skipped_lines.to_string());
+        }
+
+        Line::from(spans)
+    }
+
+    fn keybinding(&mut self, key: KeyCombination) -> KeyLookup<Command> {
         let keybindings = if self.focus.is_none() {
             &self.keybindings.normal
         } else {
             &self.keybindings.insert
         };
-        keybindings.get(&key).copied()
+        self.pending_keys.lookup(keybindings, key)
     }
 
     async fn update(&mut self, event: InputEvent) -> Result<ControlFlow<()>> {
         match event {
-            InputEvent::FocusGained => {}
-            InputEvent::FocusLost => {}
+            InputEvent::FocusGained => {
+                let len = self.session.store.events_len();
+                match self.unread_at {
+                    Some(unread_at) if unread_at < len => {
+                        if self.scroll_to_unread {
+                            self.offset = NonZeroUsize::new(unread_at + 1);
+                        }
+                    }
+                    _ => self.unread_at = None,
+                }
+            }
+            InputEvent::FocusLost => {
+                self.unread_at = Some(self.session.store.events_len());
+            }
             InputEvent::Key(event) if event.kind == KeyEventKind::Press => {
-                if let Some(command) = self.keybinding(event.into()) {
-                    return self.run(command);
+                self.info = String::new();
+                self.highlight = None;
+                match self.keybinding(event.into()) {
+                    KeyLookup::Command(Command::SendMessage) => {
+                        if self.focus.is_message() {
+                            self.error = String::new();
+                            self.send_message().await?;
+                        }
+                        return Ok(ControlFlow::Continue(()));
+                    }
+                    KeyLookup::Command(Command::DeleteMessage) => {
+                        if let Err(err) = self.delete_visible_message().await {
+                            self.set_error(format!("{err:#}"));
+                        }
+                        return Ok(ControlFlow::Continue(()));
+                    }
+                    KeyLookup::Command(Command::Ban(reason)) => {
+                        if let Err(err) = self.ban_visible_chatter(reason).await {
+                            self.set_error(format!("{err:#}"));
+                        }
+                        return Ok(ControlFlow::Continue(()));
+                    }
+                    KeyLookup::Command(command) => return self.run(command),
+                    // the key may also be text input, e.g. the first key of `j k`
+                    KeyLookup::Pending | KeyLookup::Unbound => {}
                 }
 
                 if event.modifiers.difference(KeyModifiers::SHIFT).is_empty() {
+                    let multiline = self.multiline && self.focus.is_message();
                     let (text, offset) = match &mut self.focus {
                         FocusState::None => return Ok(ControlFlow::Continue(())),
                         FocusState::Message(offset) => (&mut self.message, offset),
                         FocusState::Search(offset) => (&mut self.search, offset),
                     };
                     match event.code {
+                        KeyCode::Enter if multiline => {
+                            text.insert(text.char_to_byte_index(*offset), '\n');
+                            *offset += 1;
+                        }
                         KeyCode::Enter => {
                             self.error = String::new();
                             match self.focus {
@@ -285,7 +581,21 @@ impl State<'_> {
                 MouseEventKind::ScrollLeft => {}
                 MouseEventKind::ScrollRight => {}
             },
-            InputEvent::Paste(_) => {}
+            InputEvent::Paste(text) => {
+                let text = match TwitchLink::parse(&text) {
+                    Some(link) if self.expand_pasted_links && self.focus.is_message() => {
+                        match self.link_title(link).await {
+                            Ok(title) => format!("{title} {}", text.trim()),
+                            Err(err) => {
+                                self.set_error(format!("{err:#}"));
+                                text
+                            }
+                        }
+                    }
+                    _ => text,
+                };
+                self.paste(&text);
+            }
             InputEvent::Resize(_, _) => {}
         }
         Ok(ControlFlow::Continue(()))
@@ -295,7 +605,12 @@ impl State<'_> {
         match command {
             Command::Quit => return Ok(ControlFlow::Break(())),
             Command::Leave => {
-                if !self.focus.is_none() {
+                if self.reauth.take().is_some() {
+                    self.info = "reauthentication canceled".into();
+                } else if !self.focus.is_none() {
+                    if self.focus.is_message() && self.message.is_empty() {
+                        self.reply_to = None;
+                    }
                     self.focus = FocusState::None;
                     self.error = String::new();
                 } else if self.offset.is_some() {
@@ -307,44 +622,281 @@ impl State<'_> {
                     self.do_search();
                 }
             }
+            Command::GoUp | Command::GoDown
+                if self.multiline && self.focus.is_message() && self.message.contains('\n') =>
+            {
+                if let FocusState::Message(offset) = self.focus {
+                    let (row, column) = cursor_position(&self.message, offset);
+                    let row = match command {
+                        Command::GoUp => row.saturating_sub(1),
+                        _ => row + 1,
+                    };
+                    self.focus = FocusState::Message(cursor_offset(&self.message, row, column));
+                }
+            }
             Command::GoUp => {
                 self.offset = NonZeroUsize::new({
                     if let Some(offset) = self.offset {
                         offset.get()
                     } else {
-                        self.store.events_len()
+                        self.session.store.events_len()
                     }
                     .saturating_sub(1)
                 })
                 .or_else(|| NonZeroUsize::new(1))
             }
+            Command::GoTop => {
+                self.offset = NonZeroUsize::new(1);
+            }
             Command::GoDown => {
                 if let Some(offset) = self.offset {
                     let offset = offset.get() + 1;
-                    self.offset = if offset < self.store.events_len() {
+                    self.offset = if offset < self.session.store.events_len() {
                         NonZeroUsize::new(offset)
                     } else {
                         None
                     };
                 }
             }
+            Command::GoToParent => self.go_to_parent(),
             Command::Search => {
                 self.focus = FocusState::Search(0);
             }
+            Command::ToggleStatus => {
+                self.show_status ^= true;
+            }
+            Command::ClearView => {
+                self.cleared_at = Some(self.session.store.events_len());
+                self.offset = None;
+            }
             Command::Message => {
                 self.focus = FocusState::Message(0);
             }
+            Command::Reply => self.reply(),
+            Command::ToggleMultiline => {
+                self.multiline ^= true;
+            }
+            Command::SendMessage | Command::DeleteMessage | Command::Ban(_) => {
+                // handled in `update`, sending is async
+            }
+            Command::ToggleSound => {
+                self.session.sound_system.toggle_muted();
+            }
+            Command::ToggleMute(output) => {
+                if let Err(err) = self.session.sound_system.toggle_mute(&output) {
+                    self.set_error(format!("{err:#}"));
+                }
+            }
         }
         Ok(ControlFlow::Continue(()))
     }
 
+    /// Start the device flow, [`run`] waits for the token and calls [`State::finish_reauth`]
+    async fn start_reauth(&mut self) -> Result<()> {
+        if self.reauth.is_some() {
+            self.set_error("reauthentication already in progress");
+            return Ok(());
+        }
+        let client_id = self.session.client.token_manager_mut().client_id().clone();
+        let scopes: Scopes = crate::twitch::SCOPES.into_iter().collect();
+        let client = Client::new();
+        let device = auth::request_device_code(&client, client_id.clone(), scopes.clone()).await?;
+        self.reauth = Some(Reauth {
+            verification_uri: device.verification_uri.access_secret_value().into(),
+            user_code: device.user_code.access_secret_value().into(),
+            poll: Some(Box::pin(async move {
+                auth::poll_device_token(&client, client_id, scopes, &device).await
+            })),
+        });
+        Ok(())
+    }
+
+    /// Use the new tokens and recreate the subscriptions with them
+    async fn finish_reauth(&mut self, res: Result<TokenResponse>) {
+        self.reauth = None;
+        let result = match res {
+            Ok(res) => self.use_tokens(res).await,
+            Err(err) => Err(err),
+        };
+        match result {
+            Ok(()) => self.info = "reauthenticated and resubscribed".into(),
+            Err(err) => self.set_error(format!("reauthentication failed: {err:#}")),
+        }
+    }
+
+    async fn use_tokens(&mut self, res: TokenResponse) -> Result<()> {
+        self.session
+            .client
+            .token_manager_mut()
+            .set_tokens(res)
+            .context("save tokens")?;
+        self.session
+            .subscriptions
+            .resubscribe(self.session.client, &self.session.user)
+            .await
+    }
+
+    /// Keep the scroll offset and dividers at their events when the store continued with a new
+    /// day, transient events of the previous day are dropped
+    fn update_date(&mut self) {
+        let Some(change) = self.session.store.take_day_change() else {
+            return;
+        };
+        self.offset = self
+            .offset
+            .and_then(|offset| NonZeroUsize::new(change.index(offset.get())));
+        self.cleared_at = self.cleared_at.map(|cleared_at| change.index(cleared_at));
+        self.unread_at = self.unread_at.map(|unread_at| change.index(unread_at));
+    }
+
+    /// Shout out the channel of the user, returns the display name
+    async fn shoutout(&mut self, login: &str) -> Result<String> {
+        let user = lookup_user(self.session.client, login).await?;
+        let from_broadcaster_id = self.session.user.id.clone();
+        let (client, moderator) = self.session.sender();
+        client
+            .send(&SendShoutoutRequest {
+                from_broadcaster_id,
+                to_broadcaster_id: user.id.clone(),
+                moderator_id: moderator.id.clone(),
+            })
+            .await
+            .with_context(|| format!("send shoutout to {}", user.display_name))?;
+        self.session.shoutouts.sent(&user.id);
+        Ok(user.display_name)
+    }
+
+    /// Change the chat settings of the channel, the other settings are kept
+    async fn update_chat_settings(&mut self, update: UpdateChatSettingsRequest) -> Result<()> {
+        let broadcaster_id = self.session.user.id.clone();
+        let (client, moderator) = self.session.sender();
+        let settings = client
+            .send(&UpdateChatSettingsRequest {
+                broadcaster_id,
+                moderator_id: moderator.id.clone(),
+                ..update
+            })
+            .await
+            .context("update chat settings")?
+            .into_chat_settings()
+            .context("missing chat settings")?;
+        self.info = format_chat_modes(&settings);
+        Ok(())
+    }
+
+    /// Continue scrolling into the previous day, the indices of all events shift by its length
+    fn load_older_day(&mut self) {
+        let len = self.session.store.load_older_day(self.offset);
+        if len == 0 {
+            return;
+        }
+        self.offset = self.offset.and_then(|offset| offset.checked_add(len));
+        self.cleared_at = self.cleared_at.map(|cleared_at| cleared_at + len);
+        self.unread_at = self.unread_at.map(|unread_at| unread_at + len);
+    }
+
+    /// Write a reply to the newest visible chat message
+    fn reply(&mut self) {
+        if !self.search.is_empty() {
+            self.set_error("can not reply while searching");
+            return;
+        }
+        let Some(message) = self.session.store.chat_message_at(&mut self.offset) else {
+            self.set_error("no chat message visible");
+            return;
+        };
+        self.reply_to = Some(ReplyTo {
+            message_id: message.message_id.clone(),
+            user_name: message.chatter_user_name.clone(),
+        });
+        self.focus = FocusState::Message(self.message.chars().count());
+    }
+
+    /// Scroll to the parent of the newest visible reply, or show its body if it is not loaded
+    fn go_to_parent(&mut self) {
+        if !self.search.is_empty() {
+            self.set_error("can not go to the parent message while searching");
+            return;
+        }
+        match find_parent(&mut self.session.store, &mut self.offset) {
+            Some(Parent::Loaded { offset, message_id }) => {
+                self.offset = Some(offset);
+                self.highlight = Some(message_id);
+            }
+            Some(Parent::NotLoaded(info)) => self.info = info,
+            None => self.set_error("no reply visible"),
+        }
+    }
+
+    /// Delete the newest visible chat message, like [`Command::GoToParent`] it is not available
+    /// while searching
+    async fn delete_visible_message(&mut self) -> Result<()> {
+        anyhow::ensure!(
+            self.search.is_empty(),
+            "can not delete a message while searching"
+        );
+        let (message_id, user_name) = self
+            .session
+            .store
+            .chat_message_at(&mut self.offset)
+            .map(|message| {
+                (
+                    message.message_id.clone(),
+                    message.chatter_user_name.clone(),
+                )
+            })
+            .context("no chat message visible")?;
+        self.delete_message(message_id).await?;
+        self.info = format!("deleted message of {user_name}");
+        Ok(())
+    }
+
+    /// Ban the author of the newest visible chat message with a preset reason, like
+    /// [`Command::DeleteMessage`] it is not available while searching
+    async fn ban_visible_chatter(&mut self, reason: String) -> Result<()> {
+        anyhow::ensure!(
+            self.search.is_empty(),
+            "can not ban a chatter while searching"
+        );
+        let broadcaster_id = self.session.user.id.clone();
+        let moderator_id = self.session.sender().1.id.clone();
+        let message = self
+            .session
+            .store
+            .chat_message_at(&mut self.offset)
+            .context("no chat message visible")?;
+        let user_name = message.chatter_user_name.clone();
+        let req = quick_ban(broadcaster_id, moderator_id, message, reason.clone());
+        let (client, _) = self.session.sender();
+        client.send(&req).await.context("ban user")?;
+        self.info = format!("banned {user_name}: {reason}");
+        Ok(())
+    }
+
+    async fn delete_message(&mut self, message_id: String) -> Result<()> {
+        let broadcaster_id = self.session.user.id.clone();
+        let (client, moderator) = self.session.sender();
+        client
+            .send(&DeleteChatMessageRequest {
+                broadcaster_id,
+                moderator_id: moderator.id.clone(),
+                message_id: Some(message_id),
+            })
+            .await
+            .context("delete chat message")?;
+        Ok(())
+    }
+
     async fn send_message(&mut self) -> Result<()> {
+        if self.message.contains('\n') {
+            self.message = join_lines(&self.message);
+        }
         let message = if let Some(message) = self.message.strip_prefix('/') {
             let (cmd, text) = message.split_once(' ').unwrap_or((message, ""));
             match (cmd, text) {
                 ("poll", _) => {
-                    if self.poll.is_some() {
-                        self.error = "poll already active, try #end poll".into();
+                    if self.session.poll.is_some() {
+                        self.set_error("poll already active, try #end poll");
                         return Ok(());
                     }
 
@@ -358,24 +910,42 @@ impl State<'_> {
                         options.push(option.into());
                         write!(message, " {i}={option}").unwrap();
                     }
-                    self.poll = Some(Poll {
-                        options,
-                        votes: Default::default(),
-                    });
+                    self.session.poll = Some(Poll::new(options));
                     message
                 }
                 ("end", "poll") => {
-                    let Some(poll) = self.poll.take() else {
-                        self.error = "no active poll".into();
+                    let Some(poll) = self.session.poll.take() else {
+                        self.set_error("no active poll");
                         return Ok(());
                     };
                     poll.result()
                 }
                 ("announce", _) if !text.is_empty() => {
-                    self.client
+                    // `/announce #channel text` announces in a channel the user moderates
+                    let (broadcaster_id, text) = match text.strip_prefix('#') {
+                        Some(text) => {
+                            let (login, text) = text.split_once(' ').unwrap_or((text, ""));
+                            let broadcaster = self
+                                .session
+                                .client
+                                .send(&UsersRequest::login(login.into()))
+                                .await
+                                .context("get announcement channel")?
+                                .into_user()
+                                .with_context(|| format!("unknown channel: #{login}"))?;
+                            (broadcaster.id, text.trim())
+                        }
+                        None => (self.session.user.id.clone(), text),
+                    };
+                    if text.is_empty() {
+                        self.set_error("empty announcement");
+                        return Ok(());
+                    }
+                    let (client, moderator) = self.session.sender();
+                    client
                         .send(&SendChatAnnouncementRequest {
-                            broadcaster_id: self.user.id.clone(),
-                            moderator_id: self.user.id.clone(),
+                            broadcaster_id,
+                            moderator_id: moderator.id.clone(),
                             message: text.into(),
                             color: ChatAnnouncementColor::Primary,
                         })
@@ -384,31 +954,206 @@ impl State<'_> {
                     self.clear_message();
                     return Ok(());
                 }
+                ("ban", _) | ("timeout", _) if !text.is_empty() => {
+                    // `/ban login [reason]` and `/timeout login seconds [reason]`
+                    let (login, text) = text.split_once(' ').unwrap_or((text, ""));
+                    let (duration, reason) = if cmd == "timeout" {
+                        let (duration, reason) = text.split_once(' ').unwrap_or((text, ""));
+                        match duration.parse::<u32>() {
+                            Ok(duration) => (Some(duration), reason.trim()),
+                            Err(_) => {
+                                self.set_error(format!("invalid timeout duration: {duration:?}"));
+                                return Ok(());
+                            }
+                        }
+                    } else {
+                        (None, text.trim())
+                    };
+                    let user = lookup_user(self.session.client, login).await?;
+                    let broadcaster_id = self.session.user.id.clone();
+                    let (client, moderator) = self.session.sender();
+                    let ban = client
+                        .send(&BanUserRequest {
+                            broadcaster_id,
+                            moderator_id: moderator.id.clone(),
+                            user_id: user.id,
+                            duration,
+                            reason: (!reason.is_empty()).then(|| reason.into()),
+                        })
+                        .await
+                        .context("ban user")?
+                        .into_ban();
+                    self.info = match ban.and_then(|ban| ban.end_time) {
+                        Some(end_time) => format!(
+                            "timed out {} until {}",
+                            user.display_name,
+                            end_time.with_timezone(crate::timezone()).format("%T"),
+                        ),
+                        None => format!("banned {}", user.display_name),
+                    };
+                    self.clear_message();
+                    return Ok(());
+                }
+                ("unban", _) if !text.is_empty() => {
+                    let user = lookup_user(self.session.client, text.trim()).await?;
+                    let broadcaster_id = self.session.user.id.clone();
+                    let (client, moderator) = self.session.sender();
+                    client
+                        .send(&UnbanUserRequest {
+                            broadcaster_id,
+                            moderator_id: moderator.id.clone(),
+                            user_id: user.id,
+                        })
+                        .await
+                        .context("unban user")?;
+                    self.info = format!("unbanned {}", user.display_name);
+                    self.clear_message();
+                    return Ok(());
+                }
+                ("so", _) if !text.is_empty() => {
+                    // shoutouts are rate limited, a failed shoutout keeps the message for a retry
+                    let login = text.trim().to_owned();
+                    match self.shoutout(&login).await {
+                        Ok(user_name) => {
+                            self.info = format!("shouted out {user_name}");
+                            self.clear_message();
+                        }
+                        Err(err) => self.set_error(format!("{err:#}")),
+                    }
+                    return Ok(());
+                }
+                ("slow", _) | ("followers", _) => {
+                    // `/slow [seconds|off]` and `/followers [minutes|off]`
+                    let Some((enabled, duration)) = parse_chat_mode(text) else {
+                        self.set_error(format!("invalid {cmd} mode duration: {:?}", text.trim()));
+                        return Ok(());
+                    };
+                    let update = if cmd == "slow" {
+                        UpdateChatSettingsRequest {
+                            slow_mode: Some(enabled),
+                            slow_mode_wait_time: duration,
+                            ..Default::default()
+                        }
+                    } else {
+                        UpdateChatSettingsRequest {
+                            follower_mode: Some(enabled),
+                            follower_mode_duration: duration,
+                            ..Default::default()
+                        }
+                    };
+                    self.update_chat_settings(update).await?;
+                    self.clear_message();
+                    return Ok(());
+                }
+                ("emoteonly", "" | "off") => {
+                    let update = UpdateChatSettingsRequest {
+                        emote_mode: Some(text.is_empty()),
+                        ..Default::default()
+                    };
+                    self.update_chat_settings(update).await?;
+                    self.clear_message();
+                    return Ok(());
+                }
+                ("away", _) => {
+                    // `/away [reply]` overrides the configured reply until `/back`
+                    let reply = text.trim();
+                    self.session
+                        .away
+                        .start((!reply.is_empty()).then(|| reply.into()));
+                    self.info = match self.session.away.reply() {
+                        Some(reply) => format!("away, replying with {reply:?}"),
+                        None => "away".into(),
+                    };
+                    self.clear_message();
+                    return Ok(());
+                }
+                ("back", "") => {
+                    let Some(period) = self.session.away.stop() else {
+                        self.set_error("not away");
+                        return Ok(());
+                    };
+                    self.info = format!(
+                        "back, {} messages and {} mentions while away",
+                        period.messages, period.mentions,
+                    );
+                    self.clear_message();
+                    return Ok(());
+                }
+                ("reauth", "") => {
+                    self.start_reauth().await?;
+                    self.clear_message();
+                    return Ok(());
+                }
+                ("delete", _) if !text.is_empty() => {
+                    let message_id = text.trim().to_string();
+                    self.delete_message(message_id).await?;
+                    self.info = "deleted message".into();
+                    self.clear_message();
+                    return Ok(());
+                }
+                // twitch sends `/me` as an action message, rendered in italic
+                ("me", _) if !text.is_empty() => self.message.clone(),
+                ("editors", "") => {
+                    let editors = self
+                        .session
+                        .client
+                        .send(&ChannelEditorsRequest {
+                            broadcaster_id: self.session.user.id.clone(),
+                        })
+                        .await
+                        .context("get channel editors")?
+                        .data;
+                    self.info = if editors.is_empty() {
+                        "no channel editors".into()
+                    } else {
+                        let mut info = "channel editors:".to_string();
+                        for editor in editors {
+                            write!(
+                                info,
+                                "\n  {} (since {})",
+                                editor.user_name,
+                                editor
+                                    .created_at
+                                    .with_timezone(crate::timezone())
+                                    .format("%F"),
+                            )
+                            .unwrap();
+                        }
+                        info
+                    };
+                    self.clear_message();
+                    return Ok(());
+                }
                 ("pin", _) if !text.is_empty() => {
-                    self.error = "/pin not yet exposed by the twitch API".into();
+                    self.set_error("/pin not yet exposed by the twitch API");
                     self.clear_message();
                     return Ok(());
                 }
                 ("unpin", "") => {
-                    self.error = "/unpin not yet exposed by the twitch API".into();
+                    self.set_error("/unpin not yet exposed by the twitch API");
                     self.clear_message();
                     return Ok(());
                 }
                 _ => {
-                    self.error = format!("unknown command: /{cmd} {text:?}");
+                    self.set_error(format!("unknown command: /{cmd} {text:?}"));
                     return Ok(());
                 }
             }
         } else {
             self.message.clone()
         };
-        let message = self
-            .client
+        let broadcaster_id = self.session.user.id.clone();
+        let reply_parent_message_id = self
+            .reply_to
+            .as_ref()
+            .map(|reply_to| reply_to.message_id.clone());
+        let (client, sender) = self.session.sender();
+        let message = client
             .send(&SendChatMessageRequest {
-                broadcaster_id: self.user.id.clone(),
-                sender_id: self.user.id.clone(),
+                broadcaster_id,
+                sender_id: sender.id.clone(),
                 message,
-                reply_parent_message_id: None,
+                reply_parent_message_id,
             })
             .await
             .context("send message")?
@@ -417,78 +1162,123 @@ impl State<'_> {
         if message.is_sent {
             self.clear_message();
         } else {
-            self.error = if let Some(drop_reason) = message.drop_reason {
+            self.set_error(if let Some(drop_reason) = message.drop_reason {
                 format!(
                     "failed to send message ({}): {}",
                     drop_reason.code, drop_reason.message
                 )
             } else {
                 "failed to send message: no drop reason".into()
-            };
+            });
         }
         Ok(())
     }
 
+    /// Show the errors of loading stored events, they are not fatal
+    fn report_store_errors(&mut self) {
+        let errors = self.session.store.take_errors();
+        if !errors.is_empty() {
+            self.set_error(errors.join("\n"));
+        }
+    }
+
+    /// Show the error and play the error sound, the error is hidden after the error timeout
+    fn set_error(&mut self, error: impl Into<String>) {
+        self.error = error.into();
+        self.error_set_at = Some(Instant::now());
+        self.session
+            .sound_system
+            .play_sound_for_event(SoundEvent::Error);
+    }
+
+    fn error_expired(&self) -> impl Future<Output = ()> + 'static {
+        let deadline = self.error_set_at.zip(self.error_timeout);
+        async move {
+            if let Some((set_at, timeout)) = deadline {
+                tokio::time::sleep_until(set_at + timeout).await
+            } else {
+                std::future::pending().await
+            }
+        }
+    }
+
+    /// Resolves when the displayed uptime changes, i.e. at the next full minute of the stream
+    fn uptime_changed(&self) -> impl Future<Output = ()> + 'static {
+        let uptime = self.session.status.uptime();
+        async move {
+            if let Some(uptime) = uptime {
+                let seconds = 60 - uptime.num_seconds().rem_euclid(60);
+                tokio::time::sleep(Duration::from_secs(seconds as u64)).await
+            } else {
+                std::future::pending().await
+            }
+        }
+    }
+
+    fn clear_expired_error(&mut self) {
+        if let Some((set_at, timeout)) = self.error_set_at.zip(self.error_timeout)
+            && set_at.elapsed() >= timeout
+        {
+            self.error = String::new();
+            self.error_set_at = None;
+        }
+    }
+
+    /// Insert the text at the cursor, newlines are kept only in multi-line mode
+    fn paste(&mut self, text: &str) {
+        let text = if self.multiline && self.focus.is_message() {
+            text.replace("\r\n", "\n")
+        } else {
+            text.replace(['\r', '\n'], " ")
+        };
+        let (input, offset) = match &mut self.focus {
+            FocusState::None => return,
+            FocusState::Message(offset) => (&mut self.message, offset),
+            FocusState::Search(offset) => (&mut self.search, offset),
+        };
+        input.insert_str(input.char_to_byte_index(*offset), &text);
+        *offset += text.chars().count();
+        if self.focus.is_search() {
+            self.do_search();
+        }
+    }
+
+    /// Readable title of a clip or video, e.g. `Nice shot (clip by someone)`
+    async fn link_title(&mut self, link: TwitchLink) -> Result<String> {
+        Ok(match link {
+            TwitchLink::Clip(id) => {
+                let clip = self
+                    .session
+                    .client
+                    .send(&ClipsRequest { id })
+                    .await
+                    .context("get clip")?
+                    .into_clip()
+                    .context("clip not found")?;
+                format!("{} (clip by {})", clip.title, clip.creator_name)
+            }
+            TwitchLink::Video(id) => {
+                let video = self
+                    .session
+                    .client
+                    .send(&VideosRequest { id })
+                    .await
+                    .context("get video")?
+                    .into_video()
+                    .context("video not found")?;
+                format!("{} (video by {})", video.title, video.user_name)
+            }
+        })
+    }
+
     fn clear_message(&mut self) {
         self.message = String::new();
         self.focus = FocusState::None;
-    }
-
-    async fn handle(
-        &mut self,
-        timestamp: DateTime<Utc>,
-        notification: NotificationMessage,
-    ) -> Result<()> {
-        let extra = if let Some(message) = notification.event::<ChatMessage>()? {
-            self.sound_system.play_sound_for_event(SoundEvent::Message);
-
-            if let Some(poll) = &mut self.poll {
-                poll.vote(&message.chatter_user_id, &message.message.text);
-            }
-
-            Value::Null
-        } else if let Some(_notification) = notification.event::<ChatNotification>()? {
-            self.sound_system.play_sound_for_event(SoundEvent::Message);
-            Value::Null
-        } else if let Some(_follow) = notification.event::<Follow>()? {
-            self.sound_system.play_sound_for_event(SoundEvent::Follow);
-            Value::Null
-        } else if let Some(online) = notification.event::<StreamOnline>()? {
-            self.sound_system.play_sound_for_event(SoundEvent::Online);
-
-            let stream = self
-                .client
-                .send(&StreamsRequest::user_id(online.broadcaster_user_id))
-                .await
-                .context("load stream info")?
-                .into_stream()
-                .context("missing stream")?;
-
-            serde_json::to_value(stream).context("convert stream info to value")?
-        } else if let Some(offline) = notification.event::<StreamOffline>()? {
-            self.sound_system.play_sound_for_event(SoundEvent::Offline);
-
-            let channel = self
-                .client
-                .send(&ChannelsRequest::id(offline.broadcaster_user_id))
-                .await
-                .context("load channel info")?
-                .into_channel()
-                .context("missing channel")?;
-
-            serde_json::to_value(channel).context("convert channel info to value")?
-        } else {
-            Value::Null
-        };
-        self.store.push(Event::Notification {
-            timestamp,
-            event: notification.into_event(),
-            extra,
-        })
+        self.reply_to = None;
     }
 
     fn do_search(&mut self) {
-        self.store.start_search(&self.search);
+        self.session.store.start_search(&self.search);
     }
 
     fn autocomplete(&mut self) {
@@ -531,9 +1321,43 @@ impl State<'_> {
             return;
         }
 
-        let word = message.split_whitespace().next_back().unwrap();
-        if let Some(_needle) = word.strip_prefix('@') {
-            // TODO: complete user name
+        let word_start = message
+            .char_indices()
+            .rev()
+            .find(|(_, c)| c.is_whitespace())
+            .map_or(0, |(i, c)| i + c.len_utf8());
+        let Some(needle) = message[word_start..].strip_prefix('@') else {
+            return;
+        };
+        let needle: Utf32String = needle.to_lowercase().as_str().into();
+        if needle.is_empty() {
+            return;
+        }
+
+        let names = self.session.store.chatter_names();
+        if names.is_empty() {
+            self.set_error("no chatters to complete yet");
+            return;
+        }
+
+        let mut matcher = nucleo::Matcher::new(Config::DEFAULT);
+        let max_match = names
+            .into_iter()
+            .filter_map(|name| {
+                let haystack: Utf32String = name.as_str().into();
+                matcher
+                    .fuzzy_match(haystack.slice(..), needle.slice(..))
+                    .map(|score| (score, name))
+            })
+            .max();
+
+        if let Some((_score, name)) = max_match {
+            let prefix = &self.message[..word_start];
+            let rest = &self.message[index..];
+            let rest = rest.strip_prefix(' ').unwrap_or(rest);
+            let offset = prefix.chars().count() + name.chars().count() + 2;
+            self.message = format!("{prefix}@{name} {rest}");
+            self.focus = FocusState::Message(offset);
         }
     }
 }
@@ -559,19 +1383,48 @@ impl FocusState {
     }
 }
 
-#[derive(Debug, Clone, Copy, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename = "snake_case")]
 pub enum Command {
     Quit,
     Leave,
     GoUp,
     GoDown,
+    GoTop,
+
+    /// Scroll to the message the newest visible reply answers
+    GoToParent,
+
+    /// Write a reply to the newest visible chat message
+    Reply,
     Search,
     Message,
+    ToggleStatus,
+
+    /// Hide the events received so far, without clearing the twitch chat
+    ClearView,
+
+    /// Switch the message input between a single line and multiple lines
+    ToggleMultiline,
+
+    /// Send the message, needed in multi-line mode where enter inserts a newline
+    SendMessage,
+
+    /// Delete the newest visible chat message from the twitch chat
+    DeleteMessage,
+
+    /// Ban the author of the newest visible chat message with the given reason
+    Ban(String),
+
+    /// Mute or unmute all sounds
+    ToggleSound,
+
+    /// Mute or unmute the sound output with the given name
+    ToggleMute(String),
 }
 
 impl Command {
-    pub fn normal_keybindings() -> impl Iterator<Item = (KeyCombination, Self)> {
+    pub fn normal_keybindings() -> impl Iterator<Item = (KeySequence, Self)> {
         [
             (crokey::key! {q}, Self::Quit),
             (crokey::key! {esc}, Self::Leave),
@@ -579,36 +1432,170 @@ impl Command {
             (crokey::key! {j}, Self::GoDown),
             (crokey::key! {'/'}, Self::Search),
             (crokey::key! {o}, Self::Message),
+            (crokey::key! {s}, Self::ToggleStatus),
+            (crokey::key! {ctrl-l}, Self::ClearView),
+            (crokey::key! {m}, Self::ToggleSound),
+            (crokey::key! {r}, Self::GoToParent),
+            (crokey::key! {shift-r}, Self::Reply),
         ]
         .into_iter()
+        .map(|(key, command)| (key.into(), command))
+        .chain([
+            (
+                KeySequence(vec![crokey::key! {g}, crokey::key! {g}]),
+                Self::GoTop,
+            ),
+            (
+                KeySequence(vec![crokey::key! {d}, crokey::key! {d}]),
+                Self::DeleteMessage,
+            ),
+        ])
     }
 
-    pub fn insert_keybindings() -> impl Iterator<Item = (KeyCombination, Self)> {
+    pub fn insert_keybindings() -> impl Iterator<Item = (KeySequence, Self)> {
         [
             //
             (crokey::key! {esc}, Self::Leave),
             (crokey::key! {up}, Self::GoUp),
             (crokey::key! {down}, Self::GoDown),
+            (crokey::key! {alt-m}, Self::ToggleMultiline),
+            (crokey::key! {alt-enter}, Self::SendMessage),
         ]
         .into_iter()
+        .map(|(key, command)| (key.into(), command))
     }
 }
 
-impl StatefulWidget for &Event {
-    type State = Rect;
+/// Positions of the dividers between the events, as event indices
+struct Dividers {
+    /// Index after the newest drawn event
+    end: usize,
+    unread_at: Option<usize>,
+    cleared_at: Option<usize>,
 
-    fn render(self, mut area: Rect, buf: &mut Buffer, state: &mut Self::State) {
-        let paragraph = Paragraph::new(self.to_text().unwrap_or_else(|err| {
-            Line::from_iter([
-                Span::raw("Error: ").bold().red(),
-                Span::raw(format!("{err}")).red(),
-            ])
-            .into()
-        }))
-        .wrap(Wrap { trim: false });
-        let height = paragraph.line_count(area.width);
-        (*state, area) = bottom_area(area, height);
-        paragraph.render(area, buf)
+    /// Continue with the events before the `view cleared` divider
+    show_cleared: bool,
+}
+
+/// Draw the events from the bottom up, newest first
+///
+/// Returns the remaining area above the events, `None` if the events filled the area or ended at
+/// the `view cleared` divider.
+fn draw_events<'a>(
+    frame: &mut Frame,
+    mut area: Rect,
+    events: impl Iterator<Item = &'a Event>,
+    dividers: &Dividers,
+    render_cache: &mut RenderCache,
+    highlight: Option<&str>,
+) -> Option<Rect> {
+    let end = dividers.end;
+    for (i, event) in events.enumerate() {
+        if dividers
+            .unread_at
+            .is_some_and(|unread_at| end - i == unread_at)
+        {
+            let divider_area;
+            (area, divider_area) = bottom_area(area, 1);
+            let divider = Block::new()
+                .borders(Borders::TOP)
+                .title("unread since you left")
+                .yellow();
+            frame.render_widget(divider, divider_area);
+            if area.height == 0 {
+                return None;
+            }
+        }
+
+        if dividers
+            .cleared_at
+            .is_some_and(|cleared_at| end - i == cleared_at)
+        {
+            let divider_area;
+            (area, divider_area) = bottom_area(area, 1);
+            let divider = Block::new()
+                .borders(Borders::TOP)
+                .title("view cleared")
+                .dark_gray();
+            frame.render_widget(divider, divider_area);
+            if area.height == 0 || !dividers.show_cleared {
+                return None;
+            }
+        }
+
+        let highlighted = highlight.is_some_and(|highlight| {
+            event
+                .chat_message()
+                .is_some_and(|message| message.message_id == highlight)
+        });
+        let (paragraph, height) = render_cache.get(event, area.width);
+        let event_area;
+        (area, event_area) = bottom_area(area, *height);
+        if highlighted {
+            frame.render_widget(paragraph.clone().on_dark_gray(), event_area);
+        } else {
+            frame.render_widget(paragraph, event_area);
+        }
+        if area.height == 0 {
+            return None;
+        }
+    }
+    Some(area)
+}
+
+/// Rendered events for the current width, such that redraws do not parse the events again
+#[derive(Default)]
+struct RenderCache {
+    width: u16,
+    entries: HashMap<DateTime<Utc>, (Paragraph<'static>, usize)>,
+
+    /// Number of rendered events, to check that redraws use the cache
+    #[cfg(test)]
+    renders: usize,
+}
+
+impl RenderCache {
+    const CAPACITY: usize = 4096;
+
+    fn get(&mut self, event: &Event, width: u16) -> &(Paragraph<'static>, usize) {
+        if self.width != width || self.entries.len() >= Self::CAPACITY {
+            self.width = width;
+            self.entries.clear();
+        }
+
+        // events are identified by their timestamp, twitch resends the same message with the same timestamp
+        self.entries.entry(event.timestamp()).or_insert_with(|| {
+            #[cfg(test)]
+            {
+                self.renders += 1;
+            }
+            let paragraph = Paragraph::new(event.to_text().unwrap_or_else(|err| {
+                Line::from_iter([
+                    Span::raw("Error: ").bold().red(),
+                    Span::raw(format!("{err}")).red(),
+                ])
+                .into()
+            }))
+            .wrap(Wrap { trim: false });
+            let height = paragraph.line_count(width);
+            (paragraph, height)
+        })
+    }
+}
+
+/// Ban of the message author, the message gives the user id such that no login lookup is needed
+fn quick_ban(
+    broadcaster_id: String,
+    moderator_id: String,
+    message: &ChatMessage,
+    reason: String,
+) -> BanUserRequest {
+    BanUserRequest {
+        broadcaster_id,
+        moderator_id,
+        user_id: message.chatter_user_id.clone(),
+        duration: None,
+        reason: Some(reason),
     }
 }
 
@@ -619,8 +1606,15 @@ fn bottom_area(area: Rect, height: usize) -> (Rect, Rect) {
     (remaining, area)
 }
 
+fn top_area(area: Rect, height: usize) -> (Rect, Rect) {
+    let height = height.min(area.height as usize) as u16;
+    let layout = Layout::vertical([Constraint::Length(height), Constraint::Fill(1)]);
+    let [area, remaining] = layout.areas(area);
+    (area, remaining)
+}
+
 impl Event {
-    fn to_text(&self) -> Result<Text> {
+    fn to_text(&self) -> Result<Text<'static>> {
         Ok(match self {
             Self::Started { started_at } => {
                 Line::from_iter([started_at.to_span(), "chat started".italic()])
@@ -631,79 +1625,159 @@ impl Event {
                 text,
             } => Line::from_iter([
                 sent_at.to_span(),
-                Span::raw(user_login).bold().red(),
+                Span::raw(user_login.clone()).bold().red(),
                 Span::raw(" "),
-                Span::raw(text),
+                Span::raw(text.clone()),
             ]),
             Self::Notification {
                 timestamp,
                 event,
                 extra,
+                parsed,
             } => {
-                let notification = event;
                 let mut spans = Vec::new();
                 let mut lines = Vec::new();
-                if let Some(message) = notification.parse::<ChatMessage>()? {
-                    let color = parse_color(&message.color, &message.chatter_user_id);
-                    spans.extend([
-                        timestamp.to_span(),
-                        Span::raw(message.chatter_user_name).bold().fg(color),
-                        Span::raw(" "),
-                    ]);
-                    message_to_spans(&message.message, &mut spans);
-                    spans.into()
-                } else if let Some(notification) = notification.parse::<ChatNotification>()? {
-                    let color = parse_color(&notification.color, &notification.chatter_user_id);
-                    spans.extend([
-                        timestamp.to_span(),
-                        Span::raw(notification.chatter_user_name).bold().fg(color),
-                        Span::raw(" "),
-                    ]);
-                    if !notification.system_message.is_empty() {
+                match parsed.get(event)? {
+                    Notification::ChatMessage(message) => {
+                        let color = parse_color(&message.color, &message.chatter_user_id);
+                        let mut text = Vec::new();
+                        message_to_spans(&message.message, &mut text);
+                        spans.push(timestamp.to_span());
+                        if matches!(extra, Extra::Away) {
+                            spans.push(Span::raw("away ").yellow());
+                        }
+                        if strip_action(&mut text, color) {
+                            spans.push(Span::raw("* ").fg(color));
+                        }
+                        spans.extend([
+                            Span::raw(message.chatter_user_name.clone())
+                                .bold()
+                                .fg(color),
+                            Span::raw(" "),
+                        ]);
+                        if let Some(reply) = &message.reply {
+                            spans.push(
+                                Span::raw(format!("↪ {} ", reply.parent_user_name)).dark_gray(),
+                            );
+                        }
+                        spans.extend(text);
+                        spans.into()
+                    }
+                    Notification::ChatNotification(notification) => {
+                        let color = parse_color(&notification.color, &notification.chatter_user_id);
                         spans.extend([
-                            Span::raw(notification.system_message).italic(),
+                            timestamp.to_span(),
+                            Span::raw(notification.chatter_user_name.clone())
+                                .bold()
+                                .fg(color),
                             Span::raw(" "),
                         ]);
+                        if !notification.system_message.is_empty() {
+                            spans.extend([
+                                Span::raw(notification.system_message.clone()).italic(),
+                                Span::raw(" "),
+                            ]);
+                        }
+                        // most notifications (e.g. subs) come without a message
+                        if notification.system_message.is_empty()
+                            || !notification.message.is_empty()
+                        {
+                            message_to_spans(&notification.message, &mut spans);
+                        }
+                        spans.into()
+                    }
+                    Notification::Follow(follow) => {
+                        let follower_color = "";
+                        let color = parse_color(follower_color, &follow.user_id);
+                        Line::from_iter([
+                            follow.followed_at.to_span(),
+                            Span::raw(follow.user_name.clone()).bold().fg(color),
+                            Span::raw(" has followed you").italic(),
+                        ])
+                    }
+                    Notification::Cheer(cheer) => {
+                        let color = match &cheer.user_id {
+                            Some(user_id) => parse_color("", user_id),
+                            None => Color::DarkGray,
+                        };
+                        let mut spans = vec![
+                            timestamp.to_span(),
+                            Span::raw(cheer.user_name().to_string()).bold().fg(color),
+                            Span::raw(" cheered ").italic(),
+                            Span::raw(format!("{} bits", cheer.bits)).bold().yellow(),
+                        ];
+                        if !cheer.message.is_empty() {
+                            spans.extend([Span::raw(": "), Span::raw(cheer.message.clone())]);
+                        }
+                        spans.into()
+                    }
+                    Notification::Redemption(redemption) => {
+                        let color = parse_color("", &redemption.user_id);
+                        let mut spans = vec![
+                            redemption.redeemed_at.to_span(),
+                            Span::raw(redemption.user_name.clone()).bold().fg(color),
+                            Span::raw(" redeemed ").italic(),
+                            Span::raw(redemption.reward.title.clone()).bold().magenta(),
+                            Span::raw(format!(" ({})", redemption.reward.cost)).dark_gray(),
+                        ];
+                        if !redemption.user_input.is_empty() {
+                            spans.extend([
+                                Span::raw(": "),
+                                Span::raw(redemption.user_input.clone()),
+                            ]);
+                        }
+                        spans.into()
                     }
-                    message_to_spans(&notification.message, &mut spans);
-                    spans.into()
-                } else if let Some(follow) = notification.parse::<Follow>()? {
-                    let follower_color = "";
-                    let color = parse_color(follower_color, &follow.user_id);
-                    Line::from_iter([
-                        follow.followed_at.to_span(),
-                        Span::raw(follow.user_name).bold().fg(color),
-                        Span::raw(" has followed you").italic(),
-                    ])
-                } else if let Some(online) = notification.parse::<StreamOnline>()? {
-                    let stream: Stream =
-                        serde_json::from_value(extra.clone()).context("parse stream info")?;
-
-                    lines.push(Line::from_iter([
-                        online.started_at.to_span(),
-                        Span::raw("stream went online").italic().green(),
-                    ]));
-                    stream_info(&stream, &mut lines);
-                    return Ok(lines.into());
-                } else if let Some(offline) = notification.parse::<StreamOffline>()? {
-                    let _ = offline;
-
-                    let channel: Channel =
-                        serde_json::from_value(extra.clone()).context("parse channel info")?;
-
-                    lines.push(Line::from_iter([
+                    Notification::Raid(raid) => Line::from_iter([
                         timestamp.to_span(),
-                        Span::raw("stream went offline").italic().red(),
-                    ]));
-                    channel_info(&channel, &mut lines);
-                    return Ok(lines.into());
-                } else {
-                    Line::from_iter([
+                        Span::raw(raid.from_broadcaster_user_name.clone()).bold(),
+                        Span::raw(format!(" is raiding with {} viewers", raid.viewers))
+                            .italic()
+                            .green(),
+                    ]),
+                    Notification::StreamOnline(online) => {
+                        let Extra::Stream(stream) = extra else {
+                            anyhow::bail!("missing stream info");
+                        };
+
+                        lines.push(Line::from_iter([
+                            online.started_at.to_span(),
+                            Span::raw("stream went online").italic().green(),
+                        ]));
+                        stream_info(stream, &mut lines);
+                        return Ok(lines.into());
+                    }
+                    Notification::StreamOffline(_offline) => {
+                        let Extra::Channel(channel) = extra else {
+                            anyhow::bail!("missing channel info");
+                        };
+
+                        lines.push(Line::from_iter([
+                            timestamp.to_span(),
+                            Span::raw("stream went offline").italic().red(),
+                        ]));
+                        channel_info(channel, &mut lines);
+                        return Ok(lines.into());
+                    }
+                    Notification::Unknown => Line::from_iter([
                         timestamp.to_span(),
-                        Span::raw(format!("unknown notification event: {notification:?}")).italic(),
-                    ])
+                        Span::raw(format!("unknown notification event: {event:?}")).italic(),
+                    ]),
                 }
             }
+            Self::Combo { timestamp, events } => Line::from_iter([
+                timestamp.to_span(),
+                Span::raw(format!("{} follows! ", events.len())).bold(),
+                Span::raw(combo_follows(events).join(", ")).italic(),
+            ]),
+            Self::Live { timestamp, stream } => {
+                let mut lines = vec![Line::from_iter([
+                    timestamp.to_span(),
+                    Span::raw("currently live").italic().green(),
+                ])];
+                stream_info(stream, &mut lines);
+                return Ok(lines.into());
+            }
         }
         .into())
     }
@@ -743,6 +1817,156 @@ impl CharToByteIndex for String {
     }
 }
 
+/// Uptime in hours and minutes, e.g. `2h 05m`
+async fn lookup_user(client: &mut AuthenticatedClient, login: &str) -> Result<User> {
+    let login = login.trim_start_matches('@');
+    client
+        .send(&UsersRequest::login(login.into()))
+        .await
+        .context("get user")?
+        .into_user()
+        .with_context(|| format!("unknown user: {login}"))
+}
+
+/// Argument of a chat mode command: nothing enables it, `off` disables it, a number enables it
+/// with that duration
+fn parse_chat_mode(text: &str) -> Option<(bool, Option<u32>)> {
+    match text.trim() {
+        "" => Some((true, None)),
+        "off" => Some((false, None)),
+        duration => Some((true, Some(duration.parse().ok()?))),
+    }
+}
+
+/// Active chat modes, e.g. `chat modes: slow 30s, followers only 10m`
+fn format_chat_modes(settings: &ChatSettings) -> String {
+    let mut modes = Vec::new();
+    if settings.slow_mode {
+        modes.push(format!(
+            "slow {}s",
+            settings.slow_mode_wait_time.unwrap_or_default()
+        ));
+    }
+    if settings.follower_mode {
+        modes.push(match settings.follower_mode_duration {
+            Some(duration) if duration > 0 => format!("followers only {duration}m"),
+            _ => "followers only".into(),
+        });
+    }
+    if settings.subscriber_mode {
+        modes.push("subscribers only".into());
+    }
+    if settings.emote_mode {
+        modes.push("emote only".into());
+    }
+    if settings.unique_chat_mode {
+        modes.push("unique chat".into());
+    }
+    if modes.is_empty() {
+        "no chat modes".into()
+    } else {
+        format!("chat modes: {}", modes.join(", "))
+    }
+}
+
+/// Connection state and the stream and channel fields of the status bar
+fn status_spans(status: &Status, now: DateTime<Utc>) -> Vec<Span<'static>> {
+    let mut spans = match (status.connection, status.silent_for(now)) {
+        (Connection::Connecting, _) => vec![Span::raw("● ").yellow(), Span::raw("connecting")],
+        (Connection::Reconnecting, _) => {
+            vec![Span::raw("● ").red(), Span::raw("reconnecting")]
+        }
+        (Connection::Connected { .. }, Some(silent_for)) => vec![
+            Span::raw("● ").yellow(),
+            Span::raw(format!("silent for {}s", silent_for.num_seconds())),
+        ],
+        (Connection::Connected { .. }, None) => {
+            vec![Span::raw("● ").green(), Span::raw("connected")]
+        }
+    };
+
+    spans.extend(status_field(
+        "viewers",
+        match status.viewer_count {
+            Some(viewer_count) => viewer_count.to_string(),
+            None => "offline".into(),
+        },
+    ));
+    if let Some(started_at) = status.started_at {
+        spans.extend(status_field("uptime", format_uptime(now - started_at)));
+    }
+    spans.extend(status_field(
+        "followers",
+        match status.follower_total {
+            Some(follower_total) => follower_total.to_string(),
+            None => "-".into(),
+        },
+    ));
+    if let Some(poll) = &status.active_poll {
+        spans.extend(status_field("poll", poll.clone()));
+    }
+    if let Some(prediction) = &status.active_prediction {
+        spans.extend(status_field("prediction", prediction.clone()));
+    }
+    spans.extend(status_field(
+        "last event",
+        match status.last_notification {
+            Some(timestamp) => timestamp
+                .with_timezone(crate::timezone())
+                .format("%T")
+                .to_string(),
+            None => "-".into(),
+        },
+    ));
+    spans
+}
+
+fn status_field(name: &str, value: String) -> [Span<'static>; 2] {
+    [
+        Span::raw(format!("  {name}: ")).dark_gray(),
+        Span::raw(value),
+    ]
+}
+
+fn format_uptime(uptime: TimeDelta) -> String {
+    let minutes = uptime.num_minutes().max(0);
+    format!("{}h {:02}m", minutes / 60, minutes % 60)
+}
+
+/// Row and column (in chars) of the char offset in a multi-line text
+fn cursor_position(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..text.char_to_byte_index(offset)];
+    let row = before.matches('\n').count();
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (row, before[line_start..].chars().count())
+}
+
+/// Char offset of the row and column, clamped to the end of the row and the last row
+fn cursor_offset(text: &str, row: usize, column: usize) -> usize {
+    let mut offset = 0;
+    let mut lines = text.split('\n').peekable();
+    for _ in 0..row {
+        let Some(line) = lines.next() else { break };
+        if lines.peek().is_none() {
+            return offset + line.chars().count().min(column);
+        }
+        offset += line.chars().count() + 1;
+    }
+    offset
+        + lines
+            .next()
+            .map_or(0, |line| line.chars().count().min(column))
+}
+
+/// Chat messages are a single line, the non-empty lines are joined with a space
+fn join_lines(text: &str) -> String {
+    text.split('\n')
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
 fn stream_info(stream: &Stream, lines: &mut Vec<Line>) {
     stream_or_channel_info(
         &stream.title,
@@ -811,45 +2035,154 @@ fn try_parse_color(color: &str) -> Option<Color> {
     Some(Color::Rgb(r, g, b))
 }
 
+/// Parent of a reply, see [`find_parent`]
+#[derive(Debug, PartialEq)]
+enum Parent {
+    /// Offset that shows the parent as the newest event
+    Loaded {
+        offset: NonZeroUsize,
+        message_id: String,
+    },
+
+    /// The parent as quoted by the reply, if it is not reachable by scrolling
+    NotLoaded(String),
+}
+
+/// Parent of the newest reply visible at `offset`, `None` if no reply is visible
+fn find_parent(store: &mut Store, offset: &mut Option<NonZeroUsize>) -> Option<Parent> {
+    let reply = store
+        .events(offset)
+        .find_map(|event| event.chat_message()?.reply.as_ref())?;
+    let (message_id, user_name, body) = (
+        reply.parent_message_id.clone(),
+        reply.parent_user_name.clone(),
+        reply.parent_message_body.clone(),
+    );
+
+    Some(match store.find_message(&message_id) {
+        Some(index) => Parent::Loaded {
+            offset: NonZeroUsize::new(index + 1).unwrap(),
+            message_id,
+        },
+        None => Parent::NotLoaded(format!(
+            "parent message not loaded, {user_name} wrote: {body:?}"
+        )),
+    })
+}
+
+/// Colors for users without a chat color, configured with `ui.palette`
+static PALETTE: OnceLock<Vec<Color>> = OnceLock::new();
+
+const DEFAULT_PALETTE: [Color; 14] = [
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::Gray,
+    Color::DarkGray,
+    Color::LightRed,
+    Color::LightGreen,
+    Color::LightYellow,
+    Color::LightBlue,
+    Color::LightMagenta,
+    Color::LightCyan,
+];
+
 fn random_color(user_id: &str) -> Color {
+    palette_color(
+        PALETTE.get().map_or(&DEFAULT_PALETTE[..], Vec::as_slice),
+        user_id,
+    )
+}
+
+/// Pick the same color of the palette for each user id
+fn palette_color(palette: &[Color], user_id: &str) -> Color {
     let mut hasher = DefaultHasher::new();
     user_id.hash(&mut hasher);
     let hash = hasher.finish();
-    const COLORS: [Color; 14] = [
-        Color::Red,
-        Color::Green,
-        Color::Yellow,
-        Color::Blue,
-        Color::Magenta,
-        Color::Cyan,
-        Color::Gray,
-        Color::DarkGray,
-        Color::LightRed,
-        Color::LightGreen,
-        Color::LightYellow,
-        Color::LightBlue,
-        Color::LightMagenta,
-        Color::LightCyan,
-    ];
-    COLORS[(hash % COLORS.len() as u64) as usize]
+    palette[(hash % palette.len() as u64) as usize]
+}
+
+/// Cheermotes of the global and the broadcaster's channel, loaded once at startup
+static CHEERMOTES: OnceLock<CheermotesResponse> = OnceLock::new();
+
+/// Color of the cheered tier, dark gray if the cheermotes are not loaded
+fn cheermote_color(cheermote: &ChatMessageCheermote) -> Color {
+    CHEERMOTES
+        .get()
+        .and_then(|cheermotes| cheermotes.find(&cheermote.prefix))
+        .and_then(|found| found.tier(cheermote.bits))
+        .and_then(|tier| tier.color.parse().ok())
+        .unwrap_or(Color::DarkGray)
+}
+
+/// Remove the `/me` prefix of an action message and style it in italic with the user color
+///
+/// Returns whether the message is an action message.
+fn strip_action(spans: &mut [Span<'static>], color: Color) -> bool {
+    const PREFIXES: [&str; 2] = ["/me ", "\u{1}ACTION "];
+
+    let Some(first) = spans.first_mut() else {
+        return false;
+    };
+    let Some(text) = PREFIXES
+        .iter()
+        .find_map(|prefix| first.content.strip_prefix(prefix))
+    else {
+        return false;
+    };
+    first.content = text.to_string().into();
+    if let Some(last) = spans.last_mut()
+        && let Some(text) = last.content.strip_suffix('\u{1}')
+    {
+        last.content = text.to_string().into();
+    }
+
+    for span in spans {
+        *span = span.clone().italic().fg(color);
+    }
+    true
 }
 
 fn message_to_spans(message: &ChatMessageMessage, spans: &mut Vec<Span>) {
-    if message.fragments.is_empty() {
+    if message.is_empty() {
         spans.push(Span::raw("empty chat message").italic().dark_gray());
+        return;
+    }
+    if message.fragments.is_empty() {
+        spans.push(Span::raw(message.text.clone()));
+        return;
     }
 
     for fragment in &message.fragments {
-        spans.push(match fragment {
-            ChatMessageFragment::Text { text } => Span::raw(text.clone()),
-            ChatMessageFragment::Cheermote { text, cheermote: _ } => {
-                Span::raw(text.clone()).dark_gray()
+        match fragment {
+            ChatMessageFragment::Text { text } => spans.push(Span::raw(text.clone())),
+            ChatMessageFragment::Cheermote { text, cheermote } => spans.extend([
+                Span::raw(text.clone()).fg(cheermote_color(cheermote)),
+                Span::raw(format!(" (tier {})", cheermote.tier)).dark_gray(),
+            ]),
+            ChatMessageFragment::Emote { text, emote } => {
+                let span = Span::raw(text.clone()).magenta();
+                // animated emotes can not be shown, they are at least marked as such
+                let animated = emote
+                    .format
+                    .iter()
+                    .any(|format| matches!(format, ChatMessageEmoteFormat::Animated));
+                spans.push(if animated { span.italic() } else { span });
             }
-            ChatMessageFragment::Emote { text, emote: _ } => Span::raw(text.clone()).dark_gray(),
-            ChatMessageFragment::Mention { text, mention: _ } => {
-                Span::raw(text.clone()).dark_gray()
+            ChatMessageFragment::Mention { text, mention } => {
+                // the mention text may differ in case from the user name, show the actual name
+                let text = match text.strip_prefix('@') {
+                    Some(name) if name.eq_ignore_ascii_case(&mention.user_name) => {
+                        format!("@{}", mention.user_name)
+                    }
+                    _ => text.clone(),
+                };
+                spans.push(Span::raw(text).cyan().underlined());
             }
-        });
+        }
     }
 }
 
@@ -901,41 +2234,832 @@ fn message_to_spans(message: &ChatMessageMessage, spans: &mut Vec<Span>) {
 //     }
 // }
 
-struct Poll {
-    options: Vec<String>,
-    votes: HashMap<String, usize>,
-}
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        io::{BufRead, BufReader, Read, Write as _},
+        net::TcpListener,
+        sync::{Arc, Mutex},
+        thread,
+    };
 
-impl Poll {
-    fn vote(&mut self, user_id: &str, text: &str) {
-        let Ok(n) = text.split(' ').next().unwrap().parse() else {
-            return;
+    use chrono::TimeDelta;
+    use chrono_tz::Tz;
+    use ratatui::{Terminal, backend::TestBackend};
+    use twitch_api::{auth::TokenManager, config::TokenConfig, secret::Secret};
+    use url::Url;
+
+    use super::*;
+    use crate::{
+        config::StoreConfig,
+        session::{Sender, test_client},
+    };
+
+    #[test]
+    fn redraws_use_the_render_cache() {
+        let _ = crate::TIMEZONE.set(Tz::UTC);
+        let message = |sent_at, text: &str| Event::Message {
+            sent_at,
+            user_login: "user".into(),
+            text: text.into(),
         };
-        self.votes.insert(user_id.into(), n);
+        let now = Utc::now();
+        let events = [
+            message(now, "hello"),
+            message(now + TimeDelta::seconds(1), "a longer message that wraps"),
+        ];
+
+        let mut cache = RenderCache::default();
+        for _ in 0..3 {
+            for event in &events {
+                cache.get(event, 30);
+            }
+        }
+        assert_eq!(cache.renders, 2);
+        assert_eq!(cache.get(&events[1], 30).1, 2);
+
+        // a different width wraps the events again
+        assert_eq!(cache.get(&events[1], 60).1, 1);
+        assert_eq!(cache.renders, 3);
+    }
+
+    /// Chat message with the given id, a reply to `parent` if it is set
+    fn chat_message(message_id: &str, parent: Option<&str>) -> Event {
+        let reply = parent.map(|parent| {
+            serde_json::json!({
+                "parent_message_id": parent,
+                "parent_message_body": "first!",
+                "parent_user_id": "1337",
+                "parent_user_name": "Streamer",
+                "parent_user_login": "streamer",
+                "thread_message_id": parent,
+                "thread_user_id": "1337",
+                "thread_user_name": "Streamer",
+                "thread_user_login": "streamer",
+            })
+        });
+        Event::test_chat_message(serde_json::json!({
+            "message_id": message_id,
+            "reply": reply,
+        }))
     }
 
-    fn result(self) -> String {
-        let mut votes = vec![0; self.options.len()];
-        for vote in self.votes.into_values() {
-            votes[vote] += 1;
+    #[test]
+    fn go_to_loaded_parent() {
+        let mut store = Store::test(StoreConfig::default());
+        for event in [
+            chat_message("parent", None),
+            chat_message("other", None),
+            chat_message("reply", Some("parent")),
+            chat_message("newest", None),
+        ] {
+            store.push(event).unwrap();
         }
-        let max = votes.iter().copied().max().unwrap_or(0);
-        if max == 0 {
-            "Ergebnis: Keine Stimmen".into()
-        } else {
-            let mut message = format!("Ergebnis[{max}]:");
-            let mut first = true;
-            for (option, votes) in iter::zip(self.options, votes) {
-                if votes == max {
-                    if first {
-                        first = false;
-                    } else {
-                        message.push_str(" -");
+
+        let parent = find_parent(&mut store, &mut None);
+        assert_eq!(
+            parent,
+            Some(Parent::Loaded {
+                offset: NonZeroUsize::new(1).unwrap(),
+                message_id: "parent".into(),
+            })
+        );
+        let mut offset = NonZeroUsize::new(1);
+        let newest = store.events(&mut offset).next().unwrap();
+        assert_eq!(newest.chat_message().unwrap().message_id, "parent");
+
+        // replies scrolled out of view are not considered
+        assert_eq!(find_parent(&mut store, &mut NonZeroUsize::new(2)), None);
+    }
+
+    #[test]
+    fn missing_parent_shows_the_quote() {
+        let mut store = Store::test(StoreConfig::default());
+        store.push(chat_message("reply", Some("gone"))).unwrap();
+
+        assert_eq!(
+            find_parent(&mut store, &mut None),
+            Some(Parent::NotLoaded(
+                r#"parent message not loaded, Streamer wrote: "first!""#.into()
+            ))
+        );
+    }
+
+    #[test]
+    fn palette_color_is_deterministic() {
+        let palette = [Color::Rgb(255, 128, 0), Color::LightRed, Color::Indexed(42)];
+        let users: Vec<_> = (0..20).map(|id| id.to_string()).collect();
+        let colors: Vec<_> = users
+            .iter()
+            .map(|user_id| palette_color(&palette, user_id))
+            .collect();
+        assert!(colors.iter().all(|color| palette.contains(color)));
+        assert!(palette.iter().all(|color| colors.contains(color)));
+        for (user_id, color) in users.iter().zip(&colors) {
+            assert_eq!(palette_color(&palette, user_id), *color);
+        }
+    }
+
+    #[test]
+    fn emote_only_message_is_rendered() {
+        let _ = crate::TIMEZONE.set(Tz::UTC);
+        let text = Event::test_emote_only_message().to_text().unwrap();
+        let [line] = &text.lines[..] else {
+            panic!("expected one line");
+        };
+        let content: String = line.spans.iter().map(|span| &*span.content).collect();
+        assert!(content.ends_with("Viewer Kappa PogChamp"), "{content:?}");
+
+        let emotes: Vec<_> = line
+            .spans
+            .iter()
+            .filter(|span| span.style.fg == Some(Color::Magenta))
+            .map(|span| &*span.content)
+            .collect();
+        assert_eq!(emotes, ["Kappa", "PogChamp"]);
+    }
+
+    /// Lines of the drawn events, top to bottom, trailing spaces removed
+    fn draw_lines(events: &[Event], dividers: &Dividers) -> Vec<String> {
+        let _ = crate::TIMEZONE.set(Tz::UTC);
+        let mut terminal = Terminal::new(TestBackend::new(30, 6)).unwrap();
+        terminal
+            .draw(|frame| {
+                draw_events(
+                    frame,
+                    frame.area(),
+                    events.iter().rev(),
+                    dividers,
+                    &mut RenderCache::default(),
+                    None,
+                );
+            })
+            .unwrap();
+        buffer_lines(&terminal)
+    }
+
+    /// Non-empty lines of the terminal, top to bottom, trailing spaces removed
+    fn buffer_lines(terminal: &Terminal<TestBackend>) -> Vec<String> {
+        let buffer = terminal.backend().buffer();
+        buffer
+            .content()
+            .chunks(buffer.area.width.into())
+            .map(|line| {
+                let line: String = line.iter().map(|cell| cell.symbol()).collect();
+                line.trim_end().to_string()
+            })
+            .filter(|line| !line.is_empty())
+            .collect()
+    }
+
+    #[test]
+    fn clear_view_hides_prior_events() {
+        let started_at = Utc::now();
+        let events: Vec<_> = ["before", "cleared", "after"]
+            .into_iter()
+            .enumerate()
+            .map(|(i, text)| Event::Message {
+                sent_at: started_at + TimeDelta::seconds(i as i64),
+                user_login: "user".into(),
+                text: text.into(),
+            })
+            .collect();
+        let mut dividers = Dividers {
+            end: events.len(),
+            unread_at: None,
+            cleared_at: Some(2),
+            show_cleared: false,
+        };
+
+        let lines = draw_lines(&events, &dividers);
+        assert_eq!(lines.len(), 2, "{lines:?}");
+        assert!(lines[0].contains("view cleared"), "{lines:?}");
+        assert!(lines[1].ends_with("user after"), "{lines:?}");
+
+        // scrolling up reveals the events before the marker, if enabled
+        dividers.show_cleared = true;
+        let lines = draw_lines(&events, &dividers);
+        assert_eq!(lines.len(), 4, "{lines:?}");
+        assert!(lines[0].ends_with("user before"), "{lines:?}");
+        assert!(lines[1].ends_with("user cleared"), "{lines:?}");
+        assert!(lines[2].contains("view cleared"), "{lines:?}");
+    }
+
+    #[test]
+    fn unread_divider_after_focus_regain() {
+        let _ = crate::TIMEZONE.set(Tz::UTC);
+        let mut client = test_client();
+        let mut session = Session::test(&mut client);
+        let ui = UiConfig {
+            scroll_to_unread: true,
+            ..Default::default()
+        };
+        let mut state = State::new(Keybindings::default(), &ui, &mut session);
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let update = |state: &mut State, event| {
+            let flow = runtime.block_on(state.update(event)).unwrap();
+            assert!(flow.is_continue());
+        };
+        let push = |state: &mut State, text: &str| {
+            state
+                .session
+                .store
+                .push(Event::Message {
+                    sent_at: Utc::now(),
+                    user_login: "user".into(),
+                    text: text.into(),
+                })
+                .unwrap();
+        };
+
+        push(&mut state, "read");
+        update(&mut state, InputEvent::FocusLost);
+        push(&mut state, "missed");
+        push(&mut state, "newest");
+        update(&mut state, InputEvent::FocusGained);
+        assert_eq!(state.unread_at, Some(1));
+        // scrolled to the first missed event
+        assert_eq!(state.offset, NonZeroUsize::new(2));
+
+        let mut terminal = Terminal::new(TestBackend::new(40, 8)).unwrap();
+        terminal.draw(|frame| state.draw(frame)).unwrap();
+        let lines = buffer_lines(&terminal);
+        let divider = lines
+            .iter()
+            .position(|line| line.contains("unread since you left"))
+            .unwrap_or_else(|| panic!("no unread divider: {lines:?}"));
+        assert!(lines[divider - 1].ends_with("user read"), "{lines:?}");
+        assert!(lines[divider + 1].ends_with("user missed"), "{lines:?}");
+
+        // regaining the focus without new events removes the divider
+        update(&mut state, InputEvent::FocusLost);
+        update(&mut state, InputEvent::FocusGained);
+        assert_eq!(state.unread_at, None);
+    }
+
+    #[test]
+    fn multiline_cursor_position() {
+        let text = "first\nsecond line\n";
+        assert_eq!(cursor_position(text, 0), (0, 0));
+        assert_eq!(cursor_position(text, 5), (0, 5));
+        assert_eq!(cursor_position(text, 6), (1, 0));
+        assert_eq!(cursor_position(text, 17), (1, 11));
+        assert_eq!(cursor_position(text, 18), (2, 0));
+
+        assert_eq!(cursor_offset(text, 1, 3), 9);
+        // clamped to the end of the row and to the last row
+        assert_eq!(cursor_offset(text, 0, 11), 5);
+        assert_eq!(cursor_offset(text, 5, 3), 18);
+        assert_eq!(cursor_offset("äö\nü", 1, 1), 4);
+    }
+
+    #[test]
+    fn multiline_cursor_moves_between_lines() {
+        let mut client = test_client();
+        let mut session = Session::test(&mut client);
+        let mut state = State::new(Keybindings::default(), &UiConfig::default(), &mut session);
+        assert!(state.run(Command::ToggleMultiline).unwrap().is_continue());
+        state.message = "first\nsecond line".into();
+        state.focus = FocusState::Message(state.message.chars().count());
+
+        assert!(state.run(Command::GoUp).unwrap().is_continue());
+        assert!(matches!(state.focus, FocusState::Message(5)));
+        assert!(state.run(Command::GoDown).unwrap().is_continue());
+        assert!(matches!(state.focus, FocusState::Message(11)));
+        // the view is not scrolled while moving the cursor
+        assert_eq!(state.offset, None);
+    }
+
+    #[test]
+    fn multiline_message_is_sent_as_one_line() {
+        assert_eq!(join_lines("hello\nchat"), "hello chat");
+        assert_eq!(join_lines("  hello \n\n\n chat\n"), "hello chat");
+        assert_eq!(join_lines("\n"), "");
+    }
+
+    #[test]
+    fn quick_ban_key_bans_the_selected_chatter() {
+        let mut keybindings = Keybindings::default();
+        keybindings.extend(toml::from_str(r#"normal = { "b s" = { Ban = "spam" } }"#).unwrap());
+        let mut pending_keys = PendingKeys::default();
+        let mut lookup = |key: &str| pending_keys.lookup(&keybindings.normal, key.parse().unwrap());
+        assert!(matches!(lookup("b"), KeyLookup::Pending));
+        let KeyLookup::Command(Command::Ban(reason)) = lookup("s") else {
+            panic!("quick-ban key is not bound");
+        };
+
+        let message: ChatMessage = serde_json::from_value(serde_json::json!({
+            "broadcaster_user_id": "1337",
+            "broadcaster_user_name": "Cool_User",
+            "broadcaster_user_login": "cool_user",
+            "chatter_user_id": "4242",
+            "chatter_user_name": "Spammer",
+            "chatter_user_login": "spammer",
+            "message_id": "cc106a89-1814-919d-454c-f4f2f970aae7",
+            "message": { "text": "buy followers", "fragments": [] },
+            "message_type": "text",
+            "badges": [],
+            "color": "",
+        }))
+        .unwrap();
+        let req = quick_ban("1337".into(), "1234".into(), &message, reason);
+        assert_eq!(req.broadcaster_id, "1337");
+        assert_eq!(req.moderator_id, "1234");
+        assert_eq!(req.user_id, "4242");
+        assert_eq!(req.duration, None);
+        assert_eq!(req.reason.as_deref(), Some("spam"));
+    }
+
+    fn status_text(status: &Status, now: DateTime<Utc>) -> String {
+        let _ = crate::TIMEZONE.set(Tz::UTC);
+        status_spans(status, now)
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect()
+    }
+
+    #[test]
+    fn status_bar_fields() {
+        let now: DateTime<Utc> = "2025-01-31T20:00:00Z".parse().unwrap();
+        let status = Status {
+            connection: Connection::Connected {
+                keepalive: Duration::from_secs(10),
+            },
+            last_message: Some(now - TimeDelta::seconds(3)),
+            last_notification: Some("2025-01-31T19:59:30Z".parse().unwrap()),
+            viewer_count: Some(42),
+            follower_total: Some(1337),
+            started_at: Some(now - TimeDelta::minutes(75)),
+            active_poll: Some("best emote?".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            status_text(&status, now),
+            "● connected  viewers: 42  uptime: 1h 15m  followers: 1337  poll: best emote?  \
+             last event: 19:59:30",
+        );
+    }
+
+    #[test]
+    fn uptime_formatting() {
+        let started_at: DateTime<Utc> = "2025-01-31T18:00:00Z".parse().unwrap();
+        let uptime = |now: &str| format_uptime(now.parse::<DateTime<Utc>>().unwrap() - started_at);
+        assert_eq!(uptime("2025-01-31T18:00:59Z"), "0h 00m");
+        assert_eq!(uptime("2025-01-31T18:07:00Z"), "0h 07m");
+        assert_eq!(uptime("2025-01-31T20:15:30Z"), "2h 15m");
+        assert_eq!(uptime("2025-02-02T06:00:00Z"), "36h 00m");
+        // clock skew between twitch and the local machine
+        assert_eq!(uptime("2025-01-31T17:59:00Z"), "0h 00m");
+    }
+
+    #[test]
+    fn status_bar_connection() {
+        let now: DateTime<Utc> = "2025-01-31T20:00:00Z".parse().unwrap();
+        let mut status = Status::default();
+        assert_eq!(
+            status_text(&status, now),
+            "● connecting  viewers: offline  followers: -  last event: -",
+        );
+
+        // no keepalive within the interval
+        status.connection = Connection::Connected {
+            keepalive: Duration::from_secs(10),
+        };
+        status.last_message = Some(now - TimeDelta::seconds(12));
+        assert!(status_text(&status, now).starts_with("● silent for 12s  "));
+
+        status.connection = Connection::Reconnecting;
+        assert!(status_text(&status, now).starts_with("● reconnecting  "));
+    }
+
+    #[test]
+    fn setting_an_error_plays_the_error_sound() {
+        let mut client = test_client();
+        let mut session = Session::test(&mut client);
+        let mut state = State::new(Keybindings::default(), &UiConfig::default(), &mut session);
+        state.set_error("failed to send message");
+        assert_eq!(state.error, "failed to send message");
+        assert_eq!(state.session.sound_system.played, [SoundEvent::Error]);
+    }
+
+    #[test]
+    fn error_clears_after_timeout() {
+        let mut client = test_client();
+        let mut session = Session::test(&mut client);
+        let ui = UiConfig {
+            error_timeout_secs: 10,
+            ..Default::default()
+        };
+        let mut state = State::new(Keybindings::default(), &ui, &mut session);
+        state.set_error("failed to send message");
+        state.clear_expired_error();
+        assert_eq!(state.error, "failed to send message");
+
+        state.error_set_at = Some(Instant::now() - Duration::from_secs(10));
+        state.clear_expired_error();
+        assert_eq!(state.error, "");
+
+        // errors are kept until the next input without a timeout
+        let mut state = State::new(
+            Keybindings::default(),
+            &UiConfig {
+                error_timeout_secs: 0,
+                ..Default::default()
+            },
+            &mut session,
+        );
+        state.set_error("failed to send message");
+        state.error_set_at = Some(Instant::now() - Duration::from_secs(3600));
+        state.clear_expired_error();
+        assert_eq!(state.error, "failed to send message");
+    }
+
+    #[test]
+    fn action_messages_are_italic_in_the_user_color() {
+        for text in ["/me waves", "\u{1}ACTION waves\u{1}"] {
+            let mut spans = vec![Span::raw(text)];
+            assert!(strip_action(&mut spans, Color::Cyan));
+            assert_eq!(spans[0].content, "waves");
+            assert_eq!(spans[0].style.fg, Some(Color::Cyan));
+            assert!(
+                spans[0]
+                    .style
+                    .add_modifier
+                    .contains(ratatui::style::Modifier::ITALIC)
+            );
+        }
+
+        let mut spans = vec![Span::raw("waves /me")];
+        assert!(!strip_action(&mut spans, Color::Cyan));
+        assert_eq!(spans[0], Span::raw("waves /me"));
+    }
+
+    /// Answer the requests with the responses in order, returns the base url and the request lines
+    fn serve_responses(responses: Vec<(u16, serde_json::Value)>) -> (Url, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let log = requests.clone();
+        thread::spawn(move || {
+            for (stream, (status, body)) in listener.incoming().zip(responses) {
+                let mut stream = BufReader::new(stream.unwrap());
+                let mut request = String::new();
+                stream.read_line(&mut request).unwrap();
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    stream.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    let line = line.trim().to_ascii_lowercase();
+                    if let Some(value) = line.strip_prefix("content-length:") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+                stream.read_exact(&mut vec![0; content_length]).unwrap();
+                log.lock().unwrap().push(request.trim().to_string());
+
+                let body = if status == 204 {
+                    String::new()
+                } else {
+                    body.to_string()
+                };
+                write!(
+                    stream.get_mut(),
+                    "HTTP/1.1 {status} Status\r\n\
+                    content-type: application/json\r\n\
+                    content-length: {}\r\n\
+                    connection: close\r\n\r\n{body}",
+                    body.len(),
+                )
+                .unwrap();
+            }
+        });
+        (url, requests)
+    }
+
+    #[test]
+    fn announce_in_a_moderated_channel() {
+        let channel = serde_json::json!({
+            "id": "4242",
+            "login": "other_channel",
+            "display_name": "Other_Channel",
+            "type": "",
+            "broadcaster_type": "",
+            "description": "",
+            "profile_image_url": "",
+            "offline_image_url": "",
+            "view_count": 0,
+            "created_at": "2016-12-14T20:32:28Z",
+        });
+        let (url, requests) = serve_responses(vec![
+            (200, serde_json::json!({ "data": [channel] })),
+            (204, serde_json::Value::Null),
+        ]);
+        let mut client = Client::builder()
+            .no_proxy()
+            .base_url(url.clone(), url)
+            .build()
+            .unwrap()
+            .authenticated(TokenManager::with_config(
+                Secret::new("client id"),
+                TokenConfig {
+                    access_token: Secret::new("access token"),
+                    refresh_token: Secret::new("refresh token"),
+                    expires_at: None,
+                },
+            ));
+        let mut session = Session::test(&mut client);
+        let mut state = State::new(Keybindings::default(), &UiConfig::default(), &mut session);
+
+        state.message = "/announce #other_channel hello chat".into();
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(state.send_message())
+            .unwrap();
+        assert_eq!(state.error, "");
+        assert_eq!(state.message, "");
+
+        // the channel is the broadcaster, the user announces as moderator
+        assert_eq!(
+            *requests.lock().unwrap(),
+            [
+                "GET /users?login=other_channel HTTP/1.1",
+                "POST /chat/announcements?broadcaster_id=4242&moderator_id=1337 HTTP/1.1",
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_and_sends_use_their_own_client() {
+        let user = |id: &str, login: &str| {
+            serde_json::json!({
+                "id": id,
+                "login": login,
+                "display_name": login,
+                "type": "",
+                "broadcaster_type": "",
+                "description": "",
+                "profile_image_url": "",
+                "offline_image_url": "",
+                "view_count": 0,
+                "created_at": "2016-12-14T20:32:28Z",
+            })
+        };
+        let served_client = |url: Url| {
+            Client::builder()
+                .no_proxy()
+                .base_url(url.clone(), url)
+                .build()
+                .unwrap()
+                .authenticated(TokenManager::with_config(
+                    Secret::new("client id"),
+                    TokenConfig {
+                        access_token: Secret::new("access token"),
+                        refresh_token: Secret::new("refresh token"),
+                        expires_at: None,
+                    },
+                ))
+        };
+        let (read_url, reads) = serve_responses(vec![(
+            200,
+            serde_json::json!({ "data": [user("4242", "viewer")] }),
+        )]);
+        let (send_url, sends) = serve_responses(vec![
+            (
+                200,
+                serde_json::json!({ "data": [{ "message_id": "abc-123", "is_sent": true }] }),
+            ),
+            (204, serde_json::Value::Null),
+        ]);
+        let mut client = served_client(read_url);
+        let mut session = Session::test(&mut client);
+        session.sender = Some(Sender {
+            client: served_client(send_url),
+            user: serde_json::from_value(user("9001", "chat_bot")).unwrap(),
+        });
+        let mut state = State::new(Keybindings::default(), &UiConfig::default(), &mut session);
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+
+        state.message = "hello chat".into();
+        runtime.block_on(state.send_message()).unwrap();
+        state.message = "/unban viewer".into();
+        runtime.block_on(state.send_message()).unwrap();
+        assert_eq!(state.error, "");
+        assert_eq!(state.info, "unbanned viewer");
+
+        // the user is looked up by the reader, the bot moderates the reader's channel
+        assert_eq!(*reads.lock().unwrap(), ["GET /users?login=viewer HTTP/1.1"]);
+        assert_eq!(
+            *sends.lock().unwrap(),
+            [
+                "POST /chat/messages HTTP/1.1",
+                "DELETE /moderation/bans?broadcaster_id=1337&moderator_id=9001&user_id=4242 HTTP/1.1",
+            ]
+        );
+    }
+
+    #[test]
+    fn seed_stream_state_at_startup() {
+        let stream = serde_json::json!({
+            "id": "40952121085",
+            "user_id": "1337",
+            "user_login": "cool_user",
+            "user_name": "Cool_User",
+            "game_id": "509658",
+            "game_name": "Just Chatting",
+            "type": "live",
+            "title": "building a chat client",
+            "tags": [],
+            "viewer_count": 42,
+            "started_at": "2025-01-31T18:45:00Z",
+            "language": "en",
+            "thumbnail_url": "",
+            "tag_ids": [],
+            "is_mature": false,
+        });
+        let (url, requests) = serve_responses(vec![
+            (
+                200,
+                serde_json::json!({ "data": [stream], "pagination": {} }),
+            ),
+            (200, serde_json::json!({ "data": [], "pagination": {} })),
+        ]);
+        let mut client = Client::builder()
+            .no_proxy()
+            .base_url(url.clone(), url)
+            .build()
+            .unwrap()
+            .authenticated(TokenManager::with_config(
+                Secret::new("client id"),
+                TokenConfig {
+                    access_token: Secret::new("access token"),
+                    refresh_token: Secret::new("refresh token"),
+                    expires_at: None,
+                },
+            ));
+        let mut session = Session::test(&mut client);
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+
+        // already live at startup
+        runtime.block_on(session.update_stream()).unwrap();
+        assert_eq!(session.status.viewer_count, Some(42));
+        assert_eq!(
+            session.status.started_at,
+            Some("2025-01-31T18:45:00Z".parse().unwrap()),
+        );
+        let events: Vec<_> = session.store.events(&mut None).collect();
+        assert!(
+            matches!(
+                events.as_slice(),
+                [Event::Live { stream, .. }] if stream.title == "building a chat client"
+            ),
+            "{events:?}",
+        );
+
+        // offline at startup
+        runtime.block_on(session.update_stream()).unwrap();
+        assert_eq!(session.status.viewer_count, None);
+        assert_eq!(session.status.started_at, None);
+        assert_eq!(session.store.events(&mut None).count(), 1);
+
+        assert_eq!(
+            *requests.lock().unwrap(),
+            ["GET /streams?user_id=1337 HTTP/1.1"; 2],
+        );
+    }
+
+    /// Create every subscription, returns the base url and the request lines with their token
+    fn serve_subscriptions() -> (Url, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let log = requests.clone();
+        thread::spawn(move || {
+            for (n, stream) in listener.incoming().enumerate() {
+                let mut stream = BufReader::new(stream.unwrap());
+                let mut request = String::new();
+                stream.read_line(&mut request).unwrap();
+                let mut request = request.trim().to_string();
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    stream.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    let line = line.trim().to_ascii_lowercase();
+                    if let Some(value) = line.strip_prefix("content-length:") {
+                        content_length = value.trim().parse().unwrap();
+                    } else if let Some(value) = line.strip_prefix("authorization:") {
+                        request = format!("{request} {}", value.trim());
                     }
-                    write!(message, " {option}").unwrap();
                 }
+                stream.read_exact(&mut vec![0; content_length]).unwrap();
+                log.lock().unwrap().push(request);
+
+                let body = serde_json::json!({
+                    "data": [{
+                        "id": format!("subscription {n}"),
+                        "status": "enabled",
+                        "type": "channel.follow",
+                        "version": "2",
+                        "cost": 0,
+                        "condition": {},
+                        "transport": {
+                            "method": "websocket",
+                            "session_id": "test",
+                            "connected_at": "2025-01-31T19:00:00Z",
+                        },
+                        "created_at": "2025-01-31T19:00:00Z",
+                    }],
+                    "total": n + 1,
+                    "total_cost": 0,
+                    "max_total_cost": 10000,
+                })
+                .to_string();
+                write!(
+                    stream.get_mut(),
+                    "HTTP/1.1 202 Accepted\r\n\
+                    content-type: application/json\r\n\
+                    content-length: {}\r\n\
+                    connection: close\r\n\r\n{body}",
+                    body.len(),
+                )
+                .unwrap();
             }
-            message
+        });
+        (url, requests)
+    }
+
+    #[test]
+    fn reauth_updates_the_token_and_resubscribes() {
+        let path = std::env::temp_dir().join(format!(
+            "twitch-chat-reauth-test-{}.toml",
+            std::process::id()
+        ));
+        TokenConfig {
+            access_token: Secret::new("old access token"),
+            refresh_token: Secret::new("old refresh token"),
+            expires_at: None,
+        }
+        .save(&path)
+        .unwrap();
+
+        let (url, requests) = serve_subscriptions();
+        let mut client = Client::builder()
+            .no_proxy()
+            .base_url(url.clone(), url)
+            .build()
+            .unwrap()
+            .authenticated(TokenManager::from_path(Secret::new("client id"), &path).unwrap());
+        let mut session = Session::test(&mut client);
+        let mut state = State::new(Keybindings::default(), &UiConfig::default(), &mut session);
+
+        let res = TokenResponse {
+            access_token: Secret::new("new access token"),
+            expires_in: 14400,
+            refresh_token: Secret::new("new refresh token"),
+            scope: Vec::new(),
+            token_type: "bearer".into(),
+        };
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(state.finish_reauth(Ok(res)));
+        assert_eq!(state.error, "");
+        assert_eq!(state.info, "reauthenticated and resubscribed");
+
+        let saved = TokenConfig::load(&path).unwrap();
+        assert_eq!(saved.access_token.access_secret_value(), "new access token");
+        assert_eq!(
+            saved.refresh_token.access_secret_value(),
+            "new refresh token"
+        );
+        let _ = fs::remove_file(&path);
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 8, "{requests:?}");
+        for request in requests.iter() {
+            assert!(
+                request.starts_with("POST /eventsub/subscriptions")
+                    && request.ends_with("bearer new access token"),
+                "{request}",
+            );
         }
     }
 }
@@ -2,12 +2,58 @@ use std::path::PathBuf;
 
 use clap::{Args, Subcommand};
 
+use crate::config::Event;
+
 #[derive(Debug, Args)]
 /// Start the main chat
 pub struct Run {
     /// Config file path
     #[clap(long, default_value = "twitch-chat.toml")]
     pub config: PathBuf,
+
+    #[clap(flatten)]
+    pub emit: Emit,
+}
+
+#[derive(Debug, Args)]
+/// Run without the terminal ui (subscribe, play sounds and store events)
+pub struct Daemon {
+    /// Config file path
+    #[clap(long, default_value = "twitch-chat.toml")]
+    pub config: PathBuf,
+
+    #[clap(flatten)]
+    pub emit: Emit,
+}
+
+#[derive(Debug, Args)]
+/// Play the sounds configured for an event
+pub struct TestSound {
+    /// Config file path
+    #[clap(long, default_value = "twitch-chat.toml")]
+    pub config: PathBuf,
+
+    /// Event to play the sounds for
+    pub event: Event,
+}
+
+#[derive(Debug, Args)]
+/// Help with the config file
+pub struct Config {
+    /// Print a commented example config
+    #[clap(long)]
+    pub example: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct Emit {
+    /// Publish events as newline-delimited JSON on this unix socket
+    #[clap(long)]
+    pub emit_socket: Option<PathBuf>,
+
+    /// Write events as newline-delimited JSON to this file or FIFO (`-` for stdout in daemon mode)
+    #[clap(long)]
+    pub emit_json: Option<PathBuf>,
 }
 
 #[derive(Debug, Subcommand)]
@@ -22,6 +68,10 @@ pub enum Eventsub {
         #[clap(long)]
         all: bool,
 
+        /// List the subscriptions that would be deleted, without deleting them
+        #[clap(long)]
+        dry_run: bool,
+
         /// Subscription ids to delete
         #[clap(required_unless_present = "all")]
         id: Option<String>,
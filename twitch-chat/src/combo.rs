@@ -0,0 +1,67 @@
+use std::{
+    future::{self, Future},
+    mem,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+use twitch_api::events::ws::NotificationMessageEvent;
+
+use crate::config::ComboConfig;
+
+/// Follows received in quick succession, stored as one event once the window has passed
+pub struct Combo {
+    window: Option<Duration>,
+    events: Vec<(DateTime<Utc>, NotificationMessageEvent)>,
+    last_at: Option<Instant>,
+}
+
+impl Combo {
+    pub fn new(config: Option<ComboConfig>) -> Self {
+        Self {
+            window: config.map(|config| config.window()),
+            events: Vec::new(),
+            last_at: None,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.window.is_some()
+    }
+
+    /// Whether a combo has started, later events do not play a sound
+    pub fn is_active(&self) -> bool {
+        !self.events.is_empty()
+    }
+
+    pub fn push(&mut self, timestamp: DateTime<Utc>, event: NotificationMessageEvent) {
+        self.events.push((timestamp, event));
+        self.last_at = Some(Instant::now());
+    }
+
+    fn ends_at(&self) -> Option<Instant> {
+        Some(self.last_at? + self.window?)
+    }
+
+    pub fn is_due(&self) -> bool {
+        self.ends_at()
+            .is_some_and(|ends_at| ends_at <= Instant::now())
+    }
+
+    /// Remove the events of the combo
+    pub fn take(&mut self) -> Vec<(DateTime<Utc>, NotificationMessageEvent)> {
+        self.last_at = None;
+        mem::take(&mut self.events)
+    }
+
+    /// Resolves when the combo window has passed, never if no combo is active
+    pub fn due(&self) -> impl Future<Output = ()> + 'static {
+        let ends_at = self.ends_at();
+        async move {
+            match ends_at {
+                Some(ends_at) => tokio::time::sleep_until(ends_at.into()).await,
+                None => future::pending().await,
+            }
+        }
+    }
+}
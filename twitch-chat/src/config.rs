@@ -1,18 +1,31 @@
 use std::{
+    borrow::Borrow,
     collections::HashMap,
+    fmt::{self, Write},
     fs,
+    marker::PhantomData,
+    net::SocketAddr,
+    num::{NonZeroU64, NonZeroUsize},
     path::{Path, PathBuf},
+    time::{Duration, Instant},
 };
 
 use anyhow::{Context, Result};
+use clap::ValueEnum;
 use crokey::KeyCombination;
-use serde::{Deserialize, Deserializer};
+use directories::ProjectDirs;
+use ratatui::style::Color;
+use serde::{
+    Deserialize, Deserializer,
+    de::{MapAccess, Visitor},
+};
 
 use crate::chat::Command;
 
 #[derive(Debug, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct Config {
+    #[serde(default)]
     pub store: StoreConfig,
 
     #[serde(with = "timezone")]
@@ -24,21 +37,436 @@ pub struct Config {
     #[serde(rename = "sound", default)]
     pub sounds: Vec<SoundConfig>,
 
+    #[serde(rename = "notification", default)]
+    pub notifications: Vec<NotificationConfig>,
+
+    /// Normalize all sounds to this loudness (RMS in dBFS), unless a sound has a volume
+    #[serde(default)]
+    pub loudness_target: Option<f32>,
+
+    #[serde(default)]
+    pub seen_chatters: SeenChattersScope,
+
+    /// Shout out raiders automatically, disabled if missing
+    #[serde(default)]
+    pub auto_shoutout: Option<AutoShoutoutConfig>,
+
+    /// Coalesce follows received in quick succession, disabled if missing
+    #[serde(default)]
+    pub combo: Option<ComboConfig>,
+
+    #[serde(default)]
+    pub away: AwayConfig,
+
+    /// Seconds to wait in addition to the keepalive timeout before the connection is considered dead
+    #[serde(default = "Config::default_keepalive_grace_secs")]
+    pub keepalive_grace_secs: u64,
+
+    /// Send messages and moderate with the tokens of this profile, e.g. the broadcaster while
+    /// chat is read by a bot account
+    #[serde(default)]
+    pub send_profile: Option<String>,
+
     #[serde(default = "Keybindings::empty")]
-    pub keybindings: Keybindings,
+    pub keybindings: Keybindings<Binding>,
+
+    #[serde(default)]
+    pub emit: EmitConfig,
+
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+
+    #[serde(default)]
+    pub ui: UiConfig,
 }
 
 impl Config {
     pub fn open(path: &Path) -> Result<Self> {
         let config = fs::read_to_string(path).context("read config file")?;
-        toml::from_str(&config).context("parse config file")
+        toml::from_str(&config)
+            .map_err(|err| {
+                let suggestion = suggest_key(err.message());
+                let err = anyhow::Error::new(err);
+                match suggestion {
+                    Some(suggestion) => err.context(suggestion),
+                    None => err,
+                }
+            })
+            .with_context(|| format!("parse config file: {}", path.display()))
+    }
+
+    fn default_keepalive_grace_secs() -> u64 {
+        5
     }
 }
 
-#[derive(Debug, Deserialize)]
+const EXAMPLE: &str = r##"# Timezone used to display timestamps and to split the store into days
+timezone = "Europe/Berlin"
+
+# Normalize all sounds without a volume to this loudness (RMS in dBFS)
+# loudness_target = -20.0
+
+# Whether the first message of a chatter is detected per "session" or "all_time"
+# seen_chatters = "session"
+
+# Seconds to wait in addition to the EventSub keepalive timeout before the connection is considered dead
+# keepalive_grace_secs = 5
+
+# Send messages and moderate with the tokens of another profile (`twitch-chat --profile NAME auth`)
+# send_profile = "broadcaster"
+
+[store]
+# Storage directory, defaults to the XDG data directory
+# path = "/home/user/.local/share/twitch-chat"
+
+# Maximum number of today's events kept in memory, older events are reloaded from disk
+# memory_limit = 10000
+
+# Maximum number of events reachable by scrolling up
+# scrollback_limit = 50000
+
+# Size in bytes after which a day is continued in a new file (DATE.1.json, DATE.2.json, ...)
+# max_file_size = 104857600
+
+# Kinds of events written to disk: message, notification, follow, cheer, redemption, raid, online
+# or offline; excluded events are still shown until they are evicted from memory
+# include = ["follow", "cheer", "raid"]
+# exclude = ["message"]
+
+# Days after which stored events of a kind are removed from the day files, kept forever if missing
+# retention = { message = 7, notification = 30 }
+
+# Shout out raiders automatically, remove the section to disable it
+# [auto_shoutout]
+# Seconds to wait after the raid before the shoutout is sent
+# delay_secs = 10
+# Raids with fewer viewers are not shouted out
+# min_viewers = 2
+
+# Store follows received in quick succession as one event with a single sound
+# [combo]
+# Follows within this many seconds of the previous one are part of the combo
+# window_secs = 5
+
+# Away mode toggled with /away [reply] and /back, messages received while away are tagged
+[away]
+# Sent to chatters that mention you or reply to you while away, no reply if missing
+# reply = "I'm away for a moment, back soon!"
+# Minimum seconds between two automatic replies to the same chatter
+# reply_cooldown_secs = 600
+
+[emit]
+# Unix socket path to publish events as newline-delimited JSON
+# socket = "/run/user/1000/twitch-chat.sock"
+
+[metrics]
+# Address of the http endpoint serving prometheus metrics, disabled if missing
+# listen = "127.0.0.1:9100"
+
+[ui]
+# Seconds after which an error is hidden, 0 keeps errors until the next input
+error_timeout_secs = 10
+
+# Colors for users without a chat color, defaults to the basic terminal colors
+# palette = ["red", "green", "yellow", "blue", "magenta", "cyan", "#ff8000"]
+
+# Whether events hidden by clearing the view (ctrl-l) are shown again when scrolling up
+# clear_view_scrollback = true
+
+# Whether to scroll to the first event received while the terminal was not focused
+# scroll_to_unread = false
+
+# Prefix clip and video links pasted into the message with their title
+# expand_pasted_links = false
+
+# Sound outputs, sounds without an output are played on the "default" output
+[output.default]
+# PulseAudio device, defaults to the default sink
+# device = "alsa_output.pci-0000_00_1f.3.analog-stereo"
+# volume = 1.0
+# thread_name = "audio output"
+# high_priority = false
+
+# Sounds played for an event: message, first_message, join, leave, follow, online, offline, raid,
+# cheer, redemption or error
+[[sound]]
+event = "follow"
+sound = "sounds/follow.mp3"
+# output = ["default"]
+# Volume of the sound, skips the loudness normalization
+# volume = 0.8
+# Only one of the sounds of an event with a weight is played, picked randomly by weight
+# weight = 1.0
+
+# Desktop notifications for an event, `{user}` and `{text}` are replaced
+# [[notification]]
+# event = "raid"
+# title = "{user} is raiding"
+# body = "{text}"
+
+# Keybindings extend the defaults below, bind a key to "unbind" to remove a default keybinding
+# Sound outputs can be muted individually with e.g. `d = { ToggleMute = "default" }`
+# Authors of chat messages can be banned with a preset reason, e.g. `"b s" = { Ban = "spam" }`
+"##;
+
+impl Config {
+    /// Commented example config, the keybindings are the defaults
+    pub fn example() -> Result<String> {
+        let mut example = EXAMPLE.to_string();
+        let keybindings = Keybindings::default();
+        for (mode, keybindings) in [
+            ("normal", &keybindings.normal),
+            ("insert", &keybindings.insert),
+        ] {
+            let mut keybindings = keybindings
+                .iter()
+                .map(|(keys, command)| {
+                    Ok((
+                        toml::Value::String(keys.to_string()),
+                        toml::Value::try_from(command).context("serialize command")?,
+                    ))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            keybindings.sort_by_key(|(keys, _)| keys.to_string());
+
+            writeln!(example, "\n[keybindings.{mode}]").unwrap();
+            for (keys, command) in keybindings {
+                writeln!(example, "{keys} = {command}").unwrap();
+            }
+        }
+        Ok(example)
+    }
+}
+
+/// Suggest the closest known key (or variant) for an `unknown field` error
+fn suggest_key(message: &str) -> Option<String> {
+    if !message.starts_with("unknown field") && !message.starts_with("unknown variant") {
+        return None;
+    }
+
+    // serde formats this as: unknown field `foo`, expected one of `bar`, `baz`
+    let mut quoted = message.split('`').skip(1).step_by(2);
+    let unknown = quoted.next()?;
+    let (distance, expected) = quoted
+        .map(|expected| (levenshtein(unknown, expected), expected))
+        .min()?;
+
+    (distance <= (unknown.chars().count() / 3).max(1))
+        .then(|| format!("unknown key `{unknown}`, did you mean `{expected}`?"))
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &b) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(a != b);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
+
+#[derive(Debug, Default, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct StoreConfig {
-    pub path: PathBuf,
+    /// Storage directory, defaults to the XDG data directory
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+
+    /// Maximum number of today's events kept in memory, older events are reloaded from disk
+    #[serde(default)]
+    pub memory_limit: Option<NonZeroUsize>,
+
+    /// Maximum number of events reachable by scrolling up, older events are hidden
+    #[serde(default)]
+    pub scrollback_limit: Option<NonZeroUsize>,
+
+    /// Size in bytes after which a day is continued in a new file (`DATE.1.json`, ...)
+    #[serde(default)]
+    pub max_file_size: Option<NonZeroU64>,
+
+    /// Only these kinds of events are written to disk, all if missing
+    #[serde(default)]
+    pub include: Option<Vec<EventKind>>,
+
+    /// These kinds of events are not written to disk, they are shown until evicted from memory
+    #[serde(default)]
+    pub exclude: Vec<EventKind>,
+
+    /// Days after which stored events of a kind are removed from the day files, kept forever if
+    /// missing
+    #[serde(default)]
+    pub retention: HashMap<EventKind, u32>,
+}
+
+/// Kinds of stored events, for filtering what is written to disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    Message,
+    Notification,
+    Follow,
+    Cheer,
+    Redemption,
+    Raid,
+    Online,
+    Offline,
+}
+
+impl StoreConfig {
+    pub fn path(&self) -> Result<PathBuf> {
+        if let Some(path) = &self.path {
+            return Ok(path.clone());
+        }
+        let proj_dirs = ProjectDirs::from("de.mgjm", "twitch-tools", "twitch-chat")
+            .context("failed to get data directory")?;
+        Ok(proj_dirs.data_dir().into())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AutoShoutoutConfig {
+    /// Seconds to wait after the raid before the shoutout is sent
+    #[serde(default = "AutoShoutoutConfig::default_delay_secs")]
+    pub delay_secs: u64,
+
+    /// Raids with fewer viewers are not shouted out
+    #[serde(default = "AutoShoutoutConfig::default_min_viewers")]
+    pub min_viewers: u32,
+}
+
+impl AutoShoutoutConfig {
+    fn default_delay_secs() -> u64 {
+        10
+    }
+
+    fn default_min_viewers() -> u32 {
+        2
+    }
+
+    pub fn delay(&self) -> Duration {
+        Duration::from_secs(self.delay_secs)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ComboConfig {
+    /// Follows within this many seconds of the previous one are part of the combo
+    #[serde(default = "ComboConfig::default_window_secs")]
+    pub window_secs: u64,
+}
+
+impl ComboConfig {
+    fn default_window_secs() -> u64 {
+        5
+    }
+
+    pub fn window(&self) -> Duration {
+        Duration::from_secs(self.window_secs)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AwayConfig {
+    /// Sent to chatters that address the broadcaster while away, no reply if missing
+    #[serde(default)]
+    pub reply: Option<String>,
+
+    /// Minimum seconds between two automatic replies to the same chatter
+    #[serde(default = "AwayConfig::default_reply_cooldown_secs")]
+    pub reply_cooldown_secs: u64,
+}
+
+impl AwayConfig {
+    fn default_reply_cooldown_secs() -> u64 {
+        600
+    }
+
+    pub fn reply_cooldown(&self) -> Duration {
+        Duration::from_secs(self.reply_cooldown_secs)
+    }
+}
+
+impl Default for AwayConfig {
+    fn default() -> Self {
+        Self {
+            reply: None,
+            reply_cooldown_secs: Self::default_reply_cooldown_secs(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EmitConfig {
+    /// Unix socket path to publish events as newline-delimited JSON
+    #[serde(default)]
+    pub socket: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MetricsConfig {
+    /// Address of the http endpoint serving prometheus metrics
+    #[serde(default)]
+    pub listen: Option<SocketAddr>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UiConfig {
+    /// Seconds after which an error is hidden, `0` keeps errors until the next input
+    #[serde(default = "UiConfig::default_error_timeout_secs")]
+    pub error_timeout_secs: u64,
+
+    /// Colors for users without a chat color, names (e.g. `"light-red"`) or hex (`"#ff0000"`)
+    #[serde(default, deserialize_with = "colors")]
+    pub palette: Vec<Color>,
+
+    /// Whether events hidden by clearing the view can be shown again by scrolling up
+    #[serde(default = "UiConfig::default_clear_view_scrollback")]
+    pub clear_view_scrollback: bool,
+
+    /// Whether to scroll to the first event received while the terminal was not focused
+    #[serde(default)]
+    pub scroll_to_unread: bool,
+
+    /// Prefix pasted clip and video links with their title
+    #[serde(default)]
+    pub expand_pasted_links: bool,
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        Self {
+            error_timeout_secs: Self::default_error_timeout_secs(),
+            palette: Vec::new(),
+            clear_view_scrollback: Self::default_clear_view_scrollback(),
+            scroll_to_unread: false,
+            expand_pasted_links: false,
+        }
+    }
+}
+
+impl UiConfig {
+    fn default_error_timeout_secs() -> u64 {
+        10
+    }
+
+    fn default_clear_view_scrollback() -> bool {
+        true
+    }
+
+    pub fn error_timeout(&self) -> Option<Duration> {
+        (self.error_timeout_secs != 0).then(|| Duration::from_secs(self.error_timeout_secs))
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -49,6 +477,12 @@ pub struct OutputConfig {
 
     #[serde(default)]
     pub volume: Option<f32>,
+
+    #[serde(default)]
+    pub thread_name: Option<String>,
+
+    #[serde(default)]
+    pub high_priority: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -63,10 +497,28 @@ pub struct SoundConfig {
 
     #[serde(default)]
     pub volume: Option<f32>,
+
+    /// Sounds of an event with a weight are picked randomly, one per event
+    #[serde(default)]
+    pub weight: Option<f32>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NotificationConfig {
+    pub event: Event,
+
+    /// Title template, `{user}` and `{text}` are replaced with the user name and the message
+    pub title: String,
+
+    /// Body template, with the same replacements as the title
+    #[serde(default)]
+    pub body: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, ValueEnum)]
 #[serde(rename_all = "snake_case")]
+#[value(rename_all = "snake_case")]
 pub enum Event {
     Message,
     Join,
@@ -74,6 +526,39 @@ pub enum Event {
     Follow,
     Online,
     Offline,
+    Raid,
+    Cheer,
+    /// Channel points reward redeemed
+    Redemption,
+    /// First message of a chatter, see `seen_chatters`
+    FirstMessage,
+    Error,
+}
+
+/// How long chatters are remembered to detect their first message
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SeenChattersScope {
+    /// Until the chat is closed
+    #[default]
+    Session,
+
+    /// Saved in the store directory, a chatter is only new once
+    AllTime,
+}
+
+fn colors<'de, D>(deserializer: D) -> Result<Vec<Color>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|color| {
+            color
+                .parse()
+                .map_err(|_| serde::de::Error::custom(format!("invalid color: {color:?}")))
+        })
+        .collect()
 }
 
 fn vec_or_value<'de, T, D>(deserializer: D) -> Result<Vec<T>, D::Error>
@@ -102,13 +587,16 @@ where
 }
 
 #[derive(Debug, Deserialize)]
-#[serde(deny_unknown_fields)]
-pub struct Keybindings {
-    #[serde(default)]
-    pub normal: HashMap<KeyCombination, Command>,
+#[serde(
+    deny_unknown_fields,
+    bound(deserialize = "B: Deserialize<'de> + fmt::Debug")
+)]
+pub struct Keybindings<B = Command> {
+    #[serde(default, deserialize_with = "unique_keys")]
+    pub normal: HashMap<KeySequence, B>,
 
-    #[serde(default)]
-    pub insert: HashMap<KeyCombination, Command>,
+    #[serde(default, deserialize_with = "unique_keys")]
+    pub insert: HashMap<KeySequence, B>,
 }
 
 impl Default for Keybindings {
@@ -120,17 +608,238 @@ impl Default for Keybindings {
     }
 }
 
-impl Keybindings {
+impl<B> Keybindings<B> {
     pub fn empty() -> Self {
         Self {
             normal: HashMap::new(),
             insert: HashMap::new(),
         }
     }
+}
+
+impl Keybindings {
+    /// Add the configured keybindings, replacing or removing the existing ones
+    pub fn extend(&mut self, other: Keybindings<Binding>) {
+        for (keybindings, other) in [
+            (&mut self.normal, other.normal),
+            (&mut self.insert, other.insert),
+        ] {
+            for (keys, binding) in other {
+                match binding {
+                    Binding::Command(command) => {
+                        keybindings.insert(keys, command);
+                    }
+                    Binding::Unbind => {
+                        keybindings.remove(&keys);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Warn about key sequences that can not be entered, because their start is bound as well
+    pub fn warn_conflicts(&self) {
+        for conflict in self.conflicts() {
+            eprintln!("warning: {conflict}");
+        }
+    }
+
+    /// Key sequences that can not be entered, because their start is bound as well
+    fn conflicts(&self) -> Vec<String> {
+        let mut all_conflicts = Vec::new();
+        for (mode, keybindings) in [("normal", &self.normal), ("insert", &self.insert)] {
+            let mut conflicts = Vec::new();
+            for (keys, command) in keybindings {
+                for (prefix, prefix_command) in keybindings {
+                    if prefix.0.len() < keys.0.len() && keys.0.starts_with(&prefix.0) {
+                        conflicts.push(format!(
+                            "{mode} keybinding `{keys}` ({command:?}) is unreachable, \
+                            `{prefix}` is bound to {prefix_command:?}",
+                        ));
+                    }
+                }
+            }
+            conflicts.sort();
+            all_conflicts.extend(conflicts);
+        }
+        all_conflicts
+    }
+}
+
+/// Configured binding of a key sequence, `"unbind"` removes a default keybinding
+#[derive(Debug, Clone)]
+pub enum Binding {
+    Command(Command),
+    Unbind,
+}
+
+impl<'de> Deserialize<'de> for Binding {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "lowercase")]
+        enum Unbind {
+            Unbind,
+        }
+
+        // e.g. `"unbind"`, `"Quit"` or `{ ToggleMute = "default" }`
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum UnbindOrCommand {
+            Unbind(Unbind),
+            Command(Command),
+        }
+
+        Ok(match UnbindOrCommand::deserialize(deserializer)? {
+            UnbindOrCommand::Unbind(Unbind::Unbind) => Self::Unbind,
+            UnbindOrCommand::Command(command) => Self::Command(command),
+        })
+    }
+}
+
+/// Deserialize a keybinding map, a key sequence may only be bound once
+///
+/// Different spellings like `ctrl-c` and `Ctrl-C` are the same key sequence and would otherwise
+/// silently replace each other.
+fn unique_keys<'de, V, D>(deserializer: D) -> Result<HashMap<KeySequence, V>, D::Error>
+where
+    V: Deserialize<'de> + fmt::Debug,
+    D: Deserializer<'de>,
+{
+    struct UniqueKeysVisitor<V>(PhantomData<V>);
+
+    impl<'de, V> Visitor<'de> for UniqueKeysVisitor<V>
+    where
+        V: Deserialize<'de> + fmt::Debug,
+    {
+        type Value = HashMap<KeySequence, V>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a map of key sequences to commands")
+        }
+
+        fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+        where
+            A: MapAccess<'de>,
+        {
+            let mut keybindings = HashMap::new();
+            while let Some((keys, command)) = map.next_entry::<KeySequence, V>()? {
+                if let Some(previous) = keybindings.get(&keys) {
+                    return Err(serde::de::Error::custom(format!(
+                        "key `{keys}` is bound to multiple commands: {previous:?} and {command:?}",
+                    )));
+                }
+                keybindings.insert(keys, command);
+            }
+            Ok(keybindings)
+        }
+    }
+
+    deserializer.deserialize_map(UniqueKeysVisitor(PhantomData))
+}
+
+/// Key combinations pressed one after another, written as e.g. `"g g"`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeySequence(pub Vec<KeyCombination>);
+
+impl From<KeyCombination> for KeySequence {
+    fn from(key: KeyCombination) -> Self {
+        Self(vec![key])
+    }
+}
+
+impl Borrow<[KeyCombination]> for KeySequence {
+    fn borrow(&self) -> &[KeyCombination] {
+        &self.0
+    }
+}
+
+impl fmt::Display for KeySequence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, key) in self.0.iter().enumerate() {
+            if i != 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{key}")?;
+        }
+        Ok(())
+    }
+}
 
-    pub fn extend(&mut self, other: Self) {
-        self.normal.extend(other.normal);
-        self.insert.extend(other.insert);
+impl<'de> Deserialize<'de> for KeySequence {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let sequence = String::deserialize(deserializer)?;
+        let keys = sequence
+            .split_whitespace()
+            .map(|key| key.parse().map_err(serde::de::Error::custom))
+            .collect::<Result<Vec<_>, _>>()?;
+        if keys.is_empty() {
+            return Err(serde::de::Error::custom("empty key sequence"));
+        }
+        Ok(Self(keys))
+    }
+}
+
+pub enum KeyLookup<C> {
+    Command(C),
+
+    /// The key is the start of a key sequence, wait for the next key
+    Pending,
+
+    Unbound,
+}
+
+/// Keys of a partially entered key sequence
+#[derive(Debug, Default)]
+pub struct PendingKeys {
+    keys: Vec<KeyCombination>,
+    last_key_at: Option<Instant>,
+}
+
+impl PendingKeys {
+    /// A partial key sequence is discarded if the next key is not pressed within this time
+    const TIMEOUT: Duration = Duration::from_secs(1);
+
+    /// Match the pressed key against the keybindings
+    ///
+    /// If a key sequence is also the start of a longer one, the shorter sequence wins.
+    pub fn lookup<C: Clone>(
+        &mut self,
+        keybindings: &HashMap<KeySequence, C>,
+        key: KeyCombination,
+    ) -> KeyLookup<C> {
+        if self
+            .last_key_at
+            .is_some_and(|last_key_at| last_key_at.elapsed() > Self::TIMEOUT)
+        {
+            self.keys.clear();
+        }
+        self.last_key_at = Some(Instant::now());
+        self.keys.push(key);
+
+        loop {
+            if let Some(command) = keybindings.get(self.keys.as_slice()) {
+                self.keys.clear();
+                return KeyLookup::Command(command.clone());
+            }
+            if keybindings
+                .keys()
+                .any(|sequence| sequence.0.starts_with(&self.keys))
+            {
+                return KeyLookup::Pending;
+            }
+            if self.keys.len() == 1 {
+                self.keys.clear();
+                return KeyLookup::Unbound;
+            }
+            // the partial sequence does not continue with this key, try the key on its own
+            self.keys.drain(..self.keys.len() - 1);
+        }
     }
 }
 
@@ -168,3 +877,268 @@ mod timezone {
         deserializer.deserialize_str(TzVisitor)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn binding(value: &str) -> Binding {
+        #[derive(Deserialize)]
+        struct Binding {
+            binding: super::Binding,
+        }
+
+        toml::from_str::<Binding>(&format!("binding = {value}"))
+            .unwrap()
+            .binding
+    }
+
+    #[test]
+    fn deserialize_binding() {
+        assert!(matches!(binding(r#""unbind""#), Binding::Unbind));
+        assert!(matches!(
+            binding(r#""Quit""#),
+            Binding::Command(Command::Quit)
+        ));
+        assert!(matches!(
+            binding(r#"{ ToggleMute = "default" }"#),
+            Binding::Command(Command::ToggleMute(output)) if output == "default"
+        ));
+    }
+
+    #[test]
+    fn deserialize_palette() {
+        let ui: UiConfig = toml::from_str(r##"palette = ["light-red", "#ff8000", "42"]"##).unwrap();
+        assert_eq!(
+            ui.palette,
+            [Color::LightRed, Color::Rgb(255, 128, 0), Color::Indexed(42)]
+        );
+        assert!(toml::from_str::<UiConfig>("").unwrap().palette.is_empty());
+
+        let err = toml::from_str::<UiConfig>(r#"palette = ["not a color"]"#).unwrap_err();
+        assert!(
+            err.message().contains(r#"invalid color: "not a color""#),
+            "{err}"
+        );
+    }
+
+    fn sequence(keys: &str) -> KeySequence {
+        KeySequence(
+            keys.split_whitespace()
+                .map(|key| key.parse().unwrap())
+                .collect(),
+        )
+    }
+
+    fn key(key: &str) -> KeyCombination {
+        key.parse().unwrap()
+    }
+
+    fn open(name: &str, config: &str) -> Result<Config> {
+        let path = std::env::temp_dir().join(format!(
+            "twitch-chat-config-test-{name}-{}.toml",
+            std::process::id(),
+        ));
+        fs::write(&path, config).unwrap();
+        let config = Config::open(&path);
+        fs::remove_file(&path).unwrap();
+        config
+    }
+
+    #[test]
+    fn missing_field_is_named() {
+        let err = open("missing", "loudness_target = -20.0\n").err().unwrap();
+        let message = format!("{err:#}");
+        assert!(message.starts_with("parse config file: "), "{message}");
+        assert!(message.contains("missing field `timezone`"), "{message}");
+    }
+
+    #[test]
+    fn misspelled_key_is_suggested() {
+        let err = open("misspelled", "timezone = \"UTC\"\nloudnes_target = -20.0\n")
+            .err()
+            .unwrap();
+        let message = format!("{err:#}");
+        assert!(
+            message.contains("unknown key `loudnes_target`, did you mean `loudness_target`?"),
+            "{message}"
+        );
+        // the toml error points to the line of the key
+        assert!(message.contains("line 2"), "{message}");
+    }
+
+    #[test]
+    fn unrelated_key_is_not_suggested() {
+        let err = open("unrelated", "timezone = \"UTC\"\nvolume = 1.0\n")
+            .err()
+            .unwrap();
+        let message = format!("{err:#}");
+        assert!(message.contains("unknown field `volume`"), "{message}");
+        assert!(!message.contains("did you mean"), "{message}");
+    }
+
+    #[test]
+    fn unbind_removes_a_default_keybinding() {
+        let config: Keybindings<Binding> =
+            toml::from_str("[normal]\nq = \"unbind\"\nctrl-q = \"Quit\"\n").unwrap();
+        let mut keybindings = Keybindings::default();
+        assert!(matches!(
+            keybindings.normal.get(&sequence("q")),
+            Some(Command::Quit)
+        ));
+
+        keybindings.extend(config);
+        assert!(!keybindings.normal.contains_key(&sequence("q")));
+        assert!(matches!(
+            keybindings.normal.get(&sequence("ctrl-q")),
+            Some(Command::Quit)
+        ));
+        // other defaults are kept
+        assert!(keybindings.normal.contains_key(&sequence("j")));
+    }
+
+    #[test]
+    fn duplicate_keybinding_is_reported() {
+        let err = toml::from_str::<Keybindings<Binding>>(
+            "[normal]\nctrl-c = \"Quit\"\nCtrl-c = \"GoTop\"\n",
+        )
+        .unwrap_err();
+        assert!(
+            err.message().contains(
+                "key `Ctrl-c` is bound to multiple commands: Command(Quit) and Command(GoTop)"
+            ),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn unreachable_keybinding_is_reported() {
+        assert!(Keybindings::default().conflicts().is_empty());
+
+        let mut keybindings: Keybindings = Keybindings::empty();
+        keybindings.normal.insert(sequence("g"), Command::GoTop);
+        keybindings.normal.insert(sequence("g g"), Command::Quit);
+        assert_eq!(
+            keybindings.conflicts(),
+            ["normal keybinding `g g` (Quit) is unreachable, `g` is bound to GoTop",]
+        );
+    }
+
+    #[test]
+    fn example_config_parses() {
+        let config: Config = toml::from_str(&Config::example().unwrap()).unwrap();
+        let defaults = Keybindings::default();
+        assert_eq!(config.keybindings.normal.len(), defaults.normal.len());
+        assert_eq!(config.keybindings.insert.len(), defaults.insert.len());
+        for keys in defaults.normal.keys() {
+            assert!(
+                matches!(
+                    config.keybindings.normal.get(keys),
+                    Some(Binding::Command(_))
+                ),
+                "missing keybinding {keys}"
+            );
+        }
+    }
+
+    #[test]
+    fn key_sequence_lookup() {
+        let keybindings = HashMap::from([(sequence("g g"), 1), (sequence("d"), 2)]);
+        let mut pending = PendingKeys::default();
+        assert!(matches!(
+            pending.lookup(&keybindings, key("g")),
+            KeyLookup::Pending
+        ));
+        assert!(matches!(
+            pending.lookup(&keybindings, key("g")),
+            KeyLookup::Command(1)
+        ));
+        assert!(matches!(
+            pending.lookup(&keybindings, key("d")),
+            KeyLookup::Command(2)
+        ));
+        assert!(matches!(
+            pending.lookup(&keybindings, key("x")),
+            KeyLookup::Unbound
+        ));
+    }
+
+    #[test]
+    fn partial_sequence_falls_back_to_the_key() {
+        let keybindings = HashMap::from([(sequence("g g"), 1), (sequence("d"), 2)]);
+        let mut pending = PendingKeys::default();
+        assert!(matches!(
+            pending.lookup(&keybindings, key("g")),
+            KeyLookup::Pending
+        ));
+        assert!(matches!(
+            pending.lookup(&keybindings, key("d")),
+            KeyLookup::Command(2)
+        ));
+
+        assert!(matches!(
+            pending.lookup(&keybindings, key("g")),
+            KeyLookup::Pending
+        ));
+        assert!(matches!(
+            pending.lookup(&keybindings, key("x")),
+            KeyLookup::Unbound
+        ));
+        // the partial sequence was discarded
+        assert!(matches!(
+            pending.lookup(&keybindings, key("g")),
+            KeyLookup::Pending
+        ));
+    }
+
+    #[test]
+    fn shorter_sequence_wins() {
+        let keybindings = HashMap::from([(sequence("g"), 1), (sequence("g g"), 2)]);
+        let mut pending = PendingKeys::default();
+        assert!(matches!(
+            pending.lookup(&keybindings, key("g")),
+            KeyLookup::Command(1)
+        ));
+        assert!(matches!(
+            pending.lookup(&keybindings, key("g")),
+            KeyLookup::Command(1)
+        ));
+    }
+
+    #[test]
+    fn partial_sequence_times_out() {
+        let keybindings = HashMap::from([(sequence("g g"), 1)]);
+        let mut pending = PendingKeys::default();
+        assert!(matches!(
+            pending.lookup(&keybindings, key("g")),
+            KeyLookup::Pending
+        ));
+        pending.last_key_at = Instant::now().checked_sub(PendingKeys::TIMEOUT * 2);
+        assert!(matches!(
+            pending.lookup(&keybindings, key("g")),
+            KeyLookup::Pending
+        ));
+        assert!(matches!(
+            pending.lookup(&keybindings, key("g")),
+            KeyLookup::Command(1)
+        ));
+    }
+
+    #[test]
+    fn store_path_defaults_to_the_data_dir() {
+        let config: StoreConfig = toml::from_str("").unwrap();
+        let data_dir = ProjectDirs::from("de.mgjm", "twitch-tools", "twitch-chat")
+            .unwrap()
+            .data_dir()
+            .to_path_buf();
+        assert_eq!(config.path().unwrap(), data_dir);
+        let xdg_data_home = directories::BaseDirs::new()
+            .unwrap()
+            .data_dir()
+            .to_path_buf();
+        assert!(data_dir.starts_with(xdg_data_home), "{data_dir:?}");
+
+        let config: StoreConfig = toml::from_str(r#"path = "/srv/chat""#).unwrap();
+        assert_eq!(config.path().unwrap(), Path::new("/srv/chat"));
+    }
+}
@@ -0,0 +1,55 @@
+use std::pin::pin;
+
+use anyhow::{Context, Result};
+use futures::future::{self, Either};
+use twitch_api::events::ws::{Incoming, WebSocket};
+
+use crate::session::{Session, connection_lost};
+
+/// Handle notifications without the terminal ui until ctrl-c is pressed
+pub async fn run(session: &mut Session<'_>, mut ws: WebSocket) -> Result<()> {
+    session.start()?;
+    session.connected(&ws);
+    let expired_events = session.store.expired_events();
+    if expired_events > 0 {
+        eprintln!("removed {expired_events} expired events");
+    }
+    if let Err(err) = session.update_stream().await {
+        eprintln!("{err:?}");
+    }
+
+    let mut ctrl_c = pin!(tokio::signal::ctrl_c());
+
+    loop {
+        let due = session.due();
+        let next = match future::select(ctrl_c.as_mut(), future::select(pin!(ws.next()), pin!(due)))
+            .await
+        {
+            Either::Left((res, _)) => {
+                res.context("wait for ctrl-c")?;
+                eprintln!("received ctrl-c, shutting down");
+                return Ok(());
+            }
+            Either::Right((Either::Left((next, _)), _)) => next,
+            Either::Right((Either::Right(_), _)) => {
+                if let Err(err) = session.handle_due().await {
+                    eprintln!("{err:?}");
+                }
+                continue;
+            }
+        };
+
+        if connection_lost(&next) {
+            eprintln!("web socket connection lost, reconnecting");
+            ws = session.reconnect().await?;
+            continue;
+        }
+        let Some((timestamp, incoming)) = next? else {
+            anyhow::bail!("web socket connection closed");
+        };
+        if let Incoming::Reconnected = incoming {
+            eprintln!("event sub: reconnected web socket");
+        }
+        session.handle_incoming(timestamp, incoming).await?;
+    }
+}
@@ -0,0 +1,158 @@
+use std::{
+    fs::{self, File},
+    io::{self, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+
+use crate::store::Event;
+
+/// Publish events as newline-delimited JSON (e.g. for stream overlays)
+#[derive(Default)]
+pub struct Emitter {
+    socket: Option<Socket>,
+    writer: Option<Box<dyn Write>>,
+}
+
+impl Emitter {
+    /// Listen on a unix socket, every connected client receives all events
+    pub fn listen(&mut self, path: &Path) -> Result<()> {
+        if path.exists() {
+            fs::remove_file(path).context("remove stale emit socket")?;
+        }
+        let listener = UnixListener::bind(path).context("bind emit socket")?;
+        listener
+            .set_nonblocking(true)
+            .context("set emit socket non-blocking")?;
+        self.socket = Some(Socket {
+            path: path.into(),
+            listener,
+            clients: Vec::new(),
+        });
+        Ok(())
+    }
+
+    /// Write all events to a file or FIFO, `-` writes to stdout
+    pub fn write_to(&mut self, path: &Path) -> Result<()> {
+        self.writer = Some(if path == Path::new("-") {
+            Box::new(io::stdout())
+        } else {
+            Box::new(
+                File::options()
+                    .append(true)
+                    .create(true)
+                    .open(path)
+                    .context("open emit file")?,
+            )
+        });
+        Ok(())
+    }
+
+    pub fn emit(&mut self, event: &Event) {
+        if self.socket.is_none() && self.writer.is_none() {
+            return;
+        }
+
+        let mut json = match serde_json::to_string(event) {
+            Ok(json) => json,
+            Err(err) => {
+                eprintln!("failed to encode emitted event: {err}");
+                return;
+            }
+        };
+        json.push('\n');
+
+        if let Some(socket) = &mut self.socket {
+            socket.accept();
+            socket
+                .clients
+                .retain_mut(|client| client.write_all(json.as_bytes()).is_ok());
+        }
+
+        if let Some(writer) = &mut self.writer
+            && let Err(err) = writer
+                .write_all(json.as_bytes())
+                .and_then(|()| writer.flush())
+        {
+            eprintln!("failed to write emitted event: {err}");
+            self.writer = None;
+        }
+    }
+}
+
+struct Socket {
+    path: PathBuf,
+    listener: UnixListener,
+    clients: Vec<UnixStream>,
+}
+
+impl Socket {
+    fn accept(&mut self) {
+        loop {
+            match self.listener.accept() {
+                Ok((client, _addr)) => {
+                    // a slow client is dropped instead of blocking the chat
+                    if client.set_nonblocking(true).is_ok() {
+                        self.clients.push(client);
+                    }
+                }
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                Err(err) => {
+                    eprintln!("failed to accept emit socket client: {err}");
+                    break;
+                }
+            }
+        }
+    }
+}
+
+impl Drop for Socket {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader};
+
+    use chrono::Utc;
+
+    use super::*;
+
+    fn message(text: &str) -> Event {
+        Event::Message {
+            sent_at: Utc::now(),
+            user_login: "user".into(),
+            text: text.into(),
+        }
+    }
+
+    #[test]
+    fn socket_clients_receive_one_json_event_per_line() {
+        let path =
+            std::env::temp_dir().join(format!("twitch-chat-emit-test-{}.sock", std::process::id()));
+        let mut emitter = Emitter::default();
+        emitter.listen(&path).unwrap();
+
+        // clients are accepted on the next event, earlier events are not replayed
+        emitter.emit(&message("before"));
+        let client = UnixStream::connect(&path).unwrap();
+        emitter.emit(&message("first"));
+        emitter.emit(&message("second"));
+
+        let mut lines = BufReader::new(client).lines();
+        for expected in ["first", "second"] {
+            let line = lines.next().unwrap().unwrap();
+            let Event::Message { text, .. } = serde_json::from_str(&line).unwrap() else {
+                panic!("not a message: {line}");
+            };
+            assert_eq!(text, expected);
+        }
+
+        drop(emitter);
+        assert!(!path.exists());
+    }
+}
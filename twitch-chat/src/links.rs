@@ -0,0 +1,75 @@
+use url::Url;
+
+/// Clip or video (VOD) link to twitch
+#[derive(Debug, PartialEq, Eq)]
+pub enum TwitchLink {
+    Clip(String),
+    Video(String),
+}
+
+impl TwitchLink {
+    /// Recognize `clips.twitch.tv/ID`, `twitch.tv/CHANNEL/clip/ID`, `twitch.tv/clip/ID` and `twitch.tv/videos/ID`
+    pub fn parse(text: &str) -> Option<Self> {
+        let url = Url::parse(text.trim()).ok()?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return None;
+        }
+        let host = url.host_str()?;
+        let segments: Vec<_> = url.path_segments()?.filter(|s| !s.is_empty()).collect();
+
+        let link = match (host, segments.as_slice()) {
+            ("clips.twitch.tv", [id]) => Self::Clip(id.to_string()),
+            ("twitch.tv" | "www.twitch.tv" | "m.twitch.tv", segments) => match segments {
+                [_, "clip", id] | ["clip", id] => Self::Clip(id.to_string()),
+                ["videos", id] if id.bytes().all(|b| b.is_ascii_digit()) => {
+                    Self::Video(id.to_string())
+                }
+                _ => return None,
+            },
+            _ => return None,
+        };
+        Some(link)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_clip_links() {
+        let clip = Some(TwitchLink::Clip(
+            "AwkwardHelplessSalamanderSwiftRage".into(),
+        ));
+        for link in [
+            "https://clips.twitch.tv/AwkwardHelplessSalamanderSwiftRage",
+            "https://www.twitch.tv/cool_user/clip/AwkwardHelplessSalamanderSwiftRage",
+            "https://twitch.tv/clip/AwkwardHelplessSalamanderSwiftRage?filter=clips",
+            " https://m.twitch.tv/cool_user/clip/AwkwardHelplessSalamanderSwiftRage/ ",
+        ] {
+            assert_eq!(TwitchLink::parse(link), clip, "{link}");
+        }
+    }
+
+    #[test]
+    fn parse_video_links() {
+        assert_eq!(
+            TwitchLink::parse("https://www.twitch.tv/videos/1234567890"),
+            Some(TwitchLink::Video("1234567890".into())),
+        );
+        assert_eq!(TwitchLink::parse("https://www.twitch.tv/videos/abc"), None);
+    }
+
+    #[test]
+    fn ignore_other_links() {
+        for text in [
+            "clips.twitch.tv/AwkwardHelplessSalamanderSwiftRage",
+            "ftp://clips.twitch.tv/AwkwardHelplessSalamanderSwiftRage",
+            "https://www.twitch.tv/cool_user",
+            "https://example.com/clip/AwkwardHelplessSalamanderSwiftRage",
+            "no link",
+        ] {
+            assert_eq!(TwitchLink::parse(text), None, "{text}");
+        }
+    }
+}
@@ -1,23 +1,45 @@
-use std::{io, sync::OnceLock};
+use std::{collections::HashMap, io, panic, path::Path, sync::OnceLock, time::Duration};
 
 use anyhow::{Context, Result};
+use away::Away;
 use chrono_tz::Tz;
-use clap::Parser;
-use config::Keybindings;
+use clap::{Parser, Subcommand};
+use combo::Combo;
+use config::{
+    Config, EmitConfig, Keybindings, MetricsConfig, OutputConfig, SoundConfig, StoreConfig,
+};
 use crossterm::event;
+use emit::Emitter;
+use futures::TryStreamExt;
+use metrics_exporter_prometheus::PrometheusBuilder;
+use notifier::Notifier;
+use seen::SeenChatters;
+use session::{Sender, Session};
+use shoutout::Shoutouts;
+use sound_system::SoundSystem;
+use store::Store;
 use tokio::task::LocalSet;
 use twitch::Subscriptions;
 use twitch_api::{
     auth::{self, Scope},
-    client::Client,
+    client::{AuthenticatedClient, Client},
     events::subscription::{DeleteSubscriptionRequest, GetSubscriptionsRequest},
     secret::Secret,
-    user::UsersRequest,
+    user::{User, UsersRequest},
 };
 
+mod away;
 mod chat;
 mod cmd;
+mod combo;
 mod config;
+mod daemon;
+mod emit;
+mod links;
+mod notifier;
+mod seen;
+mod session;
+mod shoutout;
 mod sound_system;
 mod store;
 mod twitch;
@@ -25,9 +47,22 @@ mod twitch;
 #[derive(Debug, Parser)]
 #[clap(version)]
 /// Twitch chat in the terminal
+struct Cli {
+    /// Use the tokens of a named profile (`token-data.PROFILE.toml`), e.g. of a bot account
+    #[arg(long, global = true)]
+    profile: Option<String>,
+
+    #[clap(subcommand)]
+    cmd: Cmd,
+}
+
+#[derive(Debug, Subcommand)]
 enum Cmd {
     Auth(auth::Auth),
     Run(cmd::Run),
+    Daemon(cmd::Daemon),
+    TestSound(cmd::TestSound),
+    Config(cmd::Config),
     #[clap(subcommand)]
     Eventsub(cmd::Eventsub),
 }
@@ -41,19 +76,17 @@ fn main() -> Result<()> {
 }
 
 async fn run() -> Result<()> {
-    let cmd = Cmd::parse();
-
-    match cmd {
-        Cmd::Auth(cmd) => {
-            cmd.run([
-                Scope::UserReadChat,
-                Scope::UserWriteChat,
-                Scope::ModeratorManageAnnouncements,
-                Scope::ModeratorReadFollowers,
-            ])
-            .await
-        }
+    let cli = Cli::parse();
+    if let Some(profile) = cli.profile {
+        twitch_api::config::set_profile(profile)?;
+    }
+
+    match cli.cmd {
+        Cmd::Auth(cmd) => cmd.run(&Client::new(), twitch::SCOPES).await,
         Cmd::Run(cmd) => cmd.run().await,
+        Cmd::Daemon(cmd) => cmd.run().await,
+        Cmd::TestSound(cmd) => cmd.run(),
+        Cmd::Config(cmd) => cmd.run(),
         Cmd::Eventsub(cmd) => cmd.run().await,
     }
 }
@@ -66,55 +99,273 @@ fn timezone() -> &'static Tz {
 
 impl cmd::Run {
     async fn run(&self) -> Result<()> {
-        let config = crate::config::Config::open(&self.config)?;
-        anyhow::ensure!(
-            TIMEZONE.set(config.timezone).is_ok(),
-            "timezone already set",
-        );
+        let config = open_config(&self.config)?;
 
         let mut keybindings = Keybindings::default();
         keybindings.extend(config.keybindings);
+        keybindings.warn_conflicts();
 
-        let sound_system = sound_system::SoundSystem::init(config.outputs, config.sounds)?;
-
-        eprintln!("sound system initialized");
-
-        let store = crate::store::Store::init(config.store.path)?;
-
-        let mut client = Client::new().authenticated_from_env()?;
+        init_metrics(&config.metrics)?;
+        let emitter = self.emit.init(&config.emit, false)?;
+        let notifier = Notifier::new(config.notifications);
+        let (mut client, user, store, sound_system) = init(
+            config.store,
+            config.outputs,
+            config.sounds,
+            config.loudness_target,
+        )
+        .await?;
+        let sender = init_sender(&client, &user, config.send_profile).await?;
 
-        let user = client
-            .send(&UsersRequest::me())
-            .await
-            .context("fetch user me")?
-            .into_user()
-            .context("missing me user")?;
-        eprintln!("user id: {:?}", user.id);
+        let seen_chatters = SeenChatters::open(config.seen_chatters, store.directory())?;
 
-        let (subsciptions, ws) = Subscriptions::subscribe(&mut client, &user).await?;
+        let (subsciptions, ws) = Subscriptions::subscribe(
+            &mut client,
+            &user,
+            Duration::from_secs(config.keepalive_grace_secs),
+        )
+        .await?;
 
-        let terminal = ratatui::init();
-        let tty_mode_guard = TtyModes::enable();
-        let run_result = chat::run(
-            terminal,
-            keybindings,
-            store,
+        let mut session = Session::new(
             &mut client,
             user,
-            ws,
+            sender,
+            store,
             sound_system,
-        )
-        .await;
+            emitter,
+            notifier,
+            Shoutouts::new(config.auto_shoutout),
+            Combo::new(config.combo),
+            Away::new(config.away),
+            seen_chatters,
+            subsciptions,
+        );
+
+        let terminal = ratatui::init();
+        let tty_mode_guard = TtyModes::enable();
+        let run_result = chat::run(terminal, keybindings, config.ui, &mut session, ws).await;
 
         drop(tty_mode_guard);
         ratatui::restore();
 
+        let subsciptions = session.shutdown();
+
         subsciptions.unsubscribe(&mut client).await?;
 
         run_result
     }
 }
 
+impl cmd::Daemon {
+    async fn run(&self) -> Result<()> {
+        let config = open_config(&self.config)?;
+
+        init_metrics(&config.metrics)?;
+        let emitter = self.emit.init(&config.emit, true)?;
+        let notifier = Notifier::new(config.notifications);
+        let (mut client, user, store, sound_system) = init(
+            config.store,
+            config.outputs,
+            config.sounds,
+            config.loudness_target,
+        )
+        .await?;
+        let sender = init_sender(&client, &user, config.send_profile).await?;
+
+        let seen_chatters = SeenChatters::open(config.seen_chatters, store.directory())?;
+
+        let (subsciptions, ws) = Subscriptions::subscribe(
+            &mut client,
+            &user,
+            Duration::from_secs(config.keepalive_grace_secs),
+        )
+        .await?;
+
+        let mut session = Session::new(
+            &mut client,
+            user,
+            sender,
+            store,
+            sound_system,
+            emitter,
+            notifier,
+            Shoutouts::new(config.auto_shoutout),
+            Combo::new(config.combo),
+            Away::new(config.away),
+            seen_chatters,
+            subsciptions,
+        );
+        let run_result = daemon::run(&mut session, ws).await;
+        let subsciptions = session.shutdown();
+
+        subsciptions.unsubscribe(&mut client).await?;
+
+        run_result
+    }
+}
+
+impl cmd::Config {
+    fn run(&self) -> Result<()> {
+        anyhow::ensure!(self.example, "nothing to do, try --example");
+        print!("{}", Config::example()?);
+        Ok(())
+    }
+}
+
+impl cmd::TestSound {
+    fn run(&self) -> Result<()> {
+        let config = Config::open(&self.config)?;
+        let mut sound_system =
+            SoundSystem::init(config.outputs, config.sounds, config.loudness_target)?;
+        anyhow::ensure!(
+            sound_system.has_sound_for_event(self.event),
+            "no sound configured for event: {:?}",
+            self.event,
+        );
+        sound_system.play_sound_for_event(self.event);
+        sound_system.shutdown(Duration::from_secs(60))
+    }
+}
+
+impl cmd::Emit {
+    fn init(&self, config: &EmitConfig, allow_stdout: bool) -> Result<Emitter> {
+        let mut emitter = Emitter::default();
+        if let Some(path) = self.emit_socket.as_ref().or(config.socket.as_ref()) {
+            emitter.listen(path)?;
+        }
+        if let Some(path) = &self.emit_json {
+            anyhow::ensure!(
+                allow_stdout || path.as_os_str() != "-",
+                "can not emit events to stdout, it is used by the terminal ui",
+            );
+            emitter.write_to(path)?;
+        }
+        Ok(emitter)
+    }
+}
+
+fn open_config(path: &Path) -> Result<Config> {
+    let config = Config::open(path)?;
+    anyhow::ensure!(
+        TIMEZONE.set(config.timezone).is_ok(),
+        "timezone already set",
+    );
+    Ok(config)
+}
+
+/// Serve prometheus metrics, if an endpoint is configured
+fn init_metrics(config: &MetricsConfig) -> Result<()> {
+    if let Some(listen) = config.listen {
+        PrometheusBuilder::new()
+            .with_http_listener(listen)
+            .install()
+            .context("start metrics endpoint")?;
+        eprintln!("serving metrics on http://{listen}/metrics");
+    }
+    Ok(())
+}
+
+async fn init(
+    store: StoreConfig,
+    outputs: HashMap<String, OutputConfig>,
+    sounds: Vec<SoundConfig>,
+    loudness_target: Option<f32>,
+) -> Result<(AuthenticatedClient, User, Store, SoundSystem)> {
+    let sound_system = SoundSystem::init(outputs, sounds, loudness_target)?;
+
+    eprintln!("sound system initialized");
+
+    let store = Store::init(store)?;
+
+    let mut client = Client::new().authenticated_from_env()?;
+    client
+        .ensure_valid_token()
+        .await
+        .context("validate access token")?;
+
+    let user = client
+        .send(&UsersRequest::me())
+        .await
+        .context("fetch user me")?
+        .into_user()
+        .context("missing me user")?;
+    eprintln!("user id: {:?}", user.id);
+
+    Ok((client, user, store, sound_system))
+}
+
+/// Create the client of the send profile and check that both profiles have the scopes of their
+/// role, the send profile must be a different user
+async fn init_sender(
+    client: &AuthenticatedClient,
+    reader: &User,
+    profile: Option<String>,
+) -> Result<Option<Sender>> {
+    let Some(profile) = profile else {
+        return Ok(None);
+    };
+
+    check_scopes(
+        client,
+        &twitch::READ_SCOPES,
+        twitch_api::config::profile().as_deref(),
+    )
+    .await?;
+
+    let mut sender_client = Client::new().authenticated_from_profile(profile.clone())?;
+    sender_client
+        .ensure_valid_token()
+        .await
+        .with_context(|| format!("validate access token of profile {profile:?}"))?;
+    check_scopes(&sender_client, &twitch::WRITE_SCOPES, Some(&profile)).await?;
+
+    let user = sender_client
+        .send(&UsersRequest::me())
+        .await
+        .context("fetch send profile user")?
+        .into_user()
+        .context("missing send profile user")?;
+    eprintln!("sender user id: {:?}", user.id);
+    if user.id == reader.id {
+        // the token file set by the environment is shared by all profiles
+        let hint = if std::env::var_os("TWITCH_TOKEN_DATA").is_some() {
+            ", TWITCH_TOKEN_DATA is used for every profile"
+        } else {
+            ""
+        };
+        anyhow::bail!("send profile {profile:?} is the same user as the reading profile{hint}");
+    }
+
+    Ok(Some(Sender {
+        client: sender_client,
+        user,
+    }))
+}
+
+/// Fail with a hint to authenticate again, if the access token is missing any of the scopes
+async fn check_scopes(
+    client: &AuthenticatedClient,
+    scopes: &[Scope],
+    profile: Option<&str>,
+) -> Result<()> {
+    let token = client
+        .validate_token()
+        .await
+        .context("validate access token")?;
+    let missing: Vec<_> = scopes
+        .iter()
+        .filter(|scope| !token.has_scope(**scope))
+        .collect();
+    if !missing.is_empty() {
+        let auth = match profile {
+            Some(profile) => format!("twitch-chat --profile {profile} auth"),
+            None => "twitch-chat auth".into(),
+        };
+        anyhow::bail!("access token is missing scopes {missing:?}, run `{auth}`");
+    }
+    Ok(())
+}
+
 impl cmd::Eventsub {
     async fn run(self) -> Result<()> {
         let mut client = Client::new().authenticated_from_env()?;
@@ -129,26 +380,39 @@ impl cmd::Eventsub {
                     .context("get subscriptions")?;
                 eprintln!("{res:#?}");
             }
-            Self::Delete { all, id } => {
-                let ids = if all {
-                    let res = client
-                        .send(&GetSubscriptionsRequest {
+            Self::Delete { all, dry_run, id } => {
+                let ids: Vec<_> = if all {
+                    let subscriptions: Vec<_> = client
+                        .paginate(GetSubscriptionsRequest {
                             ..Default::default()
                         })
+                        .try_collect()
                         .await
                         .context("get subscriptions")?;
-
-                    res.data.into_iter().map(|i| i.id).collect()
+                    if dry_run {
+                        for subscription in &subscriptions {
+                            eprintln!("{subscription:#?}");
+                        }
+                    }
+                    subscriptions.into_iter().map(|i| i.id).collect()
                 } else {
                     Vec::from_iter(id.map(Secret::new))
                 };
 
-                let num_ids = ids.len();
-                for id in ids {
-                    client
-                        .send(&DeleteSubscriptionRequest { id })
-                        .await
-                        .context("delete subscription")?;
+                let requests = match Deletion::plan(ids, dry_run) {
+                    Deletion::DryRun(num_ids) => {
+                        eprintln!("would delete {num_ids} ids");
+                        return Ok(());
+                    }
+                    Deletion::Delete(requests) => requests,
+                };
+                let num_ids = requests.len();
+                if all && num_ids > 0 {
+                    confirm(&format!("Delete all {num_ids} subscriptions? [y/N] "))?;
+                }
+
+                for req in requests {
+                    client.send(&req).await.context("delete subscription")?;
                 }
 
                 eprintln!("deleted {num_ids} ids",);
@@ -159,29 +423,132 @@ impl cmd::Eventsub {
     }
 }
 
+/// What `eventsub delete` does with the selected subscriptions
+enum Deletion {
+    /// Only report the number of subscriptions
+    DryRun(usize),
+    Delete(Vec<DeleteSubscriptionRequest>),
+}
+
+impl Deletion {
+    fn plan(ids: Vec<Secret>, dry_run: bool) -> Self {
+        if dry_run {
+            return Self::DryRun(ids.len());
+        }
+        Self::Delete(
+            ids.into_iter()
+                .map(|id| DeleteSubscriptionRequest { id })
+                .collect(),
+        )
+    }
+}
+
+/// Ask for confirmation on stdin, fails unless the answer is yes
+fn confirm(prompt: &str) -> Result<()> {
+    eprint!("{prompt}");
+    let mut answer = String::new();
+    io::stdin()
+        .read_line(&mut answer)
+        .context("read line from stdin")?;
+    anyhow::ensure!(matches!(answer.trim(), "y" | "yes"), "canceled");
+    Ok(())
+}
+
 #[must_use]
 struct TtyModes(());
 
 impl TtyModes {
+    /// Enable the tty modes and install a panic hook, that resets the terminal before the panic
+    /// message is printed
     fn enable() -> Self {
         crossterm::execute!(
             io::stdout(),
             event::EnableFocusChange,
             event::EnableMouseCapture,
+            event::EnableBracketedPaste,
         )
         .expect("enable tty modes");
+
+        restore_on_panic(|| {
+            Self::disable();
+            ratatui::restore();
+        });
+
         Self(())
     }
-}
 
-impl Drop for TtyModes {
-    fn drop(&mut self) {
+    /// Disable the tty modes, can be called multiple times
+    fn disable() {
         if let Err(err) = crossterm::execute!(
             io::stdout(),
             event::DisableFocusChange,
             event::DisableMouseCapture,
+            event::DisableBracketedPaste,
         ) {
             eprintln!("failed to disable tty modes: {err}");
         }
     }
 }
+
+impl Drop for TtyModes {
+    fn drop(&mut self) {
+        Self::disable();
+    }
+}
+
+/// Run the restore before the previous panic hook prints the panic message
+fn restore_on_panic(restore: fn()) {
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        restore();
+        hook(info);
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dry_run_deletes_nothing() {
+        let ids = || vec![Secret::new("a"), Secret::new("b")];
+        assert!(matches!(Deletion::plan(ids(), true), Deletion::DryRun(2)));
+
+        let Deletion::Delete(requests) = Deletion::plan(ids(), false) else {
+            panic!("subscriptions are not deleted without a dry run");
+        };
+        let ids: Vec<_> = requests
+            .iter()
+            .map(|req| req.id.access_secret_value())
+            .collect();
+        assert_eq!(ids, ["a", "b"]);
+    }
+
+    #[test]
+    fn test_sound_parses_the_event() {
+        let cli = Cli::try_parse_from(["twitch-chat", "test-sound", "first_message"]).unwrap();
+        assert!(matches!(
+            cli.cmd,
+            Cmd::TestSound(cmd::TestSound {
+                event: config::Event::FirstMessage,
+                ..
+            })
+        ));
+        assert!(Cli::try_parse_from(["twitch-chat", "test-sound", "ding"]).is_err());
+    }
+
+    #[test]
+    fn panic_hook_restores_the_terminal() {
+        thread_local! {
+            static RESTORES: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+        }
+
+        // the hook runs on the panicking thread, before the panic unwinds
+        restore_on_panic(|| RESTORES.set(RESTORES.get() + 1));
+        assert!(panic::catch_unwind(|| panic!("draw failed")).is_err());
+        assert_eq!(RESTORES.get(), 1);
+
+        // uninstall the hook
+        drop(panic::take_hook());
+    }
+}
@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+
+use crate::config::{Event, NotificationConfig};
+
+/// Desktop notifications for the configured events
+pub struct Notifier {
+    notifications: HashMap<Event, Vec<NotificationConfig>>,
+    display: Box<dyn Display>,
+}
+
+/// Shows a rendered notification, tests replace the desktop
+trait Display {
+    fn show(&self, title: &str, body: &str);
+}
+
+impl Notifier {
+    pub fn new(notifications: Vec<NotificationConfig>) -> Self {
+        if !notifications.is_empty() && !cfg!(feature = "desktop-notifications") {
+            eprintln!("built without desktop notification support, notifications are ignored");
+        }
+
+        Self::with_display(notifications, Desktop)
+    }
+
+    fn with_display(
+        notifications: Vec<NotificationConfig>,
+        display: impl Display + 'static,
+    ) -> Self {
+        let mut this = Self {
+            notifications: HashMap::new(),
+            display: Box::new(display),
+        };
+        for notification in notifications {
+            this.notifications
+                .entry(notification.event)
+                .or_default()
+                .push(notification);
+        }
+        this
+    }
+
+    pub fn has_notification_for_event(&self, event: Event) -> bool {
+        self.notifications
+            .get(&event)
+            .is_some_and(|notifications| !notifications.is_empty())
+    }
+
+    /// Show the notifications for the event, `{user}` and `{text}` in the templates are replaced
+    pub fn notify(&self, event: Event, user: &str, text: &str) {
+        let render = |template: &str| template.replace("{user}", user).replace("{text}", text);
+        for notification in self.notifications.get(&event).into_iter().flatten() {
+            self.display
+                .show(&render(&notification.title), &render(&notification.body));
+        }
+    }
+}
+
+struct Desktop;
+
+#[cfg(feature = "desktop-notifications")]
+impl Display for Desktop {
+    fn show(&self, title: &str, body: &str) {
+        // fails if no notification daemon is running, e.g. on a headless machine
+        if let Err(err) = notify_rust::Notification::new()
+            .appname("twitch-chat")
+            .summary(title)
+            .body(body)
+            .show()
+        {
+            eprintln!("failed to show desktop notification: {err}");
+        }
+    }
+}
+
+#[cfg(not(feature = "desktop-notifications"))]
+impl Display for Desktop {
+    fn show(&self, _title: &str, _body: &str) {}
+}
+
+/// Notifications shown by a test notifier as `(title, body)`
+#[cfg(test)]
+pub(crate) type Shown = std::rc::Rc<std::cell::RefCell<Vec<(String, String)>>>;
+
+#[cfg(test)]
+impl Notifier {
+    /// Notifier that records the notifications instead of showing them
+    pub(crate) fn test(notifications: Vec<NotificationConfig>) -> (Self, Shown) {
+        struct Recorder(Shown);
+
+        impl Display for Recorder {
+            fn show(&self, title: &str, body: &str) {
+                self.0.borrow_mut().push((title.into(), body.into()));
+            }
+        }
+
+        let shown = Shown::default();
+        let this = Self::with_display(notifications, Recorder(shown.clone()));
+        (this, shown)
+    }
+}
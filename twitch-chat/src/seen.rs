@@ -0,0 +1,105 @@
+use std::{
+    collections::HashSet,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::Path,
+};
+
+use anyhow::{Context, Result};
+
+use crate::config::SeenChattersScope;
+
+/// User ids of the chatters that have sent a message, to detect first time chatters
+pub struct SeenChatters {
+    ids: HashSet<String>,
+
+    /// Newly seen ids are appended, if the scope is all-time
+    file: Option<File>,
+}
+
+impl SeenChatters {
+    const FILE_NAME: &str = "seen-chatters.txt";
+
+    pub fn open(scope: SeenChattersScope, store_directory: &Path) -> Result<Self> {
+        let mut this = Self {
+            ids: HashSet::new(),
+            file: None,
+        };
+        if scope == SeenChattersScope::Session {
+            return Ok(this);
+        }
+
+        let path = store_directory.join(Self::FILE_NAME);
+        match fs::read_to_string(&path) {
+            Ok(ids) => this.ids.extend(ids.lines().map(String::from)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err).context("read seen chatters"),
+        }
+        this.file = Some(
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .context("open seen chatters")?,
+        );
+        Ok(this)
+    }
+
+    /// Remember the chatter, returns `true` if it was not seen before
+    pub fn insert(&mut self, user_id: &str) -> Result<bool> {
+        if !self.ids.insert(user_id.into()) {
+            return Ok(false);
+        }
+        if let Some(file) = &mut self.file {
+            writeln!(file, "{user_id}").context("write seen chatter")?;
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn directory(name: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "twitch-chat-seen-test-{name}-{}",
+            std::process::id(),
+        ));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        path
+    }
+
+    #[test]
+    fn persisted_chatter_is_not_new() {
+        let path = directory("all-time");
+
+        let mut seen = SeenChatters::open(SeenChattersScope::AllTime, &path).unwrap();
+        assert!(seen.insert("4242").unwrap());
+        assert!(!seen.insert("4242").unwrap());
+        drop(seen);
+
+        let mut seen = SeenChatters::open(SeenChattersScope::AllTime, &path).unwrap();
+        assert!(!seen.insert("4242").unwrap());
+        assert!(seen.insert("5151").unwrap());
+
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn session_chatters_are_forgotten() {
+        let path = directory("session");
+
+        let mut seen = SeenChatters::open(SeenChattersScope::Session, &path).unwrap();
+        assert!(seen.insert("4242").unwrap());
+        assert!(!seen.insert("4242").unwrap());
+        drop(seen);
+
+        let mut seen = SeenChatters::open(SeenChattersScope::Session, &path).unwrap();
+        assert!(seen.insert("4242").unwrap());
+        assert!(!path.join(SeenChatters::FILE_NAME).exists());
+
+        fs::remove_dir_all(&path).unwrap();
+    }
+}
@@ -0,0 +1,889 @@
+use std::{collections::HashMap, fmt::Write, future::Future, iter, pin::pin, time::Duration};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, TimeDelta, Utc};
+use futures::{StreamExt, TryStreamExt, future};
+use twitch_api::{
+    channel::ChannelsRequest,
+    chat::{SendChatMessageRequest, SendShoutoutRequest},
+    client::AuthenticatedClient,
+    events::{
+        channel_points::ChannelPointsRedemptionAdd,
+        chat::{ChatMessageFragment, message::ChatMessage, notification::ChatNotification},
+        cheer::Cheer,
+        follow::Follow,
+        raid::Raid,
+        stream::{StreamOffline, StreamOnline},
+        ws::{Incoming, KeepaliveTimeout, NotificationMessage, WebSocket},
+    },
+    follower::ChannelFollowersRequest,
+    poll::{GetPollsRequest, PollStatus},
+    prediction::{GetPredictionsRequest, PredictionStatus},
+    stream::StreamsRequest,
+    user::User,
+};
+
+use crate::{
+    away::Away,
+    combo::Combo,
+    config::Event as SoundEvent,
+    emit::Emitter,
+    notifier::Notifier,
+    seen::SeenChatters,
+    shoutout::Shoutouts,
+    sound_system::SoundSystem,
+    store::{Event, Extra, Store},
+    twitch::Subscriptions,
+};
+
+const SOUND_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Number of the newest polls and predictions searched for a running one, the first page
+const RUNNING_LOOKAHEAD: usize = 20;
+
+/// Account that sends messages and moderates, if it differs from the account that reads chat
+pub struct Sender {
+    pub client: AuthenticatedClient,
+    pub user: User,
+}
+
+/// Handles incoming notifications independent of the terminal ui
+pub struct Session<'a> {
+    /// Reads chat and owns the channel, the subscriptions are created with it
+    pub client: &'a mut AuthenticatedClient,
+    pub user: User,
+
+    /// Sends messages and moderates instead of `client`, see [`Session::sender`]
+    pub sender: Option<Sender>,
+    pub store: Store,
+    pub sound_system: SoundSystem,
+    pub emitter: Emitter,
+    pub notifier: Notifier,
+    pub shoutouts: Shoutouts,
+    pub combo: Combo,
+    pub away: Away,
+    pub seen_chatters: SeenChatters,
+    pub subscriptions: Subscriptions,
+    pub poll: Option<Poll>,
+    pub status: Status,
+}
+
+/// Signals shown in the status bar
+#[derive(Default)]
+pub struct Status {
+    pub connection: Connection,
+
+    /// Time the last message was received over the web socket, including keepalive messages
+    pub last_message: Option<DateTime<Utc>>,
+
+    /// Time of the last notification received over the web socket
+    pub last_notification: Option<DateTime<Utc>>,
+
+    /// Viewer count when the stream went online, `None` while offline
+    pub viewer_count: Option<u32>,
+
+    pub follower_total: Option<usize>,
+
+    /// Start of the current stream, `None` while offline
+    pub started_at: Option<DateTime<Utc>>,
+
+    /// Title of the poll that was active at startup
+    pub active_poll: Option<String>,
+
+    /// Title of the prediction that was active or locked at startup
+    pub active_prediction: Option<String>,
+}
+
+/// State of the web socket connection
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Connection {
+    #[default]
+    Connecting,
+
+    /// Twitch sends at least one message per `keepalive` interval
+    Connected { keepalive: Duration },
+
+    /// The connection was lost, a new web socket is connected
+    Reconnecting,
+}
+
+impl Status {
+    /// The connection is silent if no message was received within the keepalive interval
+    pub fn silent_for(&self, now: DateTime<Utc>) -> Option<TimeDelta> {
+        let Connection::Connected { keepalive } = self.connection else {
+            return None;
+        };
+        let silent_for = now - self.last_message?;
+        (silent_for.to_std().ok()? > keepalive).then_some(silent_for)
+    }
+
+    /// Time since the stream went online
+    pub fn uptime(&self) -> Option<TimeDelta> {
+        self.started_at.map(|started_at| Utc::now() - started_at)
+    }
+}
+
+impl<'a> Session<'a> {
+    #[expect(clippy::too_many_arguments)]
+    pub fn new(
+        client: &'a mut AuthenticatedClient,
+        user: User,
+        sender: Option<Sender>,
+        store: Store,
+        sound_system: SoundSystem,
+        emitter: Emitter,
+        notifier: Notifier,
+        shoutouts: Shoutouts,
+        combo: Combo,
+        away: Away,
+        seen_chatters: SeenChatters,
+        subscriptions: Subscriptions,
+    ) -> Self {
+        Self {
+            client,
+            user,
+            sender,
+            store,
+            sound_system,
+            emitter,
+            notifier,
+            shoutouts,
+            combo,
+            away,
+            seen_chatters,
+            subscriptions,
+            poll: None,
+            status: Status::default(),
+        }
+    }
+
+    pub fn start(&mut self) -> Result<()> {
+        self.store.push(Event::Started {
+            started_at: Utc::now(),
+        })
+    }
+
+    /// Seed the online state at startup, a live stream is stored as a summary event
+    pub async fn update_stream(&mut self) -> Result<()> {
+        let stream = self
+            .client
+            .send(&StreamsRequest::user_id(self.user.id.clone()))
+            .await
+            .context("load stream info")?
+            .into_stream();
+        match stream {
+            Some(stream) => {
+                self.status.viewer_count = Some(stream.viewer_count);
+                self.status.started_at = Some(stream.started_at);
+                self.store.push(Event::Live {
+                    timestamp: Utc::now(),
+                    stream,
+                })
+            }
+            None => {
+                self.status.viewer_count = None;
+                self.status.started_at = None;
+                Ok(())
+            }
+        }
+    }
+
+    /// Load the poll and prediction that are running, they may have been created elsewhere
+    pub async fn update_polls(&mut self) -> Result<()> {
+        // the newest are listed first, a running one is on the first page
+        let poll = {
+            let mut polls = pin!(
+                self.client
+                    .paginate(GetPollsRequest::broadcaster_id(self.user.id.clone()))
+                    .take(RUNNING_LOOKAHEAD)
+                    .try_filter(|poll| future::ready(poll.status == PollStatus::Active))
+            );
+            polls.try_next().await.context("load polls")?
+        };
+        self.status.active_poll = poll.map(|poll| poll.title);
+
+        let prediction = {
+            let mut predictions = pin!(
+                self.client
+                    .paginate(GetPredictionsRequest::broadcaster_id(self.user.id.clone()))
+                    .take(RUNNING_LOOKAHEAD)
+                    .try_filter(|prediction| {
+                        future::ready(matches!(
+                            prediction.status,
+                            PredictionStatus::Active | PredictionStatus::Locked
+                        ))
+                    })
+            );
+            predictions.try_next().await.context("load predictions")?
+        };
+        self.status.active_prediction = prediction.map(|prediction| match prediction.status {
+            PredictionStatus::Locked => format!("{} (locked)", prediction.title),
+            _ => prediction.title,
+        });
+        Ok(())
+    }
+
+    pub async fn update_follower_total(&mut self) -> Result<()> {
+        let followers = self
+            .client
+            .send(&ChannelFollowersRequest::total_only(self.user.id.clone()))
+            .await
+            .context("load follower total")?;
+        self.status.follower_total = Some(followers.total);
+        Ok(())
+    }
+
+    /// Resolves when a queued shoutout or the end of a combo is due
+    pub fn due(&self) -> impl Future<Output = ()> + 'static {
+        let shoutouts = self.shoutouts.due();
+        let combo = self.combo.due();
+        async move {
+            future::select(pin!(shoutouts), pin!(combo)).await;
+        }
+    }
+
+    /// Store the ended combo and send the due shoutouts
+    pub async fn handle_due(&mut self) -> Result<()> {
+        if self.combo.is_due() {
+            self.flush_combo()?;
+        }
+        self.send_due_shoutouts().await
+    }
+
+    /// Store the events of the combo, a single event is stored as is
+    fn flush_combo(&mut self) -> Result<()> {
+        let mut events = self.combo.take();
+        let event = match events.len() {
+            0 => return Ok(()),
+            1 => {
+                let (timestamp, event) = events.pop().unwrap();
+                Event::Notification {
+                    timestamp,
+                    event,
+                    extra: Extra::None,
+                    parsed: Default::default(),
+                }
+            }
+            _ => Event::Combo {
+                timestamp: events[0].0,
+                events: events.into_iter().map(|(_, event)| event).collect(),
+            },
+        };
+        self.emitter.emit(&event);
+        self.store.push(event)
+    }
+
+    /// Client and account that send messages and moderate
+    pub fn sender(&mut self) -> (&mut AuthenticatedClient, &User) {
+        match &mut self.sender {
+            Some(sender) => (&mut sender.client, &sender.user),
+            None => (self.client, &self.user),
+        }
+    }
+
+    /// Send the queued shoutouts that are due
+    async fn send_due_shoutouts(&mut self) -> Result<()> {
+        while let Some(shoutout) = self.shoutouts.pop_due() {
+            let from_broadcaster_id = self.user.id.clone();
+            let (client, moderator) = self.sender();
+            client
+                .send(&SendShoutoutRequest {
+                    from_broadcaster_id,
+                    to_broadcaster_id: shoutout.user_id,
+                    moderator_id: moderator.id.clone(),
+                })
+                .await
+                .with_context(|| format!("send shoutout to {}", shoutout.user_name))?;
+        }
+        Ok(())
+    }
+
+    /// Wait for the playing sounds to finish, the subscriptions are returned for cleanup
+    pub fn shutdown(mut self) -> Subscriptions {
+        if let Err(err) = self.flush_combo() {
+            eprintln!("failed to store combo: {err:?}");
+        }
+        if let Err(err) = self.sound_system.shutdown(SOUND_SHUTDOWN_TIMEOUT) {
+            eprintln!("failed to shutdown sound system: {err:?}");
+        }
+        self.subscriptions
+    }
+
+    /// Replace the lost web socket connection, see [`connection_lost`]
+    pub async fn reconnect(&mut self) -> Result<WebSocket> {
+        self.status.connection = Connection::Reconnecting;
+        let ws = self
+            .subscriptions
+            .reconnect(self.client, &self.user)
+            .await?;
+        self.connected(&ws);
+        Ok(ws)
+    }
+
+    /// Show the web socket as connected in the status bar
+    pub fn connected(&mut self, ws: &WebSocket) {
+        self.status.connection = Connection::Connected {
+            keepalive: ws.keepalive_interval(),
+        };
+        self.status.last_message = Some(Utc::now());
+    }
+
+    pub async fn handle_incoming(
+        &mut self,
+        timestamp: DateTime<Utc>,
+        incoming: Incoming,
+    ) -> Result<()> {
+        self.status.last_message = Some(Utc::now());
+        match incoming {
+            Incoming::Notification(notification) => self.handle(timestamp, notification).await,
+            Incoming::Revocation(revocation) => {
+                self.subscriptions
+                    .handle_revocation(self.client, &self.user, revocation)
+                    .await
+            }
+            // reported by the frontends
+            Incoming::Reconnected | Incoming::Keepalive => Ok(()),
+            // transient close reasons are handled by reconnecting, see `connection_lost`
+            Incoming::Closed(reason) => {
+                Err(anyhow::Error::new(reason).context("web socket closed by twitch"))
+            }
+        }
+    }
+
+    async fn handle(
+        &mut self,
+        timestamp: DateTime<Utc>,
+        notification: NotificationMessage,
+    ) -> Result<()> {
+        self.status.last_notification = Some(timestamp);
+        metrics::counter!(
+            "twitch_chat_notifications_total",
+            "type" => notification.subscription_type().to_string(),
+        )
+        .increment(1);
+
+        let mut away_reply = None;
+        let extra = if let Some(message) = notification.event::<ChatMessage>()? {
+            // first messages without a configured sound keep the message sound
+            let first_message = message.chatter_user_id != self.user.id
+                && self.seen_chatters.insert(&message.chatter_user_id)?;
+            self.sound_system.play_sound_for_event(
+                if first_message
+                    && self
+                        .sound_system
+                        .has_sound_for_event(SoundEvent::FirstMessage)
+                {
+                    SoundEvent::FirstMessage
+                } else {
+                    SoundEvent::Message
+                },
+            );
+            self.notifier.notify(
+                if first_message
+                    && self
+                        .notifier
+                        .has_notification_for_event(SoundEvent::FirstMessage)
+                {
+                    SoundEvent::FirstMessage
+                } else {
+                    SoundEvent::Message
+                },
+                &message.chatter_user_name,
+                &message.message.text,
+            );
+
+            if let Some(poll) = &mut self.poll {
+                poll.vote(&message.chatter_user_id, &message.message.text);
+            }
+
+            if self.away.period().is_some() && !self.is_own_message(&message) {
+                let addressed = addresses(&message, &self.user.id);
+                away_reply = self
+                    .away
+                    .message(&message.chatter_user_id, addressed)
+                    .map(|reply| (message.message_id.clone(), reply));
+                Extra::Away
+            } else {
+                Extra::None
+            }
+        } else if notification.event::<ChatNotification>()?.is_some() {
+            // the raid sound and shoutout are handled for the `channel.raid` event
+            self.sound_system.play_sound_for_event(SoundEvent::Message);
+            Extra::None
+        } else if let Some(raid) = notification.event::<Raid>()? {
+            // raids without a configured sound keep the message sound
+            self.sound_system.play_sound_for_event(
+                if self.sound_system.has_sound_for_event(SoundEvent::Raid) {
+                    SoundEvent::Raid
+                } else {
+                    SoundEvent::Message
+                },
+            );
+            self.notifier.notify(
+                SoundEvent::Raid,
+                &raid.from_broadcaster_user_name,
+                &format!("is raiding with {} viewers", raid.viewers),
+            );
+            self.shoutouts.raid(&raid);
+            Extra::None
+        } else if let Some(follow) = notification.event::<Follow>()? {
+            // only the first follow of a combo is announced
+            if !self.combo.is_active() {
+                self.sound_system.play_sound_for_event(SoundEvent::Follow);
+                self.notifier
+                    .notify(SoundEvent::Follow, &follow.user_name, "has followed you");
+            }
+            if let Some(total) = &mut self.status.follower_total {
+                *total += 1;
+            }
+            if self.combo.is_enabled() {
+                self.combo.push(timestamp, notification.into_event());
+                return Ok(());
+            }
+            Extra::None
+        } else if let Some(cheer) = notification.event::<Cheer>()? {
+            self.sound_system.play_sound_for_event(SoundEvent::Cheer);
+            self.notifier.notify(
+                SoundEvent::Cheer,
+                cheer.user_name(),
+                &format!("cheered {} bits", cheer.bits),
+            );
+            Extra::None
+        } else if let Some(redemption) = notification.event::<ChannelPointsRedemptionAdd>()? {
+            self.sound_system
+                .play_sound_for_event(SoundEvent::Redemption);
+            self.notifier.notify(
+                SoundEvent::Redemption,
+                &redemption.user_name,
+                &format!("redeemed {}", redemption.reward.title),
+            );
+            Extra::None
+        } else if let Some(online) = notification.event::<StreamOnline>()? {
+            self.sound_system.play_sound_for_event(SoundEvent::Online);
+
+            let stream = self
+                .client
+                .send(&StreamsRequest::user_id(online.broadcaster_user_id))
+                .await
+                .context("load stream info")?
+                .into_stream()
+                .context("missing stream")?;
+            self.status.viewer_count = Some(stream.viewer_count);
+            self.status.started_at = Some(online.started_at);
+            self.notifier
+                .notify(SoundEvent::Online, &stream.user_name, &stream.title);
+
+            Extra::Stream(stream)
+        } else if let Some(offline) = notification.event::<StreamOffline>()? {
+            self.sound_system.play_sound_for_event(SoundEvent::Offline);
+            self.status.viewer_count = None;
+            self.status.started_at = None;
+
+            let channel = self
+                .client
+                .send(&ChannelsRequest::id(offline.broadcaster_user_id))
+                .await
+                .context("load channel info")?
+                .into_channel()
+                .context("missing channel")?;
+            self.notifier.notify(
+                SoundEvent::Offline,
+                &channel.broadcaster_name,
+                &channel.title,
+            );
+
+            Extra::Channel(channel)
+        } else {
+            Extra::None
+        };
+        let event = Event::Notification {
+            timestamp,
+            event: notification.into_event(),
+            extra,
+            parsed: Default::default(),
+        };
+        self.emitter.emit(&event);
+        self.store.push(event)?;
+
+        // replied after storing, such that a failed reply does not lose the message
+        if let Some((message_id, reply)) = away_reply {
+            self.send_away_reply(message_id, reply).await?;
+        }
+        Ok(())
+    }
+
+    /// Whether the message was sent by the broadcaster or the send profile, e.g. an away reply
+    fn is_own_message(&self, message: &ChatMessage) -> bool {
+        message.chatter_user_id == self.user.id
+            || self
+                .sender
+                .as_ref()
+                .is_some_and(|sender| sender.user.id == message.chatter_user_id)
+    }
+
+    async fn send_away_reply(&mut self, message_id: String, reply: String) -> Result<()> {
+        let broadcaster_id = self.user.id.clone();
+        let (client, sender) = self.sender();
+        client
+            .send(&SendChatMessageRequest {
+                broadcaster_id,
+                sender_id: sender.id.clone(),
+                message: reply,
+                reply_parent_message_id: Some(message_id),
+            })
+            .await
+            .context("send away reply")?;
+        Ok(())
+    }
+}
+
+/// Whether the message mentions the user or replies to one of the user's messages
+fn addresses(message: &ChatMessage, user_id: &str) -> bool {
+    message
+        .reply
+        .as_ref()
+        .is_some_and(|reply| reply.parent_user_id == user_id)
+        || message.message.fragments.iter().any(|fragment| {
+            matches!(
+                fragment,
+                ChatMessageFragment::Mention { mention, .. } if mention.user_id == user_id
+            )
+        })
+}
+
+/// Whether the web socket connection is dead and has to be replaced with [`Session::reconnect`]
+pub fn connection_lost(next: &Result<Option<(DateTime<Utc>, Incoming)>>) -> bool {
+    match next {
+        Ok(Some((_, Incoming::Closed(reason)))) => reason.is_transient(),
+        Ok(_) => false,
+        // e.g. the network dropped while the machine was asleep
+        Err(err) => err.is::<KeepaliveTimeout>(),
+    }
+}
+
+pub struct Poll {
+    options: Vec<String>,
+    votes: HashMap<String, usize>,
+}
+
+impl Poll {
+    pub fn new(options: Vec<String>) -> Self {
+        Self {
+            options,
+            votes: HashMap::new(),
+        }
+    }
+
+    fn vote(&mut self, user_id: &str, text: &str) {
+        let Ok(n) = text.split(' ').next().unwrap().parse() else {
+            return;
+        };
+        self.votes.insert(user_id.into(), n);
+    }
+
+    pub fn result(self) -> String {
+        let mut votes = vec![0; self.options.len()];
+        for vote in self.votes.into_values() {
+            votes[vote] += 1;
+        }
+        let max = votes.iter().copied().max().unwrap_or(0);
+        if max == 0 {
+            "Ergebnis: Keine Stimmen".into()
+        } else {
+            let mut message = format!("Ergebnis[{max}]:");
+            let mut first = true;
+            for (option, votes) in iter::zip(self.options, votes) {
+                if votes == max {
+                    if first {
+                        first = false;
+                    } else {
+                        message.push_str(" -");
+                    }
+                    write!(message, " {option}").unwrap();
+                }
+            }
+            message
+        }
+    }
+}
+
+/// Client with made up tokens, requests fail
+#[cfg(test)]
+pub(crate) fn test_client() -> AuthenticatedClient {
+    use twitch_api::{auth::TokenManager, client::Client, config::TokenConfig, secret::Secret};
+
+    Client::new().authenticated(TokenManager::with_config(
+        Secret::new("client id"),
+        TokenConfig {
+            access_token: Secret::new("access token"),
+            refresh_token: Secret::new("refresh token"),
+            expires_at: None,
+        },
+    ))
+}
+
+#[cfg(test)]
+impl<'a> Session<'a> {
+    /// Session of the user `1337` with a temporary store, without sounds and subscriptions
+    pub(crate) fn test(client: &'a mut AuthenticatedClient) -> Self {
+        use crate::config::{AwayConfig, SeenChattersScope, StoreConfig};
+
+        let user = serde_json::from_value(serde_json::json!({
+            "id": "1337",
+            "login": "cool_user",
+            "display_name": "Cool_User",
+            "type": "",
+            "broadcaster_type": "",
+            "description": "",
+            "profile_image_url": "",
+            "offline_image_url": "",
+            "view_count": 0,
+            "created_at": "2016-12-14T20:32:28Z",
+        }))
+        .unwrap();
+        let store = Store::test(StoreConfig::default());
+        let seen_chatters =
+            SeenChatters::open(SeenChattersScope::Session, store.directory()).unwrap();
+        Self::new(
+            client,
+            user,
+            None,
+            store,
+            SoundSystem::init(HashMap::new(), Vec::new(), None).unwrap(),
+            Emitter::default(),
+            Notifier::new(Vec::new()),
+            Shoutouts::new(None),
+            Combo::new(None),
+            Away::new(AwayConfig {
+                reply: None,
+                reply_cooldown_secs: 600,
+            }),
+            seen_chatters,
+            Subscriptions::test(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use twitch_api::events::{types::Subscription, ws::CloseReason};
+
+    use super::*;
+    use crate::{
+        config::{ComboConfig, NotificationConfig},
+        store::combo_follows,
+    };
+
+    #[test]
+    fn reconnect_after_keepalive_timeout() {
+        let timeout = Err(KeepaliveTimeout(Duration::from_secs(15)).into());
+        assert!(connection_lost(&timeout));
+
+        let other = Err(anyhow::anyhow!("parse websocket message"));
+        assert!(!connection_lost(&other));
+        assert!(!connection_lost(&Ok(None)));
+        assert!(!connection_lost(&Ok(Some((
+            Utc::now(),
+            Incoming::Reconnected
+        )))));
+    }
+
+    #[test]
+    fn reconnect_after_transient_close() {
+        let closed = |reason| Ok(Some((Utc::now(), Incoming::Closed(reason))));
+        assert!(connection_lost(&closed(CloseReason::NetworkTimeout)));
+        assert!(connection_lost(&closed(CloseReason::InternalServerError)));
+        assert!(!connection_lost(&closed(CloseReason::ConnectionUnused)));
+        assert!(!connection_lost(&closed(CloseReason::ClientFailedPingPong)));
+    }
+
+    fn notification<T: Subscription>(event: serde_json::Value) -> Incoming {
+        let notification = serde_json::from_value(serde_json::json!({
+            "subscription": {
+                "id": "f1c2a387-161a-49f9-a165-0f21d7a4e1c4",
+                "status": "enabled",
+                "type": T::TYPE,
+                "version": T::VERSION,
+                "cost": 0,
+                "condition": {},
+                "transport": { "method": "websocket", "session_id": "session" },
+                "created_at": "2025-01-31T19:00:00Z",
+            },
+            "event": event,
+        }))
+        .unwrap();
+        Incoming::Notification(notification)
+    }
+
+    #[test]
+    fn daemon_stores_notifications_and_plays_sounds() {
+        let mut client = test_client();
+        let mut session = Session::test(&mut client);
+
+        let timestamp: DateTime<Utc> = "2025-01-31T20:00:00Z".parse().unwrap();
+        let message = notification::<ChatMessage>(serde_json::json!({
+                "broadcaster_user_id": "1337",
+                "broadcaster_user_name": "Cool_User",
+                "broadcaster_user_login": "cool_user",
+                "chatter_user_id": "4242",
+                "chatter_user_name": "Viewer",
+                "chatter_user_login": "viewer",
+                "message_id": "cc106a89-1814-919d-454c-f4f2f970aae7",
+                "message": { "text": "hello", "fragments": [] },
+                "message_type": "text",
+                "badges": [],
+                "color": "",
+        }));
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(session.handle_incoming(timestamp, message))
+            .unwrap();
+
+        assert_eq!(session.status.last_notification, Some(timestamp));
+        assert_eq!(session.sound_system.played, [SoundEvent::Message]);
+        let events: Vec<_> = session.store.events(&mut None).collect();
+        assert!(
+            matches!(
+                events.as_slice(),
+                [Event::Notification { timestamp: stored, .. }] if *stored == timestamp
+            ),
+            "{events:?}",
+        );
+    }
+
+    #[test]
+    fn raid_shows_the_configured_notification() {
+        let mut client = test_client();
+        let mut session = Session::test(&mut client);
+        let (notifier, shown) = Notifier::test(vec![NotificationConfig {
+            event: SoundEvent::Raid,
+            title: "{user} is raiding".into(),
+            body: "{text}".into(),
+        }]);
+        session.notifier = notifier;
+
+        let raid = notification::<Raid>(serde_json::json!({
+                "from_broadcaster_user_id": "4242",
+                "from_broadcaster_user_login": "raider",
+                "from_broadcaster_user_name": "Raider",
+                "to_broadcaster_user_id": "1337",
+                "to_broadcaster_user_login": "cool_user",
+                "to_broadcaster_user_name": "Cool_User",
+                "viewers": 42,
+        }));
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(session.handle_incoming(Utc::now(), raid))
+            .unwrap();
+
+        assert_eq!(
+            *shown.borrow(),
+            [(
+                "Raider is raiding".to_string(),
+                "is raiding with 42 viewers".to_string(),
+            )]
+        );
+    }
+
+    #[test]
+    fn quick_follows_are_stored_as_one_combo() {
+        let mut client = test_client();
+        let mut session = Session::test(&mut client);
+        // the combo is due right away, but only ends when the due events are handled
+        session.combo = Combo::new(Some(ComboConfig { window_secs: 0 }));
+
+        let timestamp: DateTime<Utc> = "2025-01-31T20:00:00Z".parse().unwrap();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        for (i, user) in ["Alice", "Bob", "Carol"].into_iter().enumerate() {
+            let follow = notification::<Follow>(serde_json::json!({
+                "user_id": format!("{i}"),
+                "user_login": user.to_lowercase(),
+                "user_name": user,
+                "broadcaster_user_id": "1337",
+                "broadcaster_user_login": "cool_user",
+                "broadcaster_user_name": "Cool_User",
+                "followed_at": "2025-01-31T20:00:00Z",
+            }));
+            runtime
+                .block_on(session.handle_incoming(timestamp + TimeDelta::seconds(i as i64), follow))
+                .unwrap();
+        }
+        assert_eq!(session.sound_system.played, [SoundEvent::Follow]);
+        assert_eq!(session.store.events(&mut None).count(), 0);
+
+        runtime.block_on(session.handle_due()).unwrap();
+        let events: Vec<_> = session.store.events(&mut None).collect();
+        let [
+            Event::Combo {
+                timestamp: stored,
+                events,
+            },
+        ] = events.as_slice()
+        else {
+            panic!("expected one combo: {events:?}");
+        };
+        assert_eq!(*stored, timestamp);
+        assert_eq!(combo_follows(events), ["Alice", "Bob", "Carol"]);
+    }
+
+    #[test]
+    fn notifications_are_counted_by_type() {
+        let mut client = test_client();
+        let mut session = Session::test(&mut client);
+        let recorder = metrics_exporter_prometheus::PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+
+        let message = |text: &str| {
+            notification::<ChatMessage>(serde_json::json!({
+                "broadcaster_user_id": "1337",
+                "broadcaster_user_name": "Cool_User",
+                "broadcaster_user_login": "cool_user",
+                "chatter_user_id": "4242",
+                "chatter_user_name": "Viewer",
+                "chatter_user_login": "viewer",
+                "message_id": "cc106a89-1814-919d-454c-f4f2f970aae7",
+                "message": { "text": text, "fragments": [] },
+                "message_type": "text",
+                "badges": [],
+                "color": "",
+            }))
+        };
+        let follow = notification::<Follow>(serde_json::json!({
+            "user_id": "4242",
+            "user_login": "viewer",
+            "user_name": "Viewer",
+            "broadcaster_user_id": "1337",
+            "broadcaster_user_login": "cool_user",
+            "broadcaster_user_name": "Cool_User",
+            "followed_at": "2025-01-31T20:00:00Z",
+        }));
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        metrics::with_local_recorder(&recorder, || {
+            for incoming in [message("hello"), message("hi"), follow] {
+                runtime
+                    .block_on(session.handle_incoming(Utc::now(), incoming))
+                    .unwrap();
+            }
+        });
+
+        let rendered = handle.render();
+        assert!(
+            rendered.contains(r#"twitch_chat_notifications_total{type="channel.chat.message"} 2"#),
+            "{rendered}"
+        );
+        assert!(
+            rendered.contains(r#"twitch_chat_notifications_total{type="channel.follow"} 1"#),
+            "{rendered}"
+        );
+    }
+}
@@ -0,0 +1,162 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    future::{self, Future},
+    time::{Duration, Instant},
+};
+
+use twitch_api::events::raid::Raid;
+
+use crate::config::AutoShoutoutConfig;
+
+/// Raiders waiting for an automatic shoutout
+///
+/// Twitch allows one shoutout every two minutes and one shoutout of the same broadcaster every
+/// hour, due shoutouts are delayed until both cooldowns have passed.
+pub struct Shoutouts {
+    config: Option<AutoShoutoutConfig>,
+    queue: VecDeque<Shoutout>,
+    last_sent_at: Option<Instant>,
+    last_sent_to: HashMap<String, Instant>,
+}
+
+#[derive(Debug)]
+pub struct Shoutout {
+    pub user_id: String,
+    pub user_name: String,
+    due_at: Instant,
+}
+
+impl Shoutouts {
+    const COOLDOWN: Duration = Duration::from_secs(2 * 60);
+    const SAME_BROADCASTER_COOLDOWN: Duration = Duration::from_secs(60 * 60);
+
+    pub fn new(config: Option<AutoShoutoutConfig>) -> Self {
+        Self {
+            config,
+            queue: VecDeque::new(),
+            last_sent_at: None,
+            last_sent_to: HashMap::new(),
+        }
+    }
+
+    /// Queue a shoutout for the raider, returns `false` if the raid is ignored
+    pub fn raid(&mut self, raid: &Raid) -> bool {
+        let Some(config) = &self.config else {
+            return false;
+        };
+        let user_id = &raid.from_broadcaster_user_id;
+        if raid.viewers < config.min_viewers
+            || self
+                .queue
+                .iter()
+                .any(|shoutout| shoutout.user_id == *user_id)
+        {
+            return false;
+        }
+        self.queue.push_back(Shoutout {
+            user_id: user_id.clone(),
+            user_name: raid.from_broadcaster_user_name.clone(),
+            due_at: Instant::now() + config.delay(),
+        });
+        true
+    }
+
+    /// Time at which the next queued shoutout may be sent
+    fn next_at(&self) -> Option<Instant> {
+        let shoutout = self.queue.front()?;
+        let mut next_at = shoutout.due_at;
+        if let Some(last_sent_at) = self.last_sent_at {
+            next_at = next_at.max(last_sent_at + Self::COOLDOWN);
+        }
+        if let Some(last_sent_at) = self.last_sent_to.get(&shoutout.user_id) {
+            next_at = next_at.max(*last_sent_at + Self::SAME_BROADCASTER_COOLDOWN);
+        }
+        Some(next_at)
+    }
+
+    /// Remove the next shoutout if it may be sent now, it is recorded as sent
+    pub fn pop_due(&mut self) -> Option<Shoutout> {
+        let now = Instant::now();
+        if self.next_at()? > now {
+            return None;
+        }
+        let shoutout = self.queue.pop_front()?;
+        self.sent(&shoutout.user_id);
+        Some(shoutout)
+    }
+
+    /// Record a shoutout that was sent manually, such that queued shoutouts respect the cooldowns
+    pub fn sent(&mut self, user_id: &str) {
+        let now = Instant::now();
+        self.last_sent_at = Some(now);
+        self.last_sent_to.insert(user_id.into(), now);
+    }
+
+    /// Resolves when the next shoutout is due, never if the queue is empty
+    pub fn due(&self) -> impl Future<Output = ()> + 'static {
+        let next_at = self.next_at();
+        async move {
+            match next_at {
+                Some(next_at) => tokio::time::sleep_until(next_at.into()).await,
+                None => future::pending().await,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn raid(viewers: u32) -> Raid {
+        serde_json::from_value(serde_json::json!({
+            "from_broadcaster_user_id": "1234",
+            "from_broadcaster_user_login": "cool_user",
+            "from_broadcaster_user_name": "Cool_User",
+            "to_broadcaster_user_id": "1337",
+            "to_broadcaster_user_login": "cooler_user",
+            "to_broadcaster_user_name": "Cooler_User",
+            "viewers": viewers,
+        }))
+        .unwrap()
+    }
+
+    fn shoutouts() -> Shoutouts {
+        Shoutouts::new(Some(AutoShoutoutConfig {
+            delay_secs: 0,
+            min_viewers: 5,
+        }))
+    }
+
+    #[test]
+    fn raid_above_threshold_is_queued() {
+        let mut shoutouts = shoutouts();
+        assert!(shoutouts.raid(&raid(5)));
+        let shoutout = shoutouts.pop_due().unwrap();
+        assert_eq!(shoutout.user_id, "1234");
+        assert_eq!(shoutout.user_name, "Cool_User");
+    }
+
+    #[test]
+    fn raid_below_threshold_is_ignored() {
+        let mut shoutouts = shoutouts();
+        assert!(!shoutouts.raid(&raid(4)));
+        assert!(shoutouts.pop_due().is_none());
+    }
+
+    #[test]
+    fn raid_is_ignored_without_config() {
+        let mut shoutouts = Shoutouts::new(None);
+        assert!(!shoutouts.raid(&raid(100)));
+        assert!(shoutouts.pop_due().is_none());
+    }
+
+    #[test]
+    fn shoutouts_respect_cooldown() {
+        let mut shoutouts = shoutouts();
+        shoutouts.sent("5678");
+        assert!(shoutouts.raid(&raid(10)));
+        // another shoutout was sent within the last two minutes
+        assert!(shoutouts.pop_due().is_none());
+    }
+}
@@ -1,58 +1,138 @@
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant},
+};
 
 use anyhow::{Context, Result};
-use sound_fx_3000::{Output, Sound};
+use rand::{
+    Rng,
+    distr::{Distribution, weighted::WeightedIndex},
+};
+use sound_fx_3000::{Output, Sound, ThreadOptions};
 
 use crate::config::{Event, OutputConfig, SoundConfig};
 
 pub(crate) struct SoundSystem {
     pub(crate) outputs: HashMap<String, Output>,
-    pub(crate) sounds: HashMap<Event, Vec<(String, Sound)>>,
+    pub(crate) sounds: HashMap<Event, Vec<EventSound>>,
+
+    /// No sounds are played while muted, independent of the output mute states
+    muted: bool,
+
+    /// Events a sound was played for, tests run without sound outputs
+    #[cfg(test)]
+    pub(crate) played: Vec<Event>,
+
+    /// Warnings printed while loading the sounds
+    #[cfg(test)]
+    warnings: Vec<String>,
 }
 
+/// A configured sound, played on each of its outputs
+pub(crate) struct EventSound {
+    /// Sounds with a weight are picked randomly, sounds without are always played
+    weight: Option<f32>,
+    outputs: Vec<(String, Sound)>,
+}
+
+/// All sounds without a weight and one of the weighted sounds, picked by weight
+fn pick_sounds<'a>(
+    sounds: &'a [EventSound],
+    rng: &mut impl Rng,
+) -> impl Iterator<Item = &'a EventSound> {
+    let pool = sounds
+        .iter()
+        .filter(|sound| sound.weight.is_some())
+        .collect::<Vec<_>>();
+    let picked = WeightedIndex::new(pool.iter().filter_map(|sound| sound.weight))
+        .ok()
+        .map(|weights| pool[weights.sample(rng)]);
+
+    sounds
+        .iter()
+        .filter(|sound| sound.weight.is_none())
+        .chain(picked)
+}
+
+/// Output of the sounds without a configured output
+const DEFAULT_OUTPUT: &str = "default";
+
 impl SoundSystem {
     pub fn init(
         mut outputs: HashMap<String, OutputConfig>,
         sounds: Vec<SoundConfig>,
+        loudness_target: Option<f32>,
     ) -> Result<Self> {
-        let mut sample_rate = None;
-
-        let mut this = Self {
-            outputs: Default::default(),
-            sounds: Default::default(),
-        };
+        let mut this = Self::load(&mut outputs, sounds, loudness_target)?;
+        this.spawn_outputs(outputs)?;
+        Ok(this)
+    }
 
-        pub(crate) const DEFAULT_NAME: &str = "default";
-        if !outputs.contains_key(DEFAULT_NAME) {
-            outputs.insert(DEFAULT_NAME.into(), OutputConfig {
+    /// Decode the configured sounds without opening any output
+    fn load(
+        outputs: &mut HashMap<String, OutputConfig>,
+        sounds: Vec<SoundConfig>,
+        loudness_target: Option<f32>,
+    ) -> Result<Self> {
+        if !outputs.contains_key(DEFAULT_OUTPUT) {
+            outputs.insert(DEFAULT_OUTPUT.into(), OutputConfig {
                 device: None,
                 volume: None,
+                thread_name: None,
+                high_priority: false,
             });
         }
 
-        let mut used_outputs = HashSet::new();
+        let mut sample_rate = None;
+
+        let mut this = Self {
+            outputs: Default::default(),
+            sounds: Default::default(),
+            muted: false,
+            #[cfg(test)]
+            played: Vec::new(),
+            #[cfg(test)]
+            warnings: Vec::new(),
+        };
 
         for mut sound_config in sounds {
             let mut sound = Sound::open(&sound_config.sound)?;
             if let Some(volume) = sound_config.volume {
                 sound.set_volume(volume);
+            } else if let Some(loudness_target) = loudness_target {
+                sound.normalize(loudness_target);
             }
             if let Some(sample_rate) = sample_rate {
-                anyhow::ensure!(
-                    sample_rate == sound.spec().rate,
-                    "sample rate does not match: {} != {}",
-                    sample_rate,
-                    sound.spec().rate,
-                )
+                if sound.spec().rate != sample_rate {
+                    let warning = format!(
+                        "resampled sound {:?} from {} Hz to {sample_rate} Hz, \
+                        export it with {sample_rate} Hz for the best quality",
+                        sound_config.sound,
+                        sound.spec().rate,
+                    );
+                    eprintln!("{warning}");
+                    #[cfg(test)]
+                    this.warnings.push(warning);
+                    sound.resample(sample_rate);
+                }
             } else {
                 sample_rate = Some(sound.spec().rate);
             }
+            if let Some(weight) = sound_config.weight {
+                anyhow::ensure!(
+                    weight.is_finite() && weight > 0.0,
+                    "sound weight must be positive: {:?}",
+                    sound_config.sound,
+                );
+            }
             if sound_config.output.is_empty() {
-                sound_config.output.push(DEFAULT_NAME.into());
+                sound_config.output.push(DEFAULT_OUTPUT.into());
             }
+            let mut event_sound = EventSound {
+                weight: sound_config.weight,
+                outputs: Vec::new(),
+            };
             for output in sound_config.output {
-                used_outputs.insert(output.clone());
-
                 let mut sound = sound.clone();
                 if let Some(volume) = outputs
                     .get(&output)
@@ -61,34 +141,308 @@ impl SoundSystem {
                 {
                     sound.set_volume(volume);
                 }
-                this.sounds
-                    .entry(sound_config.event)
-                    .or_default()
-                    .push((output, sound));
+                event_sound.outputs.push((output, sound));
             }
+            this.sounds
+                .entry(sound_config.event)
+                .or_default()
+                .push(event_sound);
         }
 
-        if let Some(sample_rate) = sample_rate {
-            for (name, output_config) in outputs {
-                if !used_outputs.contains(&name) {
-                    continue;
-                }
-                let output = Output::spawn(sample_rate, output_config.device.as_deref())?;
-                this.outputs.insert(name, output);
+        Ok(this)
+    }
+
+    /// Open the outputs used by at least one sound
+    fn spawn_outputs(&mut self, outputs: HashMap<String, OutputConfig>) -> Result<()> {
+        let sounds = || {
+            self.sounds
+                .values()
+                .flatten()
+                .flat_map(|sound| &sound.outputs)
+        };
+        // all sounds are resampled to the same sample rate
+        let Some(sample_rate) = sounds().next().map(|(_, sound)| sound.spec().rate) else {
+            return Ok(());
+        };
+        let used_outputs: HashSet<_> = sounds().map(|(name, _)| name.clone()).collect();
+        for (name, output_config) in outputs {
+            if !used_outputs.contains(&name) {
+                continue;
             }
+            let mut options = ThreadOptions {
+                high_priority: output_config.high_priority,
+                ..Default::default()
+            };
+            if let Some(thread_name) = output_config.thread_name {
+                options.name = thread_name;
+            }
+            let output =
+                Output::spawn_with_options(sample_rate, output_config.device.as_deref(), options)?;
+            if let Some(err) = output.priority_error() {
+                eprintln!("output {name}: {err:#}, using the default priority");
+            }
+            self.outputs.insert(name, output);
         }
 
-        Ok(this)
+        Ok(())
+    }
+
+    pub(crate) fn has_sound_for_event(&self, event: Event) -> bool {
+        self.sounds
+            .get(&event)
+            .is_some_and(|sounds| !sounds.is_empty())
     }
 
     pub(crate) fn play_sound_for_event(&mut self, event: Event) {
-        for (output, sound) in self.sounds.get(&event).into_iter().flatten() {
+        if self.muted {
+            return;
+        }
+        #[cfg(test)]
+        self.played.push(event);
+        let Some(sounds) = self.sounds.get(&event) else {
+            return;
+        };
+
+        let mut rng = rand::rng();
+        let sounds = pick_sounds(sounds, &mut rng).flat_map(|sound| &sound.outputs);
+        for (output, sound) in sounds {
             let Some(output) = self.outputs.get(output) else {
                 continue;
             };
+            metrics::counter!("twitch_chat_sound_plays_total", "event" => format!("{event:?}"))
+                .increment(1);
             if let Err(err) = output.play(sound) {
                 eprintln!("failed to play sound for {event:?}: {err:?}");
             }
         }
     }
+
+    /// Toggle the global mute state and return whether all sounds are muted now
+    pub(crate) fn toggle_muted(&mut self) -> bool {
+        self.muted = !self.muted;
+        self.muted
+    }
+
+    pub(crate) fn is_muted(&self) -> bool {
+        self.muted
+    }
+
+    /// Toggle the mute state of an output and return whether it is muted now
+    pub(crate) fn toggle_mute(&self, name: &str) -> Result<bool> {
+        let output = self
+            .outputs
+            .get(name)
+            .with_context(|| format!("unknown sound output: {name:?}"))?;
+        let muted = !output.is_muted();
+        output.set_muted(muted);
+        Ok(muted)
+    }
+
+    /// Names of the muted outputs
+    pub(crate) fn muted_outputs(&self) -> impl Iterator<Item = &str> {
+        self.outputs
+            .iter()
+            .filter(|(_, output)| output.is_muted())
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Stop all outputs after the queued sounds are played, but wait at most `timeout`
+    pub(crate) fn shutdown(self, timeout: Duration) -> Result<()> {
+        let deadline = Instant::now() + timeout;
+        for (name, output) in self.outputs {
+            output
+                .shutdown_timeout(deadline.saturating_duration_since(Instant::now()))
+                .with_context(|| format!("shutdown sound output: {name:?}"))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use serde::Deserialize;
+
+    use super::*;
+
+    /// Load the `[output.*]` and `[[sound]]` tables of a config, sound paths are relative to the
+    /// sound-fx-3000 fixtures
+    fn load(config: &str) -> Result<SoundSystem> {
+        #[derive(Deserialize)]
+        struct Config {
+            #[serde(rename = "output", default)]
+            outputs: HashMap<String, OutputConfig>,
+            #[serde(rename = "sound")]
+            sounds: Vec<SoundConfig>,
+        }
+
+        let mut config: Config = toml::from_str(config).unwrap();
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("../sound-fx-3000/fixtures");
+        for sound in &mut config.sounds {
+            sound.sound = fixtures.join(&sound.sound);
+        }
+        SoundSystem::load(&mut config.outputs, config.sounds, None)
+    }
+
+    fn outputs(sound_system: &SoundSystem, event: Event) -> Vec<Vec<&str>> {
+        sound_system.sounds[&event]
+            .iter()
+            .map(|sound| {
+                sound
+                    .outputs
+                    .iter()
+                    .map(|(name, _)| name.as_str())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sounds_are_mapped_to_their_event() {
+        let sound_system = load(
+            r#"
+            [output.alerts]
+            volume = 0.5
+
+            [[sound]]
+            event = "follow"
+            sound = "sine.flac"
+
+            [[sound]]
+            event = "follow"
+            sound = "sine.flac"
+            output = ["default", "alerts"]
+
+            [[sound]]
+            event = "raid"
+            sound = "silence.ogg"
+            output = "alerts"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            outputs(&sound_system, Event::Follow),
+            [vec!["default"], vec!["default", "alerts"],]
+        );
+        assert_eq!(outputs(&sound_system, Event::Raid), [vec!["alerts"]]);
+        assert!(!sound_system.has_sound_for_event(Event::Message));
+        // no output is opened while loading
+        assert!(sound_system.outputs.is_empty());
+
+        // the output volume applies to the sound played on it
+        let [(_, default), (_, alerts)] = &sound_system.sounds[&Event::Follow][1].outputs[..]
+        else {
+            panic!("expected two outputs");
+        };
+        for (default, alerts) in default.frames().iter().zip(alerts.frames().iter()) {
+            assert_eq!(default[0] * 0.5, alerts[0]);
+            assert_eq!(default[1] * 0.5, alerts[1]);
+        }
+    }
+
+    #[test]
+    fn mismatched_sample_rate_is_resampled() {
+        let sound_system = load(
+            r#"
+            [[sound]]
+            event = "follow"
+            sound = "sine.flac"
+
+            [[sound]]
+            event = "raid"
+            sound = "silence.ogg"
+            "#,
+        )
+        .unwrap();
+
+        // the first sound sets the sample rate
+        let [(_, raid)] = &sound_system.sounds[&Event::Raid][0].outputs[..] else {
+            panic!("expected one output");
+        };
+        assert_eq!(raid.spec().rate, 48000);
+        assert_eq!(raid.frames().len(), 2560);
+
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("../sound-fx-3000/fixtures");
+        assert_eq!(
+            sound_system.warnings,
+            [format!(
+                "resampled sound {:?} from 24000 Hz to 48000 Hz, \
+                export it with 48000 Hz for the best quality",
+                fixtures.join("silence.ogg"),
+            )]
+        );
+    }
+
+    #[test]
+    fn muting_suppresses_all_sounds() {
+        let mut sound_system = load(
+            r#"
+            [[sound]]
+            event = "follow"
+            sound = "sine.flac"
+
+            [[sound]]
+            event = "raid"
+            sound = "sine.flac"
+            "#,
+        )
+        .unwrap();
+
+        assert!(sound_system.toggle_muted());
+        assert!(sound_system.is_muted());
+        sound_system.play_sound_for_event(Event::Follow);
+        sound_system.play_sound_for_event(Event::Raid);
+        assert!(sound_system.played.is_empty());
+
+        assert!(!sound_system.toggle_muted());
+        sound_system.play_sound_for_event(Event::Follow);
+        sound_system.play_sound_for_event(Event::Raid);
+        assert_eq!(sound_system.played, [Event::Follow, Event::Raid]);
+    }
+
+    #[test]
+    fn weighted_sounds_are_picked_by_weight() {
+        use rand::{SeedableRng, rngs::StdRng};
+
+        let sound = |weight| EventSound {
+            weight,
+            outputs: Vec::new(),
+        };
+        let sounds = [sound(None), sound(Some(1.0)), sound(Some(3.0))];
+
+        let mut rng = StdRng::seed_from_u64(2006);
+        let mut picks = [0; 3];
+        for _ in 0..10_000 {
+            let picked: Vec<_> = pick_sounds(&sounds, &mut rng).collect();
+            assert_eq!(picked.len(), 2);
+            for sound in picked {
+                picks[sounds.iter().position(|s| std::ptr::eq(s, sound)).unwrap()] += 1;
+            }
+        }
+
+        // the unweighted sound is always played, the weighted ones one in four and three in four
+        assert_eq!(picks[0], 10_000);
+        assert_eq!(picks[1] + picks[2], 10_000);
+        assert!((2_300..2_700).contains(&picks[1]), "{picks:?}");
+
+        // without weighted sounds only the unweighted sound is played
+        assert_eq!(pick_sounds(&sounds[..1], &mut rng).count(), 1);
+    }
+
+    #[test]
+    fn unknown_output_is_an_error() {
+        let err = load(
+            r#"
+            [[sound]]
+            event = "follow"
+            sound = "sine.flac"
+            output = "alerts"
+            "#,
+        )
+        .err()
+        .unwrap();
+        assert_eq!(err.to_string(), r#"unknown sound output: "alerts""#);
+    }
 }
@@ -1,53 +1,132 @@
 use std::{
-    collections::BTreeSet,
-    fs::File,
-    io::{BufRead, BufReader, Write},
-    num::NonZeroUsize,
+    cell::Cell,
+    collections::{BTreeSet, HashMap, VecDeque},
+    fs::{self, File},
+    io::{BufRead, BufReader, Read, Seek, SeekFrom, Write},
+    iter, mem,
+    num::{NonZeroU64, NonZeroUsize},
     ops::Bound,
-    path::PathBuf,
-    sync::Arc,
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex, OnceLock,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread,
 };
 
 use anyhow::{Context, Result};
-use chrono::{DateTime, NaiveDate, Utc};
+use chrono::{DateTime, Days, NaiveDate, Utc};
 use nucleo::{
-    Nucleo,
+    Injector, Nucleo,
     pattern::{CaseMatching, Normalization},
 };
-use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde::{Deserialize, Deserializer, Serialize};
 use tokio::sync::Notify;
-use twitch_api::events::{
-    chat::{message::ChatMessage, notification::ChatNotification},
-    follow::Follow,
-    stream::{StreamOffline, StreamOnline},
-    ws::NotificationMessageEvent,
+use twitch_api::{
+    channel::Channel,
+    events::{
+        channel_points::ChannelPointsRedemptionAdd,
+        chat::{message::ChatMessage, notification::ChatNotification},
+        cheer::Cheer,
+        follow::Follow,
+        raid::Raid,
+        stream::{StreamOffline, StreamOnline},
+        ws::NotificationMessageEvent,
+    },
+    stream::Stream,
 };
 
+use crate::config::{EventKind, StoreConfig};
+
 pub struct Store {
     directory: PathBuf,
-    files: BTreeSet<NaiveDate>,
-    today: Vec<Event>,
+    /// Stored days and their parts, part 0 is `DATE.json` and part n is `DATE.n.json`
+    files: BTreeSet<(NaiveDate, usize)>,
+    today_date: NaiveDate,
+    today: VecDeque<Event>,
     today_file: Option<File>,
+    today_part: usize,
+    today_file_size: u64,
+    max_file_size: Option<NonZeroU64>,
+    memory_limit: Option<NonZeroUsize>,
+    scrollback_limit: Option<NonZeroUsize>,
+    evicted: usize,
+    scrollback: Option<Scrollback>,
+
+    /// Previous days reachable by scrolling up, oldest first, see [`Store::load_older_day`]
+    older: Vec<OlderDay>,
+    /// Events of the most recently used previous days, least recently used first
+    older_cache: VecDeque<(NaiveDate, Vec<Event>)>,
+
     search: Option<Search>,
+    include: Option<Vec<EventKind>>,
+    exclude: Vec<EventKind>,
+    retention: HashMap<EventKind, u32>,
+
+    /// Whether each of today's events in memory is written to disk, see [`Store::is_stored`]
+    today_stored: VecDeque<bool>,
+
+    /// Number of malformed lines in today's files, they are skipped when loading
+    skipped_lines: usize,
+
+    /// Set when the store continued with a new day, see [`Store::take_day_change`]
+    day_change: Option<DayChange>,
+
+    /// Number of events removed by the retention when the store was opened
+    expired_events: usize,
+
+    /// Failed loads of stored events, they are shown by the ui, see [`Store::take_errors`]
+    errors: Vec<String>,
 }
 
 impl Store {
-    pub fn init(path: PathBuf) -> Result<Self> {
+    /// Number of previous days whose events are kept in memory
+    const OLDER_DAYS_CACHED: usize = 3;
+
+    /// Number of events before the scroll offset that are loaded from previous days
+    const LOAD_AHEAD: usize = 200;
+
+    pub fn init(config: StoreConfig) -> Result<Self> {
+        let directory = config.path()?;
+        fs::create_dir_all(&directory).context("create storage directory")?;
+
         let mut store = Self {
-            directory: path,
+            directory,
             files: BTreeSet::new(),
-            today: Vec::new(),
+            today_date: NaiveDate::default(),
+            today: VecDeque::new(),
             today_file: None,
+            today_part: 0,
+            today_file_size: 0,
+            max_file_size: config.max_file_size,
+            memory_limit: config.memory_limit,
+            scrollback_limit: config.scrollback_limit,
+            evicted: 0,
+            scrollback: None,
+            older: Vec::new(),
+            older_cache: VecDeque::new(),
             search: None,
+            include: config.include,
+            exclude: config.exclude,
+            retention: config.retention,
+            today_stored: VecDeque::new(),
+            skipped_lines: 0,
+            day_change: None,
+            expired_events: 0,
+            errors: Vec::new(),
         };
 
         store.update_files()?;
-        store.update_today()?;
+        store.apply_retention()?;
+        store.update_today(local_date(Utc::now()))?;
 
         Ok(store)
     }
 
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
     fn update_files(&mut self) -> Result<()> {
         self.files = self
             .directory
@@ -58,67 +137,426 @@ impl Store {
                     Ok(it) => it,
                     Err(err) => return Some(Err(err)),
                 };
-                entry
-                    .file_name()
-                    .to_str()?
-                    .strip_suffix(".json")?
-                    .parse()
-                    .ok()
-                    .map(Ok)
+                parse_file_name(entry.file_name().to_str()?).map(Ok)
             })
             .collect::<Result<_>>()?;
-        dbg!(&self.files);
         Ok(())
     }
 
-    fn file_path(&self, date: NaiveDate) -> PathBuf {
-        self.directory.join(format!("{date}.json"))
+    /// Index of the chat message with the given id, `None` if it is not reachable by scrolling
+    pub fn find_message(&mut self, message_id: &str) -> Option<usize> {
+        let is_message = |event: &Event| {
+            event
+                .chat_message()
+                .is_some_and(|message| message.message_id == message_id)
+        };
+        let older_len = self.older_len();
+        let index = match self.today.iter().rposition(is_message) {
+            Some(index) => older_len + self.evicted + index,
+            None => match self.load_evicted().iter().rposition(is_message) {
+                Some(index) => older_len + index,
+                None => self.older_days().find_map(|(start, day)| {
+                    Some(start + self.cached_day(day.date)?.iter().rposition(is_message)?)
+                })?,
+            },
+        };
+        (index >= self.history_start()).then_some(index)
     }
 
-    fn load_file(&self, date: NaiveDate) -> Result<impl Iterator<Item = Result<Event>>> {
-        let events = File::open(self.file_path(date)).context("open storage file")?;
-        let events = BufReader::new(events).lines().map(|line| {
-            let line = line.context("read storage file")?;
-            let event = serde_json::from_str(&line).context("parse stored event")?;
-            Ok(event)
-        });
-        Ok(events)
+    fn file_path(&self, date: NaiveDate, part: usize) -> PathBuf {
+        self.directory.join(match part {
+            0 => format!("{date}.json"),
+            part => format!("{date}.{part}.json"),
+        })
     }
 
-    fn update_today(&mut self) -> Result<()> {
-        let today = chrono::Utc::now()
-            .with_timezone(crate::timezone())
-            .date_naive();
-        let events = if self.files.contains(&today) {
-            self.load_file(today)?.collect::<Result<_>>()?
+    /// Remove events older than the retention of their kind from the day files
+    fn apply_retention(&mut self) -> Result<()> {
+        if self.retention.is_empty() {
+            return Ok(());
+        }
+        let today = local_date(Utc::now());
+        let retention = self.retention.clone();
+        let files: Vec<_> = self.files.iter().copied().collect();
+        for (date, part) in files {
+            let is_expired = |kind: EventKind| {
+                retention
+                    .get(&kind)
+                    .and_then(|&days| today.checked_sub_days(Days::new(days.into())))
+                    .is_some_and(|cutoff| date < cutoff)
+            };
+            if !retention.keys().any(|&kind| is_expired(kind)) {
+                continue;
+            }
+            self.expired_events += self.rewrite_file(date, part, |event| {
+                event.kind().is_none_or(|kind| !is_expired(kind))
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Rewrite a part of a day with only the events that are kept, the file is removed if empty
+    ///
+    /// Returns the number of removed events.
+    fn rewrite_file(
+        &mut self,
+        date: NaiveDate,
+        part: usize,
+        keep: impl Fn(&Event) -> bool,
+    ) -> Result<usize> {
+        let path = self.file_path(date, part);
+        let file = File::open(&path).context("open storage file")?;
+        let mut lines = Vec::new();
+        let mut removed = 0;
+        for line in BufReader::new(file).lines() {
+            let line = line.context("read storage file")?;
+            // malformed lines are kept, they are skipped when loading
+            match serde_json::from_str(&line) {
+                Ok(event) if !keep(&event) => removed += 1,
+                _ => lines.push(line),
+            }
+        }
+        if removed == 0 {
+            return Ok(0);
+        }
+
+        if lines.is_empty() {
+            fs::remove_file(&path).context("remove expired storage file")?;
+            self.files.remove(&(date, part));
         } else {
-            Vec::new()
-        };
-        self.today = events;
+            // write a temporary file first, such that no events are lost if writing fails
+            let tmp_path = path.with_extension("json.tmp");
+            let mut tmp = File::create(&tmp_path).context("create temporary storage file")?;
+            for line in lines {
+                writeln!(tmp, "{line}").context("write temporary storage file")?;
+            }
+            tmp.sync_all().context("write temporary storage file")?;
+            fs::rename(&tmp_path, &path).context("replace storage file")?;
+        }
+        Ok(removed)
+    }
 
-        self.today_file = Some(
-            File::options()
-                .append(true)
-                .create(true)
-                .open(self.file_path(today))
-                .context("failed to open today storage file")?,
-        );
+    /// Events of all parts of the day, in order, see [`read_files`]
+    fn load_file(
+        &self,
+        date: NaiveDate,
+        skipped: &Cell<usize>,
+    ) -> Result<impl Iterator<Item = Result<Event>>> {
+        read_files(self.day_paths(date), skipped)
+    }
 
+    /// Paths of all parts of the day, in order
+    fn day_paths(&self, date: NaiveDate) -> Vec<PathBuf> {
+        self.files
+            .range((date, 0)..=(date, usize::MAX))
+            .map(|&(date, part)| self.file_path(date, part))
+            .collect()
+    }
+
+    /// All events of the day, malformed lines are skipped
+    fn load_day(&self, date: NaiveDate) -> Result<Vec<Event>> {
+        self.load_file(date, &Cell::new(0))?.collect()
+    }
+
+    /// Open the part of today that new events are appended to
+    fn open_today_file(&mut self, part: usize) -> Result<()> {
+        let mut file = File::options()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(self.file_path(self.today_date, part))
+            .context("failed to open today storage file")?;
+        self.today_file_size = file.metadata().context("read storage file size")?.len();
+        if self.today_file_size > 0 {
+            // end a partially written line, such that the next event is not appended to it
+            let mut last = [0];
+            file.seek(SeekFrom::End(-1))
+                .and_then(|_| file.read_exact(&mut last))
+                .context("read storage file")?;
+            if last != *b"\n" {
+                file.write_all(b"\n").context("write storage file")?;
+                self.today_file_size += 1;
+            }
+        }
+        self.today_file = Some(file);
+        self.today_part = part;
+        self.files.insert((self.today_date, part));
         Ok(())
     }
 
+    fn update_today(&mut self, today: NaiveDate) -> Result<()> {
+        let skipped = Cell::new(0);
+        let events: VecDeque<_> = self.load_file(today, &skipped)?.collect::<Result<_>>()?;
+        let last_part = self
+            .files
+            .range((today, 0)..=(today, usize::MAX))
+            .next_back()
+            .map_or(0, |&(_, part)| part);
+        self.today_date = today;
+        self.today_stored = iter::repeat_n(true, events.len()).collect();
+        self.today = events;
+        self.skipped_lines = skipped.get();
+        self.evicted = 0;
+        self.scrollback = None;
+        self.evict();
+
+        self.open_today_file(last_part)
+    }
+
     pub fn push(&mut self, event: Event) -> Result<()> {
+        let date = local_date(event.timestamp());
+        if date > self.today_date {
+            self.change_day(date)?;
+        }
+
+        if !self.is_stored(&event) {
+            self.today_stored.push_back(false);
+            self.today.push_back(event);
+            self.evict();
+            return Ok(());
+        }
+
         let mut json = serde_json::to_string(&event).context("encode storage event")?;
         json.push('\n');
+        if let Some(max_file_size) = self.max_file_size
+            && self.today_file_size > 0
+            && self.today_file_size + json.len() as u64 > max_file_size.get()
+        {
+            self.open_today_file(self.today_part + 1)?;
+        }
         self.today_file
             .as_mut()
             .unwrap()
             .write_all(json.as_bytes())
             .context("write storage event")?;
-        self.today.push(event);
+        self.today_file_size += json.len() as u64;
+        self.today_stored.push_back(true);
+        self.today.push_back(event);
+        self.evict();
         Ok(())
     }
 
+    /// Continue in the file of the new day, today's events become the newest previous day
+    ///
+    /// The indices of the stored events do not change, transient events are dropped.
+    fn change_day(&mut self, date: NaiveDate) -> Result<()> {
+        if let Some(file) = self.today_file.take() {
+            file.sync_all().context("sync storage file")?;
+        }
+
+        let start = self.older_len() + self.evicted;
+        let today = mem::take(&mut self.today);
+        let stored = mem::take(&mut self.today_stored);
+        let len = self.evicted + stored.iter().filter(|&&stored| stored).count();
+        // a day without stored events has no file
+        if len > 0 {
+            self.older.push(OlderDay {
+                date: self.today_date,
+                len,
+            });
+            if self.evicted == 0 {
+                let events = iter::zip(today, &stored)
+                    .filter(|&(_, &stored)| stored)
+                    .map(|(event, _)| event)
+                    .collect();
+                self.cache_day(self.today_date, events);
+            }
+        }
+        self.day_change = Some(DayChange { start, stored });
+
+        self.update_today(date)
+    }
+
+    /// Mapping of the indices of the previous day, if the store continued with a new day
+    pub fn take_day_change(&mut self) -> Option<DayChange> {
+        self.day_change.take()
+    }
+
+    /// Drop the oldest events from memory, they can be reloaded from disk
+    fn evict(&mut self) {
+        let Some(limit) = self.memory_limit else {
+            return;
+        };
+        while self.today.len() > limit.get() {
+            self.today.pop_front();
+            // transient events cannot be reloaded, they are dropped
+            if self.today_stored.pop_front().unwrap() {
+                self.evicted += 1;
+            }
+        }
+    }
+
+    /// Whether the event is written to disk, according to `include` and `exclude`
+    fn is_stored(&self, event: &Event) -> bool {
+        let Some(kind) = event.kind() else {
+            return true;
+        };
+        self.include
+            .as_ref()
+            .is_none_or(|include| include.contains(&kind))
+            && !self.exclude.contains(&kind)
+    }
+
+    /// Load evicted events from disk, such that the event before `end` is available
+    fn load_scrollback(&mut self, end: usize) {
+        let Some(limit) = self.memory_limit else {
+            return;
+        };
+        if end == 0 {
+            return;
+        }
+        if let Some(scrollback) = &self.scrollback
+            && scrollback.start < end
+            && end <= scrollback.start + scrollback.events.len()
+        {
+            return;
+        }
+
+        let start = end.saturating_sub(limit.get());
+        match self
+            .load_file(self.today_date, &Cell::new(0))
+            .and_then(|events| {
+                events
+                    .skip(start)
+                    .take(end - start)
+                    .collect::<Result<Vec<_>>>()
+            }) {
+            Ok(events) => self.scrollback = Some(Scrollback { start, events }),
+            Err(err) => {
+                self.errors
+                    .push(format!("failed to load evicted events: {err:#}"));
+                self.scrollback = None;
+            }
+        }
+    }
+
+    fn load_evicted(&mut self) -> Vec<Event> {
+        if self.evicted == 0 {
+            return Vec::new();
+        }
+        match self
+            .load_file(self.today_date, &Cell::new(0))
+            .and_then(|events| events.take(self.evicted).collect::<Result<Vec<_>>>())
+        {
+            Ok(events) => events,
+            Err(err) => {
+                self.errors
+                    .push(format!("failed to load evicted events: {err:#}"));
+                Vec::new()
+            }
+        }
+    }
+
+    /// Number of events of the previous days, today's events start at this index
+    fn older_len(&self) -> usize {
+        self.older.iter().map(|day| day.len).sum()
+    }
+
+    /// Previous days with the index of their first event, newest first
+    fn older_days(&self) -> impl Iterator<Item = (usize, &OlderDay)> {
+        let mut end = self.older_len();
+        self.older.iter().rev().map(move |day| {
+            end -= day.len;
+            (end, day)
+        })
+    }
+
+    fn cached_day(&self, date: NaiveDate) -> Option<&[Event]> {
+        self.older_cache
+            .iter()
+            .find(|(cached, _)| *cached == date)
+            .map(|(_, events)| events.as_slice())
+    }
+
+    /// Keep the events of a previous day in memory, the least recently used day is dropped
+    fn cache_day(&mut self, date: NaiveDate, events: Vec<Event>) {
+        self.older_cache.retain(|(cached, _)| *cached != date);
+        self.older_cache.push_back((date, events));
+        while self.older_cache.len() > Self::OLDER_DAYS_CACHED {
+            self.older_cache.pop_front();
+        }
+    }
+
+    /// Prepend the previous stored day when the scroll offset gets close to the oldest event
+    ///
+    /// Returns the number of prepended events, the indices of all events shift by it.
+    pub fn load_older_day(&mut self, offset: Option<NonZeroUsize>) -> usize {
+        let Some(offset) = offset else {
+            return 0;
+        };
+        if self.search.is_some() || self.history_limited() || offset.get() > Self::LOAD_AHEAD {
+            return 0;
+        }
+        let oldest = self.older.first().map_or(self.today_date, |day| day.date);
+        let Some(&(date, _)) = self.files.range(..(oldest, 0)).next_back() else {
+            return 0;
+        };
+        let events = match self.load_day(date) {
+            Ok(events) => events,
+            Err(err) => {
+                self.errors
+                    .push(format!("failed to load events of {date}: {err:#}"));
+                return 0;
+            }
+        };
+        let len = events.len();
+        self.older.insert(0, OlderDay { date, len });
+        self.cache_day(date, events);
+        len
+    }
+
+    /// Load the previous days with events shortly before `end` into the cache
+    fn cache_older_days(&mut self, end: usize) {
+        let mut needed = Self::LOAD_AHEAD;
+        let visible: Vec<_> = self
+            .older_days()
+            .filter(|&(start, _)| start < end)
+            .map(|(start, day)| (day.date, (start + day.len).min(end) - start))
+            .take(Self::OLDER_DAYS_CACHED)
+            .collect();
+        for (date, len) in visible {
+            if needed == 0 {
+                break;
+            }
+            needed = needed.saturating_sub(len);
+            let events = match self
+                .older_cache
+                .iter()
+                .position(|(cached, _)| *cached == date)
+            {
+                Some(index) => self.older_cache.remove(index).unwrap().1,
+                None => match self.load_day(date) {
+                    Ok(events) => events,
+                    Err(err) => {
+                        self.errors
+                            .push(format!("failed to load events of {date}: {err:#}"));
+                        return;
+                    }
+                },
+            };
+            self.cache_day(date, events);
+        }
+    }
+
+    /// Number of malformed lines skipped when loading today's files
+    pub fn skipped_lines(&self) -> usize {
+        self.skipped_lines
+    }
+
+    /// Number of events removed by the retention when the store was opened
+    pub fn expired_events(&self) -> usize {
+        self.expired_events
+    }
+
+    /// Errors of loading stored events since the last call, including the background search
+    pub fn take_errors(&mut self) -> Vec<String> {
+        let mut errors = mem::take(&mut self.errors);
+        if let Some(search) = &self.search {
+            errors.append(&mut search.progress.errors.lock().unwrap());
+        }
+        errors
+    }
+
     pub fn events_len(&self) -> usize {
         match &self.search {
             Some(search) => search
@@ -127,11 +565,47 @@ impl Store {
                 .matched_item_count()
                 .try_into()
                 .unwrap(),
-            None => self.today.len(),
+            None => self.older_len() + self.evicted + self.today.len(),
         }
     }
 
-    pub fn events(&self, offset: &mut Option<NonZeroUsize>) -> impl Iterator<Item = &Event> {
+    /// Index of the oldest event reachable by scrolling up
+    fn history_start(&self) -> usize {
+        match self.scrollback_limit {
+            Some(limit) if self.search.is_none() => self.events_len().saturating_sub(limit.get()),
+            _ => 0,
+        }
+    }
+
+    /// Whether older events are hidden because of the scrollback limit
+    pub fn history_limited(&self) -> bool {
+        self.history_start() > 0
+    }
+
+    /// Distinct names of the chatters of today's events in memory, for completion
+    pub fn chatter_names(&self) -> Vec<String> {
+        self.today
+            .iter()
+            .filter_map(Event::chat_message)
+            .map(|message| message.chatter_user_name.as_str())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .map(String::from)
+            .collect()
+    }
+
+    /// The newest chat message visible at the scroll offset
+    pub fn chat_message_at(&mut self, offset: &mut Option<NonZeroUsize>) -> Option<&ChatMessage> {
+        self.events(offset).find_map(Event::chat_message)
+    }
+
+    /// Events before the scroll offset, newest first
+    ///
+    /// The iterator only borrows the store, the offset is clamped to the reachable events.
+    pub fn events<'a>(
+        &'a mut self,
+        offset: &mut Option<NonZeroUsize>,
+    ) -> impl Iterator<Item = &'a Event> + use<'a> {
         enum Either<A, B> {
             Left(A),
             Right(B),
@@ -152,7 +626,23 @@ impl Store {
             }
         }
 
-        match &self.search {
+        if self.search.is_none() {
+            let len = self.events_len();
+            if matches!(offset, Some(offset) if offset.get() >= len) {
+                *offset = None;
+            }
+            let history_start = self.history_start();
+            if matches!(offset, Some(offset) if offset.get() <= history_start) {
+                *offset = NonZeroUsize::new(history_start + 1);
+            }
+            let end = offset.map_or(len, NonZeroUsize::get);
+            let today_end = end.saturating_sub(self.older_len());
+            self.load_scrollback(today_end.min(self.evicted));
+            self.cache_older_days(end);
+        }
+
+        let store: &'a Self = self;
+        match &store.search {
             Some(search) => {
                 let snapshot = search.nucleo.snapshot();
                 let len = snapshot.matched_item_count().try_into().unwrap();
@@ -170,17 +660,32 @@ impl Store {
                 )
             }
             None => {
-                if matches!(offset, Some(offset) if offset.get() >= self.today.len()) {
-                    *offset = None;
-                }
-                Either::Right(
-                    if let Some(offset) = offset {
-                        &self.today[..offset.get()]
-                    } else {
-                        &self.today
+                let end = offset.map_or(store.events_len(), NonZeroUsize::get);
+                let today_end = end.saturating_sub(store.older_len());
+                let memory = store.today.range(..today_end.saturating_sub(store.evicted));
+                let scrollback = match &store.scrollback {
+                    Some(scrollback) => {
+                        let len = today_end
+                            .min(store.evicted)
+                            .saturating_sub(scrollback.start);
+                        &scrollback.events[..len.min(scrollback.events.len())]
                     }
-                    .iter()
-                    .rev(),
+                    None => &[],
+                };
+                // previous days that are not cached end the iteration early
+                let older = self
+                    .older_days()
+                    .filter(move |&(start, _)| start < end)
+                    .map_while(move |(start, day)| {
+                        let events = store.cached_day(day.date)?;
+                        Some(&events[..(start + day.len).min(end) - start])
+                    });
+                Either::Right(
+                    memory
+                        .rev()
+                        .chain(scrollback.iter().rev())
+                        .chain(older.flat_map(|events| events.iter().rev()))
+                        .take(end.saturating_sub(store.history_start())),
                 )
             }
         }
@@ -227,20 +732,48 @@ impl Store {
                 .pattern
                 .reparse(1, query, CaseMatching::Smart, Normalization::Smart, false);
 
-            for event in self.today.iter().rev() {
+            let evicted = self.load_evicted();
+            let stored = iter::zip(&self.today, &self.today_stored)
+                .filter(|&(_, &stored)| stored)
+                .map(|(event, _)| event);
+            for event in stored.rev().chain(evicted.iter().rev()) {
                 nucleo.injector().push(event.clone(), |event, columns| {
                     event.fill_columns(columns).unwrap();
                 });
             }
 
+            let days: Vec<_> = self
+                .files
+                .range(..(self.today_date, 0))
+                .map(|&(date, _)| date)
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .rev()
+                .map(|date| self.day_paths(date))
+                .collect();
+            let progress = SearchProgress {
+                days: days.len(),
+                loaded: Arc::new(AtomicUsize::new(0)),
+                errors: Arc::default(),
+            };
+            inject_days(days, nucleo.injector(), &notify, &progress);
+
             self.search = Some(Search {
                 query: query.into(),
                 nucleo,
                 notify,
+                progress,
             });
         }
     }
 
+    /// Number of previous days loaded into the search and the number of all days, while loading
+    pub fn search_progress(&self) -> Option<(usize, usize)> {
+        let progress = &self.search.as_ref()?.progress;
+        let loaded = progress.loaded.load(Ordering::Relaxed);
+        (loaded < progress.days).then_some((loaded, progress.days))
+    }
+
     pub fn tick(&mut self) {
         if let Some(search) = &mut self.search {
             search.nucleo.tick(10);
@@ -259,10 +792,91 @@ impl Store {
     }
 }
 
+/// Indices of the events before the store continued with a new day, see [`DayChange::index`]
+pub struct DayChange {
+    /// Index of the first event that was in memory
+    start: usize,
+    /// Whether each event in memory was stored, the others were dropped
+    stored: VecDeque<bool>,
+}
+
+impl DayChange {
+    /// Index of an event after the day changed, e.g. to keep the scroll offset
+    ///
+    /// The index of a dropped transient event maps to the next stored event.
+    pub fn index(&self, index: usize) -> usize {
+        match index.checked_sub(self.start) {
+            Some(memory_index) => {
+                let stored = self.stored.iter().take(memory_index);
+                self.start + stored.filter(|&&stored| stored).count()
+            }
+            None => index,
+        }
+    }
+}
+
+/// Evicted events reloaded from disk
+struct Scrollback {
+    /// Index of the first loaded event within the day
+    start: usize,
+    events: Vec<Event>,
+}
+
+/// Previous day reachable by scrolling up, its events are cached while they are viewed
+struct OlderDay {
+    date: NaiveDate,
+    len: usize,
+}
+
 struct Search {
     query: String,
     nucleo: Nucleo<Event>,
     notify: Arc<Notify>,
+    progress: SearchProgress,
+}
+
+/// Previous days are loaded into the search in the background, see [`inject_days`]
+struct SearchProgress {
+    days: usize,
+    loaded: Arc<AtomicUsize>,
+    errors: Arc<Mutex<Vec<String>>>,
+}
+
+/// Load the events of the days (paths of their parts, newest day first) into the search on a
+/// background thread, it stops once the search is dropped
+fn inject_days(
+    days: Vec<Vec<PathBuf>>,
+    injector: Injector<Event>,
+    notify: &Arc<Notify>,
+    progress: &SearchProgress,
+) {
+    let notify = Arc::downgrade(notify);
+    let loaded = progress.loaded.clone();
+    let errors = progress.errors.clone();
+    thread::spawn(move || {
+        for paths in days {
+            let Some(notify) = notify.upgrade() else {
+                return;
+            };
+            match read_files(paths, &Cell::new(0))
+                .and_then(|events| events.collect::<Result<Vec<_>>>())
+            {
+                Ok(events) => {
+                    for event in events.into_iter().rev() {
+                        injector.push(event, |event, columns| {
+                            event.fill_columns(columns).unwrap();
+                        });
+                    }
+                }
+                Err(err) => errors
+                    .lock()
+                    .unwrap()
+                    .push(format!("failed to search stored events: {err:#}")),
+            }
+            loaded.fetch_add(1, Ordering::Relaxed);
+            notify.notify_one();
+        }
+    });
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -279,14 +893,68 @@ pub enum Event {
         timestamp: DateTime<Utc>,
         event: NotificationMessageEvent,
 
-        #[serde(default, skip_serializing_if = "Value::is_null")]
-        extra: Value,
+        #[serde(default, skip_serializing_if = "Extra::is_none")]
+        extra: Extra,
+
+        #[serde(skip)]
+        parsed: ParsedNotification,
+    },
+    /// Follows received in quick succession
+    Combo {
+        timestamp: DateTime<Utc>,
+        events: Vec<NotificationMessageEvent>,
+    },
+    /// Stream that was already online when the chat started
+    Live {
+        timestamp: DateTime<Utc>,
+        stream: Stream,
     },
 }
 
 impl Event {
     const NUM_COLUMNS: u32 = 2;
 
+    pub fn timestamp(&self) -> DateTime<Utc> {
+        match self {
+            Event::Started { started_at } => *started_at,
+            Event::Message { sent_at, .. } => *sent_at,
+            Event::Notification { timestamp, .. } => *timestamp,
+            Event::Combo { timestamp, .. } => *timestamp,
+            Event::Live { timestamp, .. } => *timestamp,
+        }
+    }
+
+    /// Kind for filtering what is stored, `None` for events that are always stored
+    pub fn kind(&self) -> Option<EventKind> {
+        Some(match self {
+            Event::Started { .. } => return None,
+            Event::Message { .. } => EventKind::Message,
+            Event::Notification { event, parsed, .. } => match parsed.get(event).ok()? {
+                Notification::ChatMessage(_) => EventKind::Message,
+                Notification::ChatNotification(_) => EventKind::Notification,
+                Notification::Follow(_) => EventKind::Follow,
+                Notification::Cheer(_) => EventKind::Cheer,
+                Notification::Redemption(_) => EventKind::Redemption,
+                Notification::Raid(_) => EventKind::Raid,
+                Notification::StreamOnline(_) => EventKind::Online,
+                Notification::StreamOffline(_) => EventKind::Offline,
+                Notification::Unknown => return None,
+            },
+            Event::Combo { .. } => EventKind::Follow,
+            Event::Live { .. } => EventKind::Online,
+        })
+    }
+
+    pub fn chat_message(&self) -> Option<&ChatMessage> {
+        let Event::Notification { event, parsed, .. } = self else {
+            return None;
+        };
+        match parsed.get(event).ok()? {
+            Notification::ChatMessage(message) => Some(message),
+            _ => None,
+        }
+    }
+
     fn fill_columns(&self, columns: &mut [nucleo::Utf32String]) -> Result<()> {
         let [user, text] = columns else {
             anyhow::bail!("{} colomns", columns.len());
@@ -297,30 +965,827 @@ impl Event {
             Event::Message {
                 user_login, text, ..
             } => [user_login.as_str().into(), text.as_str().into()],
-            Event::Notification { event, .. } => {
-                let notification = event;
-                if let Some(message) = notification.parse::<ChatMessage>()? {
-                    [
-                        message.chatter_user_name.into(),
-                        message.message.text.into(),
-                    ]
-                } else if let Some(notification) = notification.parse::<ChatNotification>()? {
-                    [
-                        notification.chatter_user_name.into(),
-                        notification.message.text.into(),
-                    ]
-                } else if let Some(follow) = notification.parse::<Follow>()? {
-                    [follow.user_name.into(), "has followd you".into()]
-                } else if let Some(_online) = notification.parse::<StreamOnline>()? {
+            Event::Notification { event, parsed, .. } => match parsed.get(event)? {
+                Notification::ChatMessage(message) => [
+                    message.chatter_user_name.as_str().into(),
+                    message.message.plain_text().as_ref().into(),
+                ],
+                Notification::ChatNotification(notification) => [
+                    notification.chatter_user_name.as_str().into(),
+                    notification.message.plain_text().as_ref().into(),
+                ],
+                Notification::Follow(follow) => {
+                    [follow.user_name.as_str().into(), "has followd you".into()]
+                }
+                Notification::Cheer(cheer) => [
+                    cheer.user_name().into(),
+                    format!("cheered {} bits {}", cheer.bits, cheer.message)
+                        .as_str()
+                        .into(),
+                ],
+                Notification::Redemption(redemption) => [
+                    redemption.user_name.as_str().into(),
+                    format!(
+                        "redeemed {} {}",
+                        redemption.reward.title, redemption.user_input
+                    )
+                    .as_str()
+                    .into(),
+                ],
+                Notification::Raid(raid) => [
+                    raid.from_broadcaster_user_name.as_str().into(),
+                    format!("raid with {} viewers", raid.viewers)
+                        .as_str()
+                        .into(),
+                ],
+                Notification::StreamOnline(_online) => {
                     [Default::default(), "stream went online".into()]
-                } else if let Some(_offline) = notification.parse::<StreamOffline>()? {
+                }
+                Notification::StreamOffline(_offline) => {
                     [Default::default(), "stream went offline".into()]
-                } else {
-                    Default::default()
                 }
-            }
+                Notification::Unknown => Default::default(),
+            },
+            Event::Combo { events, .. } => [
+                combo_follows(events).join(" ").into(),
+                format!("{} follows", events.len()).into(),
+            ],
+            Event::Live { stream, .. } => [
+                stream.user_name.as_str().into(),
+                format!("currently live {}", stream.title).as_str().into(),
+            ],
         };
 
         Ok(())
     }
 }
+
+/// Events of the files, in order
+///
+/// Malformed lines, e.g. partially written before a crash, are skipped and counted in `skipped`.
+/// They are skipped before the events are indexed, the index of an event is the same as in
+/// memory.
+fn read_files(
+    paths: impl IntoIterator<Item = PathBuf>,
+    skipped: &Cell<usize>,
+) -> Result<impl Iterator<Item = Result<Event>>> {
+    let files = paths
+        .into_iter()
+        .map(File::open)
+        .collect::<Result<Vec<_>, _>>()
+        .context("open storage file")?;
+    let events = files
+        .into_iter()
+        .flat_map(|file| BufReader::new(file).lines())
+        .filter_map(move |line| {
+            let line = match line.context("read storage file") {
+                Ok(line) => line,
+                Err(err) => return Some(Err(err)),
+            };
+            match serde_json::from_str(&line) {
+                Ok(event) => Some(Ok(event)),
+                Err(_) => {
+                    skipped.set(skipped.get() + 1);
+                    None
+                }
+            }
+        });
+    Ok(events)
+}
+
+/// Date of the day file a timestamp belongs to, in the configured timezone
+fn local_date(timestamp: DateTime<Utc>) -> NaiveDate {
+    timestamp.with_timezone(crate::timezone()).date_naive()
+}
+
+/// Parse a storage file name, `DATE.json` is part 0 and `DATE.n.json` is part n
+fn parse_file_name(name: &str) -> Option<(NaiveDate, usize)> {
+    let name = name.strip_suffix(".json")?;
+    match name.split_once('.') {
+        Some((date, part)) => Some((date.parse().ok()?, part.parse().ok()?)),
+        None => Some((name.parse().ok()?, 0)),
+    }
+}
+
+/// User names of the followers in a combo
+pub fn combo_follows(events: &[NotificationMessageEvent]) -> Vec<String> {
+    events
+        .iter()
+        .filter_map(|event| event.parse::<Follow>().ok().flatten())
+        .map(|follow| follow.user_name)
+        .collect()
+}
+
+/// Info loaded when a notification is received, to show what the stream was like at that time
+#[derive(Debug, Default, Clone, Serialize)]
+pub enum Extra {
+    #[default]
+    None,
+    Stream(Stream),
+    Channel(Channel),
+    /// Chat message received while the broadcaster was away
+    Away,
+}
+
+impl Extra {
+    fn is_none(&self) -> bool {
+        matches!(self, Self::None)
+    }
+}
+
+impl<'de> Deserialize<'de> for Extra {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        enum Tagged {
+            None,
+            Stream(Stream),
+            Channel(Channel),
+            Away,
+        }
+
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Stored {
+            Tagged(Tagged),
+            // events stored before the extra was tagged contain the plain stream or channel
+            Stream(Stream),
+            Channel(Channel),
+            Null(()),
+        }
+
+        Ok(match Stored::deserialize(deserializer)? {
+            Stored::Tagged(Tagged::None) | Stored::Null(()) => Self::None,
+            Stored::Tagged(Tagged::Stream(stream)) | Stored::Stream(stream) => Self::Stream(stream),
+            Stored::Tagged(Tagged::Channel(channel)) | Stored::Channel(channel) => {
+                Self::Channel(channel)
+            }
+            Stored::Tagged(Tagged::Away) => Self::Away,
+        })
+    }
+}
+
+/// Notification event parsed into one of the subscribed event types
+#[derive(Debug)]
+pub enum Notification {
+    ChatMessage(ChatMessage),
+    ChatNotification(ChatNotification),
+    Follow(Follow),
+    Cheer(Cheer),
+    Redemption(ChannelPointsRedemptionAdd),
+    Raid(Raid),
+    StreamOnline(StreamOnline),
+    StreamOffline(StreamOffline),
+    Unknown,
+}
+
+#[cfg(test)]
+thread_local! {
+    /// Number of notifications parsed on this thread
+    static PARSES: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+impl Notification {
+    fn parse(event: &NotificationMessageEvent) -> Result<Self> {
+        #[cfg(test)]
+        PARSES.set(PARSES.get() + 1);
+        Ok(if let Some(message) = event.parse()? {
+            Self::ChatMessage(message)
+        } else if let Some(notification) = event.parse()? {
+            Self::ChatNotification(notification)
+        } else if let Some(follow) = event.parse()? {
+            Self::Follow(follow)
+        } else if let Some(cheer) = event.parse()? {
+            Self::Cheer(cheer)
+        } else if let Some(redemption) = event.parse()? {
+            Self::Redemption(redemption)
+        } else if let Some(raid) = event.parse()? {
+            Self::Raid(raid)
+        } else if let Some(online) = event.parse()? {
+            Self::StreamOnline(online)
+        } else if let Some(offline) = event.parse()? {
+            Self::StreamOffline(offline)
+        } else {
+            Self::Unknown
+        })
+    }
+}
+
+/// Lazily parsed notification, such that search and rendering parse the event only once
+#[derive(Debug, Default, Clone)]
+pub struct ParsedNotification(OnceLock<Arc<Result<Notification>>>);
+
+impl ParsedNotification {
+    pub fn get(&self, event: &NotificationMessageEvent) -> Result<&Notification> {
+        match &**self.0.get_or_init(|| Arc::new(Notification::parse(event))) {
+            Ok(notification) => Ok(notification),
+            Err(err) => Err(anyhow::anyhow!("{err:#}")),
+        }
+    }
+}
+
+#[cfg(test)]
+impl Store {
+    /// Store in a new temporary directory, dates are in UTC
+    pub(crate) fn test(config: StoreConfig) -> Self {
+        static DIRECTORIES: AtomicUsize = AtomicUsize::new(0);
+
+        let _ = crate::TIMEZONE.set(chrono_tz::Tz::UTC);
+        let path = std::env::temp_dir().join(format!(
+            "twitch-chat-store-test-{}-{}",
+            std::process::id(),
+            DIRECTORIES.fetch_add(1, Ordering::Relaxed),
+        ));
+        let _ = fs::remove_dir_all(&path);
+        Store::init(StoreConfig {
+            path: Some(path),
+            ..config
+        })
+        .unwrap()
+    }
+}
+
+#[cfg(test)]
+impl Event {
+    /// Chat message notification of the user `Viewer`, the `fields` replace the defaults
+    pub(crate) fn test_chat_message(fields: serde_json::Value) -> Self {
+        let mut message = serde_json::json!({
+            "broadcaster_user_id": "1337",
+            "broadcaster_user_login": "cool_user",
+            "broadcaster_user_name": "Cool_User",
+            "chatter_user_id": "4242",
+            "chatter_user_login": "viewer",
+            "chatter_user_name": "Viewer",
+            "message_id": "cc106a89-1814-919d-454c-f4f2f970aae7",
+            "message": { "text": "hello", "fragments": [] },
+            "message_type": "text",
+            "badges": [],
+            "color": "",
+        });
+        for (key, value) in fields.as_object().unwrap() {
+            message[key] = value.clone();
+        }
+        serde_json::from_value(serde_json::json!({
+            "Notification": {
+                "timestamp": "2025-01-31T20:00:00Z",
+                "event": {
+                    "type_": "channel.chat.message",
+                    "version": "1",
+                    "event": message,
+                },
+            },
+        }))
+        .unwrap()
+    }
+
+    /// Chat message consisting of the `Kappa` and `PogChamp` emotes, without plain text
+    pub(crate) fn test_emote_only_message() -> Self {
+        let emote = |text: &str, id: &str| {
+            serde_json::json!({
+                "type": "emote",
+                "text": text,
+                "emote": {
+                    "id": id,
+                    "emote_set_id": "0",
+                    "owner_id": "0",
+                    "format": ["static"],
+                },
+            })
+        };
+        Self::test_chat_message(serde_json::json!({
+            "message": {
+                "text": "",
+                "fragments": [
+                    emote("Kappa", "25"),
+                    { "type": "text", "text": " " },
+                    emote("PogChamp", "305954156"),
+                ],
+            },
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeDelta;
+
+    use super::*;
+
+    fn store(config: StoreConfig) -> Store {
+        Store::test(config)
+    }
+
+    /// Stored stream online notification with the given extra
+    fn online_event(extra: serde_json::Value) -> Event {
+        serde_json::from_value(serde_json::json!({
+            "Notification": {
+                "timestamp": "2025-01-31T20:00:00Z",
+                "event": {
+                    "type_": "stream.online",
+                    "version": "1",
+                    "event": {
+                        "id": "9001",
+                        "broadcaster_user_id": "1337",
+                        "broadcaster_user_login": "cool_user",
+                        "broadcaster_user_name": "Cool_User",
+                        "type": "live",
+                        "started_at": "2025-01-31T20:00:00Z",
+                    },
+                },
+                "extra": extra,
+            },
+        }))
+        .unwrap()
+    }
+
+    fn stream() -> serde_json::Value {
+        serde_json::json!({
+            "id": "9001",
+            "user_id": "1337",
+            "user_login": "cool_user",
+            "user_name": "Cool_User",
+            "game_id": "509658",
+            "game_name": "Just Chatting",
+            "type": "live",
+            "title": "hello chat",
+            "tags": ["English"],
+            "viewer_count": 42,
+            "started_at": "2025-01-31T20:00:00Z",
+            "language": "en",
+            "thumbnail_url": "https://example.com/{width}x{height}.jpg",
+            "tag_ids": [],
+            "is_mature": false,
+        })
+    }
+
+    fn extra(event: &Event) -> &Extra {
+        match event {
+            Event::Notification { extra, .. } => extra,
+            _ => panic!("not a notification"),
+        }
+    }
+
+    #[test]
+    fn stream_extra_round_trips() {
+        let event = online_event(serde_json::json!({ "Stream": stream() }));
+        let Extra::Stream(stream) = extra(&event) else {
+            panic!("expected a stream extra");
+        };
+        assert_eq!(stream.title, "hello chat");
+
+        let stored = serde_json::to_value(&event).unwrap();
+        assert_eq!(
+            stored["Notification"]["extra"]["Stream"]["title"],
+            "hello chat"
+        );
+        let event: Event = serde_json::from_value(stored.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&event).unwrap(), stored);
+    }
+
+    #[test]
+    fn old_extra_format_is_read() {
+        // the extra used to be the plain stream or channel
+        let event = online_event(stream());
+        assert!(matches!(extra(&event), Extra::Stream(stream) if stream.title == "hello chat"));
+        let stored = serde_json::to_value(&event).unwrap();
+        assert_eq!(
+            stored["Notification"]["extra"]["Stream"]["title"],
+            "hello chat"
+        );
+
+        let event = online_event(serde_json::json!({
+            "broadcaster_id": "1337",
+            "broadcaster_login": "cool_user",
+            "broadcaster_name": "Cool_User",
+            "broadcaster_language": "en",
+            "game_name": "Just Chatting",
+            "game_id": "509658",
+            "title": "offline for now",
+            "delay": 0,
+            "tags": [],
+            "content_classification_labels": [],
+            "is_branded_content": false,
+        }));
+        assert!(
+            matches!(extra(&event), Extra::Channel(channel) if channel.title == "offline for now")
+        );
+
+        let event = online_event(serde_json::Value::Null);
+        assert!(matches!(extra(&event), Extra::None));
+    }
+
+    #[test]
+    fn notifications_are_parsed_once() {
+        let event: Event = serde_json::from_value(serde_json::json!({
+            "Notification": {
+                "timestamp": "2025-01-31T20:00:00Z",
+                "event": {
+                    "type_": "channel.follow",
+                    "version": "2",
+                    "event": {
+                        "user_id": "4242",
+                        "user_login": "viewer",
+                        "user_name": "Viewer",
+                        "broadcaster_user_id": "1337",
+                        "broadcaster_user_login": "cool_user",
+                        "broadcaster_user_name": "Cool_User",
+                        "followed_at": "2025-01-31T20:00:00Z",
+                    },
+                },
+            },
+        }))
+        .unwrap();
+
+        let before = PARSES.get();
+        let mut columns = vec![nucleo::Utf32String::default(); Event::NUM_COLUMNS as usize];
+        for _ in 0..3 {
+            event.fill_columns(&mut columns).unwrap();
+            assert!(matches!(event.kind(), Some(EventKind::Follow)));
+            assert!(event.chat_message().is_none());
+        }
+        assert_eq!(columns[0].to_string(), "Viewer");
+        // clones share the parsed notification
+        event.clone().fill_columns(&mut columns).unwrap();
+        assert_eq!(PARSES.get() - before, 1);
+    }
+
+    #[test]
+    fn emote_only_message_is_searchable() {
+        let event = Event::test_emote_only_message();
+        let mut columns = vec![nucleo::Utf32String::default(); Event::NUM_COLUMNS as usize];
+        event.fill_columns(&mut columns).unwrap();
+        assert_eq!(columns[0].to_string(), "Viewer");
+        assert_eq!(columns[1].to_string(), "Kappa PogChamp");
+    }
+
+    fn message(text: &str) -> Event {
+        Event::Message {
+            sent_at: Utc::now(),
+            user_login: "user".into(),
+            text: text.into(),
+        }
+    }
+
+    fn text(event: &Event) -> &str {
+        match event {
+            Event::Message { text, .. } => text,
+            _ => "",
+        }
+    }
+
+    /// Texts of the events reachable by scrolling, oldest first
+    fn texts(store: &mut Store) -> Vec<String> {
+        let mut texts: Vec<_> = store
+            .events(&mut None)
+            .map(text)
+            .map(String::from)
+            .collect();
+        texts.reverse();
+        texts
+    }
+
+    /// Texts of the events in today's files
+    fn stored_texts(store: &Store) -> Vec<String> {
+        store
+            .load_day(store.today_date)
+            .unwrap()
+            .iter()
+            .map(text)
+            .map(String::from)
+            .collect()
+    }
+
+    /// Write a previous day with a message for each text, `days_ago` before today
+    fn write_day(store: &Store, days_ago: u64, texts: &[&str]) {
+        let date = store.today_date - Days::new(days_ago);
+        let lines: String = texts
+            .iter()
+            .map(|&text| serde_json::to_string(&message(text)).unwrap() + "\n")
+            .collect();
+        fs::write(store.file_path(date, 0), lines).unwrap();
+    }
+
+    /// Store that was opened after the previous days were written
+    fn reopen(store: Store) -> Store {
+        Store::init(StoreConfig {
+            path: Some(store.directory.clone()),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn scroll_into_previous_days() {
+        let store = store(StoreConfig::default());
+        write_day(&store, 2, &["a", "b"]);
+        write_day(&store, 1, &["c"]);
+        let mut store = reopen(store);
+        store.push(message("d")).unwrap();
+        assert_eq!(texts(&mut store), ["d"]);
+
+        // the previous days are prepended once the scroll offset gets close to the oldest event
+        assert_eq!(store.load_older_day(None), 0);
+        assert_eq!(store.load_older_day(NonZeroUsize::new(1)), 1);
+        assert_eq!(texts(&mut store), ["c", "d"]);
+        assert_eq!(store.load_older_day(NonZeroUsize::new(1)), 2);
+        assert_eq!(texts(&mut store), ["a", "b", "c", "d"]);
+        assert_eq!(store.load_older_day(NonZeroUsize::new(1)), 0);
+
+        // scrolled up into the oldest day
+        let mut offset = NonZeroUsize::new(2);
+        let events: Vec<_> = store.events(&mut offset).map(text).collect();
+        assert_eq!(events, ["b", "a"]);
+    }
+
+    #[test]
+    fn find_message_by_id() {
+        let mut store = store(StoreConfig {
+            scrollback_limit: NonZeroUsize::new(2),
+            ..Default::default()
+        });
+        for message_id in ["a", "b", "c"] {
+            let message = Event::test_chat_message(serde_json::json!({ "message_id": message_id }));
+            store.push(message).unwrap();
+        }
+
+        assert_eq!(store.find_message("c"), Some(2));
+        assert_eq!(store.find_message("b"), Some(1));
+        // hidden by the scrollback limit
+        assert_eq!(store.find_message("a"), None);
+        assert_eq!(store.find_message("unknown"), None);
+    }
+
+    #[test]
+    fn scrollback_is_limited() {
+        let store = store(StoreConfig::default());
+        write_day(&store, 2, &["a"]);
+        write_day(&store, 1, &["b", "c"]);
+        let mut store = Store::init(StoreConfig {
+            path: Some(store.directory.clone()),
+            scrollback_limit: NonZeroUsize::new(3),
+            ..Default::default()
+        })
+        .unwrap();
+        store.push(message("d")).unwrap();
+        store.push(message("e")).unwrap();
+        assert!(!store.history_limited());
+
+        // the oldest of the loaded events is hidden, no further days are loaded
+        assert_eq!(store.load_older_day(NonZeroUsize::new(1)), 2);
+        assert!(store.history_limited());
+        assert_eq!(texts(&mut store), ["c", "d", "e"]);
+        assert_eq!(store.load_older_day(NonZeroUsize::new(1)), 0);
+        assert_eq!(texts(&mut store), ["c", "d", "e"]);
+    }
+
+    #[test]
+    fn cache_of_previous_days_is_limited() {
+        let store = store(StoreConfig::default());
+        for days_ago in 1..=5 {
+            write_day(&store, days_ago, &[&days_ago.to_string()]);
+        }
+        let mut store = reopen(store);
+        while store.load_older_day(NonZeroUsize::new(1)) > 0 {}
+        assert_eq!(store.older.len(), 5);
+        assert_eq!(store.older_cache.len(), Store::OLDER_DAYS_CACHED);
+
+        // the least recently loaded days are dropped, the newest days are loaded again
+        let cached = |store: &Store| {
+            let mut dates: Vec<_> = store.older_cache.iter().map(|&(date, _)| date).collect();
+            dates.sort();
+            dates
+        };
+        assert_eq!(
+            cached(&store),
+            [5, 4, 3].map(|days_ago| store.today_date - Days::new(days_ago))
+        );
+        assert_eq!(texts(&mut store), ["3", "2", "1"]);
+        assert_eq!(
+            cached(&store),
+            [3, 2, 1].map(|days_ago| store.today_date - Days::new(days_ago))
+        );
+    }
+
+    #[test]
+    fn keep_indices_on_new_day() {
+        let mut store = store(StoreConfig {
+            exclude: vec![EventKind::Message],
+            ..Default::default()
+        });
+        // the timestamps of the events are the clock of the store
+        let today = Utc::now();
+        let tomorrow = today + TimeDelta::days(1);
+        let date = store.today_date;
+        store.push(Event::Started { started_at: today }).unwrap();
+        store.push(message("transient")).unwrap();
+        store
+            .push(Event::Started {
+                started_at: today + TimeDelta::seconds(1),
+            })
+            .unwrap();
+        assert!(store.take_day_change().is_none());
+
+        store
+            .push(Event::Started {
+                started_at: tomorrow,
+            })
+            .unwrap();
+        assert_eq!(store.today_date, local_date(tomorrow));
+        assert!(store.files.contains(&(date, 0)));
+        assert!(store.files.contains(&(local_date(tomorrow), 0)));
+
+        let change = store.take_day_change().unwrap();
+        assert_eq!(change.index(0), 0);
+        // the transient event is dropped, the next event takes its place
+        assert_eq!(change.index(1), 1);
+        assert_eq!(change.index(2), 1);
+        assert_eq!(change.index(3), 2);
+
+        assert_eq!(store.events_len(), 3);
+        let timestamps: Vec<_> = store.events(&mut None).map(Event::timestamp).collect();
+        assert_eq!(timestamps, [tomorrow, today + TimeDelta::seconds(1), today]);
+        let mut offset = NonZeroUsize::new(change.index(2));
+        let timestamps: Vec<_> = store.events(&mut offset).map(Event::timestamp).collect();
+        assert_eq!(timestamps, [today]);
+    }
+
+    #[test]
+    fn parse_part_file_names() {
+        let date = NaiveDate::from_ymd_opt(2025, 1, 31).unwrap();
+        assert_eq!(parse_file_name("2025-01-31.json"), Some((date, 0)));
+        assert_eq!(parse_file_name("2025-01-31.2.json"), Some((date, 2)));
+        assert_eq!(parse_file_name("2025-01-31.12.json"), Some((date, 12)));
+        assert_eq!(parse_file_name("2025-01-31.json.tmp"), None);
+        assert_eq!(parse_file_name("2025-01-31.x.json"), None);
+        assert_eq!(parse_file_name("config.json"), None);
+    }
+
+    #[test]
+    fn rotate_at_max_file_size() {
+        let mut store = store(StoreConfig {
+            max_file_size: NonZeroU64::new(100),
+            ..Default::default()
+        });
+        // every event is larger than half of the file size, each one starts a new part
+        let texts_written: Vec<_> = (0..12).map(|i| i.to_string()).collect();
+        for text in &texts_written {
+            store.push(message(text)).unwrap();
+        }
+
+        let date = store.today_date;
+        let parts: Vec<_> = store.files.iter().map(|&(_, part)| part).collect();
+        assert_eq!(parts, (0..12).collect::<Vec<_>>());
+        for &(date, part) in &store.files {
+            let size = fs::metadata(store.file_path(date, part)).unwrap().len();
+            assert!(size <= 100, "part {part} has {size} bytes");
+        }
+
+        // the parts are read in order, part 10 is read after part 2
+        let mut store = Store::init(StoreConfig {
+            path: Some(store.directory.clone()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(store.today_date, date);
+        assert_eq!(texts(&mut store), texts_written);
+    }
+
+    #[test]
+    fn expired_events_are_removed_at_init() {
+        let directory = store(StoreConfig::default()).directory.clone();
+        let date = local_date(Utc::now()) - Days::new(10);
+        let line = serde_json::to_string(&message("old")).unwrap();
+        fs::write(
+            directory.join(format!("{date}.json")),
+            format!("{line}\n{line}\n"),
+        )
+        .unwrap();
+
+        let store = Store::init(StoreConfig {
+            path: Some(directory.clone()),
+            retention: HashMap::from([(EventKind::Message, 7)]),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(store.expired_events(), 2);
+        assert!(!directory.join(format!("{date}.json")).exists());
+    }
+
+    #[test]
+    fn failed_loads_are_reported() {
+        let mut store = store(StoreConfig {
+            memory_limit: NonZeroUsize::new(2),
+            ..Default::default()
+        });
+        for text in ["a", "b", "c"] {
+            store.push(message(text)).unwrap();
+        }
+        assert!(store.take_errors().is_empty());
+
+        fs::remove_file(store.file_path(store.today_date, 0)).unwrap();
+        assert_eq!(store.find_message("missing"), None);
+        let errors = store.take_errors();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("failed to load evicted events"));
+        assert!(store.take_errors().is_empty());
+    }
+
+    #[test]
+    fn evicted_events_are_reloaded_from_disk() {
+        let mut store = store(StoreConfig {
+            memory_limit: NonZeroUsize::new(2),
+            ..Default::default()
+        });
+        for text in ["a", "b", "c", "d", "e"] {
+            store.push(message(text)).unwrap();
+        }
+
+        assert_eq!(store.today.len(), 2);
+        assert_eq!(store.evicted, 3);
+        assert_eq!(store.events_len(), 5);
+
+        // scrolled up to the oldest event, it is reloaded from disk
+        let mut offset = NonZeroUsize::new(1);
+        let older: Vec<_> = store.events(&mut offset).map(text).collect();
+        assert_eq!(older, ["a"]);
+        // at most `memory_limit` evicted events are loaded before the scroll offset
+        let mut offset = NonZeroUsize::new(4);
+        let older: Vec<_> = store.events(&mut offset).map(text).collect();
+        assert_eq!(older, ["d", "c", "b"]);
+    }
+
+    #[test]
+    fn malformed_lines_are_skipped() {
+        let mut store = store(StoreConfig::default());
+        store.push(message("a")).unwrap();
+        store.push(message("b")).unwrap();
+        let path = store.file_path(store.today_date, 0);
+        // a line written partially before a crash
+        let mut file = File::options().append(true).open(&path).unwrap();
+        file.write_all(br#"{"Message":{"sent_at":"#).unwrap();
+        drop(file);
+
+        let mut store = Store::init(StoreConfig {
+            path: Some(store.directory.clone()),
+            memory_limit: NonZeroUsize::new(1),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(store.skipped_lines(), 1);
+        store.push(message("c")).unwrap();
+        store.push(message("d")).unwrap();
+
+        assert_eq!(store.evicted, 3);
+        let mut offset = NonZeroUsize::new(3);
+        let older: Vec<_> = store.events(&mut offset).map(text).collect();
+        assert_eq!(older, ["c"]);
+        let mut offset = NonZeroUsize::new(1);
+        let older: Vec<_> = store.events(&mut offset).map(text).collect();
+        assert_eq!(older, ["a"]);
+        assert_eq!(stored_texts(&store), ["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn excluded_events_are_shown_but_not_stored() {
+        let mut store = store(StoreConfig {
+            exclude: vec![EventKind::Message],
+            ..Default::default()
+        });
+        store
+            .push(Event::Started {
+                started_at: Utc::now(),
+            })
+            .unwrap();
+        store.push(message("hello")).unwrap();
+
+        assert_eq!(texts(&mut store), ["", "hello"]);
+        assert_eq!(stored_texts(&store), [""]);
+    }
+
+    #[test]
+    fn evicted_excluded_events_are_dropped() {
+        let mut store = store(StoreConfig {
+            exclude: vec![EventKind::Message],
+            memory_limit: NonZeroUsize::new(2),
+            ..Default::default()
+        });
+        // events with the same timestamp, only the flag tells them apart
+        let started_at = Utc::now();
+        store.push(Event::Started { started_at }).unwrap();
+        store
+            .push(Event::Message {
+                sent_at: started_at,
+                user_login: "user".into(),
+                text: "hello".into(),
+            })
+            .unwrap();
+        store.push(Event::Started { started_at }).unwrap();
+        store.push(message("world")).unwrap();
+
+        assert_eq!(store.evicted, 1);
+        assert_eq!(store.events_len(), 3);
+        assert_eq!(texts(&mut store), ["", "", "world"]);
+    }
+}
@@ -1,121 +1,219 @@
+use std::time::Duration;
+
 use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
 use twitch_api::{
+    auth::Scope,
     client::AuthenticatedClient,
     events::{
-        chat::{
-            message::{ChatMessage, ChatMessageCondition},
-            notification::{ChatNotification, ChatNotificationCondition},
-        },
-        follow::{Follow, FollowCondition},
-        stream::{StreamOffline, StreamOfflineCondition, StreamOnline, StreamOnlineCondition},
+        channel_points::ChannelPointsRedemptionAdd,
+        chat::{message::ChatMessage, notification::ChatNotification},
+        cheer::Cheer,
+        follow::Follow,
+        raid::{Raid, RaidCondition},
+        stream::{StreamOffline, StreamOnline},
         subscription::{
-            CreateSubscriptionRequest, CreateSubscriptionResponse, DeleteSubscriptionRequest,
+            CreateSubscriptionRequest, DeleteSubscriptionRequest, SubscriptionStatus,
             TransportRequest,
         },
-        ws::WebSocket,
+        types::Subscription,
+        ws::{RevocationMessage, SubscriptionInfo, WebSocket},
     },
     secret::Secret,
     user::User,
 };
 
+/// Scopes requested by `auth` and `/reauth`, needed for the subscriptions and chat commands
+pub const SCOPES: [Scope; 13] = [
+    Scope::UserReadChat,
+    Scope::UserWriteChat,
+    Scope::ModeratorManageAnnouncements,
+    Scope::ModeratorReadFollowers,
+    Scope::ChannelReadEditors,
+    Scope::ModeratorManageShoutouts,
+    Scope::ModeratorManageBannedUsers,
+    Scope::ModeratorManageChatMessages,
+    Scope::ModeratorManageChatSettings,
+    Scope::BitsRead,
+    Scope::ChannelReadRedemptions,
+    Scope::ChannelReadPolls,
+    Scope::ChannelReadPredictions,
+];
+
+/// Scopes needed to read chat and create the subscriptions, if messages are sent by another profile
+pub const READ_SCOPES: [Scope; 7] = [
+    Scope::UserReadChat,
+    Scope::ModeratorReadFollowers,
+    Scope::ChannelReadEditors,
+    Scope::BitsRead,
+    Scope::ChannelReadRedemptions,
+    Scope::ChannelReadPolls,
+    Scope::ChannelReadPredictions,
+];
+
+/// Scopes needed to send messages and moderate, if chat is read by another profile
+pub const WRITE_SCOPES: [Scope; 6] = [
+    Scope::UserWriteChat,
+    Scope::ModeratorManageAnnouncements,
+    Scope::ModeratorManageShoutouts,
+    Scope::ModeratorManageBannedUsers,
+    Scope::ModeratorManageChatMessages,
+    Scope::ModeratorManageChatSettings,
+];
+
 pub struct Subscriptions {
     ids: Vec<Secret>,
+    session_id: Secret,
+    subscribed_at: DateTime<Utc>,
+    keepalive_grace: Duration,
 }
 
 impl Subscriptions {
+    /// Attempts to connect a new web socket before the connection loss is returned as error
+    const MAX_RECONNECT_ATTEMPTS: u32 = 5;
+
+    /// Wait before the second connection attempt, doubled for every further attempt
+    const RECONNECT_BACKOFF: Duration = Duration::from_secs(1);
+
+    #[cfg(test)]
+    /// Subscriptions without ids, nothing is deleted on cleanup
+    pub(crate) fn test() -> Self {
+        Self {
+            ids: Vec::new(),
+            session_id: Secret::new("test"),
+            subscribed_at: Utc::now(),
+            keepalive_grace: Duration::ZERO,
+        }
+    }
+
     pub async fn subscribe(
         client: &mut AuthenticatedClient,
         user: &User,
+        keepalive_grace: Duration,
     ) -> Result<(Self, WebSocket)> {
-        let ws = WebSocket::connect().await?;
+        let mut ws = WebSocket::connect().await?;
+        ws.set_keepalive_grace(keepalive_grace);
         eprintln!("websocket: {:?}", ws.session_id());
 
-        let mut ids = Vec::new();
-        let mut push = |res: CreateSubscriptionResponse| -> Result<()> {
-            ids.push(
-                res.into_subscription()
-                    .context("missing subscription info")?
-                    .id,
-            );
-            Ok(())
+        let mut subscriptions = Self {
+            ids: Vec::new(),
+            session_id: ws.session_id().clone(),
+            subscribed_at: Utc::now(),
+            keepalive_grace,
         };
+        subscriptions.create(client, user).await?;
 
-        let res = client
-            .send(&CreateSubscriptionRequest::new::<ChatMessage>(
-                &ChatMessageCondition {
-                    broadcaster_user_id: user.id.clone(),
-                    user_id: user.id.clone(),
-                },
-                TransportRequest::WebSocket {
-                    session_id: ws.session_id().clone(),
-                },
-            )?)
-            .await
-            .context("create subscription")?;
-        // eprintln!("{res:#?}");
-        push(res)?;
-
-        let res = client
-            .send(&CreateSubscriptionRequest::new::<ChatNotification>(
-                &ChatNotificationCondition {
-                    broadcaster_user_id: user.id.clone(),
-                    user_id: user.id.clone(),
-                },
-                TransportRequest::WebSocket {
-                    session_id: ws.session_id().clone(),
-                },
-            )?)
-            .await
-            .context("create subscription")?;
-        // eprintln!("{res:#?}");
-        push(res)?;
-
-        let res = client
-            .send(&CreateSubscriptionRequest::new::<Follow>(
-                &FollowCondition {
-                    broadcaster_user_id: user.id.clone(),
-                    moderator_user_id: user.id.clone(),
-                },
-                TransportRequest::WebSocket {
-                    session_id: ws.session_id().clone(),
-                },
-            )?)
-            .await
-            .context("create subscription")?;
-        // eprintln!("{res:#?}");
-        push(res)?;
-
-        let res = client
-            .send(&CreateSubscriptionRequest::new::<StreamOnline>(
-                &StreamOnlineCondition {
-                    broadcaster_user_id: user.id.clone(),
-                },
-                TransportRequest::WebSocket {
-                    session_id: ws.session_id().clone(),
-                },
-            )?)
-            .await
-            .context("create subscription")?;
-        // eprintln!("{res:#?}");
-        push(res)?;
-
-        let res = client
-            .send(&CreateSubscriptionRequest::new::<StreamOffline>(
-                &StreamOfflineCondition {
-                    broadcaster_user_id: user.id.clone(),
-                },
-                TransportRequest::WebSocket {
-                    session_id: ws.session_id().clone(),
+        Ok((subscriptions, ws))
+    }
+
+    /// Connect a new web socket after the connection was lost and move the subscriptions to it
+    pub async fn reconnect(
+        &mut self,
+        client: &mut AuthenticatedClient,
+        user: &User,
+    ) -> Result<WebSocket> {
+        let mut attempt = 0;
+        let mut ws = loop {
+            match WebSocket::connect().await {
+                Ok(ws) => break ws,
+                Err(err) if attempt + 1 >= Self::MAX_RECONNECT_ATTEMPTS => {
+                    return Err(err.context("reconnect web socket"));
+                }
+                Err(_) => {
+                    tokio::time::sleep(Self::RECONNECT_BACKOFF * 2u32.pow(attempt)).await;
+                    attempt += 1;
+                }
+            }
+        };
+        ws.set_keepalive_grace(self.keepalive_grace);
+        self.session_id = ws.session_id().clone();
+        self.resubscribe(client, user).await?;
+        Ok(ws)
+    }
+
+    async fn create(&mut self, client: &mut AuthenticatedClient, user: &User) -> Result<()> {
+        let session_id = &self.session_id;
+        self.subscribed_at = Utc::now();
+
+        let ids = vec![
+            create_subscription::<ChatMessage>(client, &user.into(), session_id).await?,
+            create_subscription::<ChatNotification>(client, &user.into(), session_id).await?,
+            create_subscription::<Follow>(client, &user.into(), session_id).await?,
+            create_subscription::<StreamOnline>(client, &user.into(), session_id).await?,
+            create_subscription::<StreamOffline>(client, &user.into(), session_id).await?,
+            create_subscription::<Cheer>(client, &user.into(), session_id).await?,
+            create_subscription::<ChannelPointsRedemptionAdd>(client, &user.into(), session_id)
+                .await?,
+            create_subscription::<Raid>(
+                client,
+                &RaidCondition {
+                    to_broadcaster_user_id: Some(user.id.clone()),
+                    ..Default::default()
                 },
-            )?)
-            .await
-            .context("create subscription")?;
-        // eprintln!("{res:#?}");
-        push(res)?;
+                session_id,
+            )
+            .await?,
+        ];
 
         eprintln!("subscribed {} ids", ids.len());
+        self.ids = ids;
+
+        Ok(())
+    }
+
+    /// Recreate the subscriptions if they were revoked because the access token was refreshed
+    pub async fn handle_revocation(
+        &mut self,
+        client: &mut AuthenticatedClient,
+        user: &User,
+        revocation: RevocationMessage,
+    ) -> Result<()> {
+        if self.is_revoked_by_refresh(&revocation.subscription, client.token_refreshed_at())? {
+            self.resubscribe(client, user).await?;
+        }
+        Ok(())
+    }
+
+    /// Whether the subscription was revoked by a token refresh, other revocations are errors
+    ///
+    /// Revocations of subscriptions that were already replaced are ignored.
+    fn is_revoked_by_refresh(
+        &self,
+        subscription: &SubscriptionInfo,
+        token_refreshed_at: Option<DateTime<Utc>>,
+    ) -> Result<bool> {
+        let id = subscription.id.access_secret_value();
+        if !self.ids.iter().any(|it| it.access_secret_value() == id) {
+            return Ok(false);
+        }
 
-        Ok((Self { ids }, ws))
+        let refreshed =
+            token_refreshed_at.is_some_and(|refreshed_at| refreshed_at > self.subscribed_at);
+        anyhow::ensure!(
+            matches!(
+                subscription.status,
+                SubscriptionStatus::AuthorizationRevoked
+            ) && refreshed,
+            "subscription revoked: {:?} {:?}",
+            subscription.type_,
+            subscription.status,
+        );
+        Ok(true)
+    }
+
+    /// Delete and recreate all subscriptions, e.g. after the access token was replaced
+    pub async fn resubscribe(
+        &mut self,
+        client: &mut AuthenticatedClient,
+        user: &User,
+    ) -> Result<()> {
+        for id in self.ids.drain(..) {
+            // the other subscriptions may be revoked as well
+            if let Err(err) = client.send(&DeleteSubscriptionRequest { id }).await {
+                eprintln!("failed to delete subscription: {err}");
+            }
+        }
+        self.create(client, user).await.context("resubscribe")
     }
 
     pub async fn unsubscribe(self, client: &mut AuthenticatedClient) -> Result<()> {
@@ -130,3 +228,87 @@ impl Subscriptions {
         Ok(())
     }
 }
+
+/// Create a subscription for the websocket session, returns its id
+async fn create_subscription<T: Subscription>(
+    client: &mut AuthenticatedClient,
+    condition: &T::Condition,
+    session_id: &Secret,
+) -> Result<Secret> {
+    let res = client
+        .send(&CreateSubscriptionRequest::new::<T>(
+            condition,
+            TransportRequest::WebSocket {
+                session_id: session_id.clone(),
+            },
+        )?)
+        .await
+        .context("create subscription")?;
+    Ok(res
+        .into_subscription()
+        .context("missing subscription info")?
+        .id)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeDelta;
+
+    use super::*;
+
+    fn revoked(id: &str, status: &str) -> SubscriptionInfo {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "status": status,
+            "type": "channel.follow",
+            "version": "2",
+            "cost": 0,
+            "condition": {},
+            "transport": { "method": "websocket", "session_id": "session" },
+            "created_at": "2025-01-31T19:00:00Z",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn revocation_after_refresh_resubscribes() {
+        let mut subscriptions = Subscriptions::test();
+        subscriptions.ids.push(Secret::new("follow"));
+        let refreshed_at = Some(subscriptions.subscribed_at + TimeDelta::seconds(1));
+
+        let subscription = revoked("follow", "authorization_revoked");
+        assert!(
+            subscriptions
+                .is_revoked_by_refresh(&subscription, refreshed_at)
+                .unwrap()
+        );
+
+        // without a refresh since subscribing, the authorization was revoked by the user
+        assert!(
+            subscriptions
+                .is_revoked_by_refresh(&subscription, None)
+                .is_err()
+        );
+        let before = Some(subscriptions.subscribed_at - TimeDelta::seconds(1));
+        assert!(
+            subscriptions
+                .is_revoked_by_refresh(&subscription, before)
+                .is_err()
+        );
+
+        let removed = revoked("follow", "user_removed");
+        assert!(
+            subscriptions
+                .is_revoked_by_refresh(&removed, refreshed_at)
+                .is_err()
+        );
+
+        // the revocation of a replaced subscription is ignored
+        let replaced = revoked("old follow", "authorization_revoked");
+        assert!(
+            !subscriptions
+                .is_revoked_by_refresh(&replaced, refreshed_at)
+                .unwrap()
+        );
+    }
+}